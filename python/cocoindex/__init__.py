@@ -2,14 +2,14 @@
 Cocoindex is a framework for building and running indexing pipelines.
 """
 
-from . import functions, sources, targets, cli, utils
+from . import functions, sources, targets, cli, utils, query
 
 from . import targets as storages  # Deprecated: Use targets instead
 
 from .auth_registry import AuthEntryReference, add_auth_entry, ref_auth_entry
 from .flow import FlowBuilder, DataScope, DataSlice, Flow, transform_flow
 from .flow import flow_def
-from .flow import EvaluateAndDumpOptions, GeneratedField
+from .flow import DryRunOptions, EvaluateAndDumpOptions, GeneratedField
 from .flow import FlowLiveUpdater, FlowLiveUpdaterOptions
 from .flow import update_all_flows_async, setup_all_flows, drop_all_flows
 from .lib import init, start_server, stop, main_fn
@@ -28,6 +28,7 @@ __all__ = [
     "storages",
     "cli",
     "utils",
+    "query",
     # Auth registry
     "AuthEntryReference",
     "add_auth_entry",
@@ -39,6 +40,7 @@ __all__ = [
     "Flow",
     "transform_flow",
     "flow_def",
+    "DryRunOptions",
     "EvaluateAndDumpOptions",
     "GeneratedField",
     "FlowLiveUpdater",
@@ -401,6 +401,16 @@ class _SourceRefreshOptions:
     refresh_interval: datetime.timedelta | None = None
 
 
+@dataclass
+class _ExecutionOptions:
+    """
+    Options for bounding the concurrency of processing rows from a source.
+    """
+
+    max_inflight_rows: int | None = None
+    max_inflight_bytes: int | None = None
+
+
 class FlowBuilder:
     """
     A flow builder is used to build a flow.
@@ -424,6 +434,8 @@ class FlowBuilder:
         *,
         name: str | None = None,
         refresh_interval: datetime.timedelta | None = None,
+        max_inflight_rows: int | None = None,
+        max_inflight_bytes: int | None = None,
     ) -> DataSlice[T]:
         """
         Import a source to the flow.
@@ -442,6 +454,12 @@ class FlowBuilder:
                 dump_engine_object(
                     _SourceRefreshOptions(refresh_interval=refresh_interval)
                 ),
+                dump_engine_object(
+                    _ExecutionOptions(
+                        max_inflight_rows=max_inflight_rows,
+                        max_inflight_bytes=max_inflight_bytes,
+                    )
+                ),
             ),
             name,
         )
@@ -461,6 +479,12 @@ class FlowLiveUpdaterOptions:
 
     live_mode: bool = True
     print_stats: bool = False
+    # Limits on rows / bytes being processed at the same time, shared by all sources of the flow.
+    max_inflight_rows: int | None = None
+    max_inflight_bytes: int | None = None
+    # Maximum number of LLM tokens (input and output) consumed by the flow during the update.
+    # The update is aborted once exceeded.
+    llm_token_budget: int | None = None
 
 
 class FlowLiveUpdater:
@@ -546,6 +570,16 @@ class EvaluateAndDumpOptions:
     use_cache: bool = True
 
 
+@dataclass
+class DryRunOptions:
+    """
+    Options for a dry-run update of a flow.
+    """
+
+    # Maximum number of keys to sample for each kind of change on each target.
+    max_sample_keys: int = 10
+
+
 class Flow:
     """
     A flow describes an indexing pipeline.
@@ -638,6 +672,26 @@ class Flow:
             await updater.wait_async()
         return updater.update_stats()
 
+    def dry_run_update(
+        self, options: DryRunOptions | None = None
+    ) -> _engine.DryRunReport:
+        """
+        Run the update of the flow without writing to targets or internal storage.
+        Returns a report of the changes that would be applied to each target.
+        """
+        return execution_context.run(self.dry_run_update_async(options))
+
+    async def dry_run_update_async(
+        self, options: DryRunOptions | None = None
+    ) -> _engine.DryRunReport:
+        """
+        Run the update of the flow without writing to targets or internal storage. The async version.
+        """
+        engine_flow = await self.internal_flow_async()
+        return await engine_flow.dry_run_update(
+            dump_engine_object(options or DryRunOptions())
+        )
+
     def evaluate_and_dump(
         self, options: EvaluateAndDumpOptions
     ) -> _engine.IndexUpdateInfo:
@@ -19,7 +19,10 @@ except ImportError:
 
 
 class ParseJson(op.FunctionSpec):
-    """Parse a text into a JSON object."""
+    """Parse a text in JSON, JSON5, YAML, TOML or CSV into a JSON object."""
+
+    # If provided, the parsed value is converted to this type (e.g. a dataclass), instead of untyped JSON.
+    output_type: type | None = None
 
 
 @dataclasses.dataclass
@@ -31,11 +34,54 @@ class CustomLanguageSpec:
     aliases: list[str] = dataclasses.field(default_factory=list)
 
 
+@dataclasses.dataclass
+class TiktokenTokenizer:
+    """Tokenizer of OpenAI models, by a tiktoken encoding (e.g. `cl100k_base`) or a model name."""
+
+    kind = "Tiktoken"
+
+    encoding: str
+
+
+@dataclasses.dataclass
+class HuggingFaceTokenizer:
+    """Tokenizer loaded from a HuggingFace `tokenizer.json` file."""
+
+    kind = "HuggingFace"
+
+    path: str
+
+
 class SplitRecursively(op.FunctionSpec):
     """Split a document (in string) recursively."""
 
     custom_languages: list[CustomLanguageSpec] = dataclasses.field(default_factory=list)
 
+    # If provided, chunk sizes are measured in tokens of this tokenizer, instead of bytes.
+    tokenizer: TiktokenTokenizer | HuggingFaceTokenizer | None = None
+
+
+class ExtractCodeSymbols(op.FunctionSpec):
+    """Extract definitions of symbols (e.g. functions, classes) from source code."""
+
+
+class ExtractPdfText(op.FunctionSpec):
+    """Extract text of each page and metadata from a PDF document."""
+
+
+class HtmlToText(op.FunctionSpec):
+    """Convert HTML to clean text, with boilerplate (e.g. navigation, scripts) removed."""
+
+    # If true, also output paragraphs of the text, with headings they're under.
+    include_paragraphs: bool = False
+
+
+class MarkdownToText(op.FunctionSpec):
+    """Convert Markdown to clean text, with markup removed."""
+
+    # If true, also output paragraphs of the text, with headings they're under.
+    include_paragraphs: bool = False
+
 
 class EmbedText(op.FunctionSpec):
     """Embed a text into a vector space."""
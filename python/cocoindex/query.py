@@ -0,0 +1,156 @@
+"""
+Query handlers to search indexes built by flows.
+"""
+
+from dataclasses import dataclass
+from threading import Lock
+from typing import Any, Callable, cast
+
+from . import _engine  # type: ignore
+from . import flow as fl
+from .convert import dump_engine_object
+from .index import VectorSimilarityMetric
+from .runtime import execution_context
+
+
+@dataclass
+class QueryResult:
+    """
+    A single result of a query.
+    """
+
+    data: dict[str, Any]
+    score: float
+
+
+@dataclass
+class SimpleSemanticsQueryInfo:
+    """
+    Additional information about a query.
+    """
+
+    vector_field_name: str
+    similarity_metric: VectorSimilarityMetric
+    query_vector: list[float]
+
+
+_handlers_lock = Lock()
+_handlers: dict[tuple[str, str], "SimpleSemanticsQueryHandler"] = {}
+
+
+class SimpleSemanticsQueryHandler:
+    """
+    A query handler that computes the query vector with a transform flow,
+    and performs a vector search on an export target.
+    """
+
+    _name: str
+    _lazy_query_handler: Callable[[], _engine.SimpleSemanticsQueryHandler]
+
+    def __init__(
+        self,
+        name: str,
+        flow: fl.Flow,
+        target_name: str,
+        query_transform_flow: fl.TransformFlow[Any],
+        default_similarity_metric: VectorSimilarityMetric = (
+            VectorSimilarityMetric.COSINE_SIMILARITY
+        ),
+    ) -> None:
+        self._name = name
+        engine_handler = None
+        lock = Lock()
+
+        def _lazy_query_handler() -> _engine.SimpleSemanticsQueryHandler:
+            nonlocal engine_handler, lock
+            if engine_handler is None:
+                with lock:
+                    if engine_handler is None:
+                        engine_handler = _engine.SimpleSemanticsQueryHandler(
+                            name,
+                            flow.internal_flow(),
+                            target_name,
+                            query_transform_flow.internal_flow(),
+                            dump_engine_object(default_similarity_metric),
+                        )
+            return engine_handler
+
+        self._lazy_query_handler = _lazy_query_handler
+
+        with _handlers_lock:
+            if (flow.name, name) in _handlers:
+                raise KeyError(
+                    f"Query handler with name {name} already exists in flow {flow.name}"
+                )
+            _handlers[(flow.name, name)] = self
+
+    @property
+    def name(self) -> str:
+        """
+        Get the name of the query handler.
+        """
+        return self._name
+
+    def internal_handler(self) -> _engine.SimpleSemanticsQueryHandler:
+        """
+        Get the internal query handler.
+        """
+        return self._lazy_query_handler()
+
+    def search(
+        self,
+        query: str,
+        limit: int,
+        vector_field_name: str | None = None,
+        similarity_metric: VectorSimilarityMetric | None = None,
+    ) -> tuple[list[QueryResult], SimpleSemanticsQueryInfo]:
+        """
+        Search the index with the given query.
+        """
+        return cast(
+            tuple[list[QueryResult], SimpleSemanticsQueryInfo],
+            execution_context.run(
+                self.search_async(query, limit, vector_field_name, similarity_metric)
+            ),
+        )
+
+    async def search_async(
+        self,
+        query: str,
+        limit: int,
+        vector_field_name: str | None = None,
+        similarity_metric: VectorSimilarityMetric | None = None,
+    ) -> tuple[list[QueryResult], SimpleSemanticsQueryInfo]:
+        """
+        Search the index with the given query.
+        """
+        response = await self.internal_handler().search_async(
+            query,
+            limit,
+            vector_field_name,
+            dump_engine_object(similarity_metric),
+        )
+        fields = [field["name"] for field in response["results"]["fields"]]
+        results = [
+            QueryResult(data=dict(zip(fields, result["data"])), score=result["score"])
+            for result in response["results"]["results"]
+        ]
+        info = response["info"]
+        return (
+            results,
+            SimpleSemanticsQueryInfo(
+                vector_field_name=info["vector_field_name"],
+                similarity_metric=VectorSimilarityMetric(info["similarity_metric"]),
+                query_vector=info["query_vector"],
+            ),
+        )
+
+
+def ensure_all_handlers_built() -> None:
+    """
+    Ensure all query handlers are built and registered to their flows.
+    """
+    with _handlers_lock:
+        handlers = list(_handlers.values())
+    for handler in handlers:
+        handler.internal_handler()
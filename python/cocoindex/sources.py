@@ -1,6 +1,11 @@
 """All builtin sources."""
 
+from dataclasses import dataclass
+from enum import Enum
+
 from . import op
+from .auth_registry import AuthEntryReference
+from .setting import DatabaseConnectionSpec
 import datetime
 
 
@@ -20,6 +25,59 @@ class LocalFile(op.SourceSpec):
     # See https://docs.rs/globset/latest/globset/index.html#syntax for the syntax of the patterns.
     excluded_patterns: list[str] | None = None
 
+    # If true, watch the file system for changes in live mode.
+    watch_changes: bool = False
+
+
+class LocalArchive(op.SourceSpec):
+    """Import members of local archive files (.zip, .tar, .tar.gz), as individual files."""
+
+    _op_category = op.OpCategory.SOURCE
+
+    # An archive file, or a directory to find archive files under.
+    path: str
+    binary: bool = False
+
+    # If provided, only members matching these patterns will be included.
+    # See https://docs.rs/globset/latest/globset/index.html#syntax for the syntax of the patterns.
+    included_patterns: list[str] | None = None
+
+    # If provided, members matching these patterns will be excluded.
+    # See https://docs.rs/globset/latest/globset/index.html#syntax for the syntax of the patterns.
+    excluded_patterns: list[str] | None = None
+
+
+class StructuredFileFormat(Enum):
+    """Format of files for the StructuredFile source."""
+
+    JSONL = "Jsonl"
+    CSV = "Csv"
+    PARQUET = "Parquet"
+
+
+class StructuredFile(op.SourceSpec):
+    """Import records of local JSONL, CSV or Parquet files, one row per record."""
+
+    _op_category = op.OpCategory.SOURCE
+
+    # A file, or a directory to find files under.
+    path: str
+    format: StructuredFileFormat
+
+    # The column used as the key. If not provided, rows are keyed by the filename and the row number.
+    key_column: str | None = None
+
+    # Type of records, e.g. a dataclass. Inferred from the first file if not provided.
+    record_type: type | None = None
+
+    # If provided, only files matching these patterns will be included. Defaults to extensions of the format.
+    # See https://docs.rs/globset/latest/globset/index.html#syntax for the syntax of the patterns.
+    included_patterns: list[str] | None = None
+
+    # If provided, files matching these patterns will be excluded.
+    # See https://docs.rs/globset/latest/globset/index.html#syntax for the syntax of the patterns.
+    excluded_patterns: list[str] | None = None
+
 
 class GoogleDrive(op.SourceSpec):
     """Import data from Google Drive."""
@@ -29,9 +87,20 @@ class GoogleDrive(op.SourceSpec):
     service_account_credential_path: str
     root_folder_ids: list[str]
     binary: bool = False
+    # ID of the Shared Drive containing the root folders. Files are in My Drive if not specified.
+    drive_id: str | None = None
     recent_changes_poll_interval: datetime.timedelta | None = None
 
 
+@dataclass
+class AmazonS3Credential:
+    """Static credentials for the AmazonS3 source."""
+
+    access_key_id: str
+    secret_access_key: str
+    session_token: str | None = None
+
+
 class AmazonS3(op.SourceSpec):
     """Import data from an Amazon S3 bucket. Supports optional prefix and file filtering by glob patterns."""
 
@@ -43,3 +112,183 @@ class AmazonS3(op.SourceSpec):
     included_patterns: list[str] | None = None
     excluded_patterns: list[str] | None = None
     sqs_queue_url: str | None = None
+
+    # Endpoint of an S3-compatible store (e.g. MinIO, Ceph), instead of Amazon S3.
+    endpoint_url: str | None = None
+    region: str | None = None
+    # Address buckets by path instead of subdomain, which most S3-compatible stores require.
+    force_path_style: bool = False
+    # If not provided, credentials are resolved from the default AWS config.
+    credential: AuthEntryReference[AmazonS3Credential] | None = None
+
+
+@dataclass
+class AzureBlobCredential:
+    """Credential for the AzureBlob source. Exactly one of the fields should be provided."""
+
+    account_key: str | None = None
+    sas_token: str | None = None
+
+
+class AzureBlob(op.SourceSpec):
+    """Import data from an Azure Blob Storage container. Supports optional prefix and file filtering by glob patterns."""
+
+    _op_category = op.OpCategory.SOURCE
+
+    account_name: str
+    container_name: str
+    prefix: str | None = None
+    binary: bool = False
+    included_patterns: list[str] | None = None
+    excluded_patterns: list[str] | None = None
+
+    # Endpoint of the Blob service, e.g. `http://127.0.0.1:10000/devstoreaccount1` for Azurite.
+    endpoint_url: str | None = None
+    # If not provided, the container is accessed anonymously.
+    credential: AuthEntryReference[AzureBlobCredential] | None = None
+
+    # If provided, receive Event Grid events of blob changes from this queue in live mode.
+    queue_name: str | None = None
+    # Endpoint of the Queue service, e.g. `http://127.0.0.1:10001/devstoreaccount1` for Azurite.
+    queue_endpoint_url: str | None = None
+
+
+@dataclass
+class PostgresNotification:
+    """Notification spec for the Postgres source, to receive row changes by LISTEN/NOTIFY."""
+
+    # Name of the channel to listen on. Derived from the table name if not specified.
+    channel_name: str | None = None
+
+
+class Postgres(op.SourceSpec):
+    """Import rows from a Postgres table."""
+
+    _op_category = op.OpCategory.SOURCE
+
+    table_name: str
+    database: AuthEntryReference[DatabaseConnectionSpec] | None = None
+
+    # Value columns to include. All non-primary-key columns are included if not specified.
+    included_columns: list[str] | None = None
+
+    # An integer or timestamp column that increases whenever a row changes, e.g. `updated_at`.
+    ordinal_column: str | None = None
+
+    # If provided, receive row changes by LISTEN/NOTIFY in live mode.
+    notification: PostgresNotification | None = None
+
+
+class Sqlite(op.SourceSpec):
+    """Import rows from a table or a query of a SQLite database."""
+
+    _op_category = op.OpCategory.SOURCE
+
+    # Path to the database file.
+    path: str
+    # Column used as the key of rows.
+    key_column: str
+
+    # Exactly one of `table_name` and `query` should be provided.
+    table_name: str | None = None
+    query: str | None = None
+
+    # Value columns to include. All non-key columns are included if not specified.
+    included_columns: list[str] | None = None
+
+    # An integer, date or datetime column that increases whenever a row changes, e.g. `updated_at`.
+    ordinal_column: str | None = None
+
+    # If provided, poll `PRAGMA data_version` with this interval in live mode, and re-list rows once the database changed.
+    change_poll_interval: datetime.timedelta | None = None
+
+
+class GitRepository(op.SourceSpec):
+    """Import files from a git repository at a specific rev."""
+
+    _op_category = op.OpCategory.SOURCE
+
+    # Path to the repository, either bare or with a working tree.
+    path: str
+    # The rev to import files from, e.g. a branch, a tag or `HEAD`. Defaults to `HEAD`.
+    rev: str | None = None
+    binary: bool = False
+
+    # If provided, only files matching these patterns will be included.
+    # See https://docs.rs/globset/latest/globset/index.html#syntax for the syntax of the patterns.
+    included_patterns: list[str] | None = None
+
+    # If provided, files matching these patterns will be excluded.
+    # See https://docs.rs/globset/latest/globset/index.html#syntax for the syntax of the patterns.
+    excluded_patterns: list[str] | None = None
+
+    # If provided, poll the rev in live mode with this interval, and process files changed since the last tip.
+    rev_poll_interval: datetime.timedelta | None = None
+
+
+class WebCrawler(op.SourceSpec):
+    """Import pages of websites, by crawling from seed URLs."""
+
+    _op_category = op.OpCategory.SOURCE
+
+    start_urls: list[str]
+
+    # Domains to follow links within. Defaults to domains of `start_urls`. Subdomains are included.
+    allowed_domains: list[str] | None = None
+    # If provided, only follow links with paths starting with one of these prefixes.
+    allowed_path_prefixes: list[str] | None = None
+    # Maximum number of links to follow from the start URLs. Unlimited if not provided.
+    max_depth: int | None = None
+
+    binary: bool = False
+    respect_robots_txt: bool = True
+
+    # If provided, requests are sent with at least this interval in between.
+    min_request_interval: datetime.timedelta | None = None
+    user_agent: str | None = None
+
+
+class KafkaOrdinalSource(Enum):
+    """What to use as the ordinal of rows from the Kafka source."""
+
+    OFFSET = "Offset"
+    TIMESTAMP = "Timestamp"
+
+
+class Kafka(op.SourceSpec):
+    """Import the latest message of each key from a Kafka topic."""
+
+    _op_category = op.OpCategory.SOURCE
+
+    bootstrap_servers: str
+    topic: str
+    # Consumer group to commit offsets for, in live mode.
+    group_id: str
+
+    ordinal_source: KafkaOrdinalSource = KafkaOrdinalSource.OFFSET
+    binary_key: bool = False
+    binary: bool = False
+
+    # Additional librdkafka configuration properties, e.g. for authentication.
+    # See https://github.com/confluentinc/librdkafka/blob/master/CONFIGURATION.md
+    consumer_options: dict[str, str] | None = None
+
+
+class MailboxFormat(Enum):
+    """Format of the mailbox for the Mailbox source."""
+
+    MAILDIR = "Maildir"
+    MBOX = "Mbox"
+
+
+class Mailbox(op.SourceSpec):
+    """Import email messages from Maildir directories or mbox files."""
+
+    _op_category = op.OpCategory.SOURCE
+
+    # A Maildir directory, or an mbox file or a directory of mbox files.
+    path: str
+    format: MailboxFormat
+
+    included_patterns: list[str] | None = None
+    excluded_patterns: list[str] | None = None
@@ -268,12 +268,32 @@ impl fmt::Display for SourceRefreshOptions {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ExecutionOptions {
+    /// Maximum number of rows being processed concurrently.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_inflight_rows: Option<usize>,
+
+    /// Maximum total size (in bytes) of source data of rows being processed concurrently.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_inflight_bytes: Option<usize>,
+}
+
+impl ExecutionOptions {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_inflight_rows.is_none() && self.max_inflight_bytes.is_none()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportOpSpec {
     pub source: OpSpec,
 
     #[serde(default)]
     pub refresh_options: SourceRefreshOptions,
+
+    #[serde(default, skip_serializing_if = "ExecutionOptions::is_unlimited")]
+    pub execution_options: ExecutionOptions,
 }
 
 impl SpecFormatter for ImportOpSpec {
@@ -860,6 +860,76 @@ where
     }
 }
 
+impl FieldValues {
+    /// Estimated size of the values in memory, in bytes.
+    pub fn estimated_byte_size(&self) -> usize {
+        self.fields.iter().map(Value::estimated_byte_size).sum()
+    }
+}
+
+impl Value {
+    /// Estimated size of the value in memory, in bytes.
+    pub fn estimated_byte_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + match self {
+                Value::Null => 0,
+                Value::Basic(v) => v.estimated_heap_byte_size(),
+                Value::Struct(v) => v.estimated_byte_size(),
+                Value::UTable(v) | Value::LTable(v) => {
+                    v.iter().map(|v| v.estimated_byte_size()).sum()
+                }
+                Value::KTable(v) => v
+                    .iter()
+                    .map(|(k, v)| k.estimated_byte_size() + v.estimated_byte_size())
+                    .sum(),
+            }
+    }
+}
+
+impl KeyValue {
+    /// Estimated size of the key in memory, in bytes.
+    pub fn estimated_byte_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + match self {
+                KeyValue::Bytes(v) => v.len(),
+                KeyValue::Str(v) => v.len(),
+                KeyValue::Struct(v) => v.iter().map(KeyValue::estimated_byte_size).sum(),
+                _ => 0,
+            }
+    }
+}
+
+impl BasicValue {
+    fn estimated_heap_byte_size(&self) -> usize {
+        match self {
+            BasicValue::Bytes(v) => v.len(),
+            BasicValue::Str(v) => v.len(),
+            BasicValue::Json(v) => estimated_json_byte_size(v),
+            BasicValue::Vector(v) => v
+                .iter()
+                .map(|v| std::mem::size_of::<BasicValue>() + v.estimated_heap_byte_size())
+                .sum(),
+            BasicValue::UnionVariant { value, .. } => {
+                std::mem::size_of::<BasicValue>() + value.estimated_heap_byte_size()
+            }
+            _ => 0,
+        }
+    }
+}
+
+fn estimated_json_byte_size(value: &serde_json::Value) -> usize {
+    std::mem::size_of::<serde_json::Value>()
+        + match value {
+            serde_json::Value::String(v) => v.len(),
+            serde_json::Value::Array(v) => v.iter().map(estimated_json_byte_size).sum(),
+            serde_json::Value::Object(v) => v
+                .iter()
+                .map(|(k, v)| k.len() + estimated_json_byte_size(v))
+                .sum(),
+            _ => 0,
+        }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ScopeValue(pub FieldValues);
 
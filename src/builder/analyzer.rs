@@ -7,6 +7,8 @@ use crate::lib_context::get_auth_registry;
 use crate::utils::fingerprint::Fingerprinter;
 use crate::{
     base::{schema::*, spec::*},
+    execution::stats,
+    llm, metrics,
     ops::interface::*,
 };
 use futures::future::{BoxFuture, try_join3};
@@ -662,6 +664,7 @@ impl AnalyzerContext {
         &self,
         op_scope: &Arc<OpScope>,
         import_op: NamedSpec<ImportOpSpec>,
+        setup_declarations: &mut Vec<OpSpec>,
     ) -> Result<impl Future<Output = Result<AnalyzedImportOp>> + Send + use<>> {
         let source_factory = match get_executor_factory(&import_op.spec.source.kind)? {
             ExecutorFactory::Source(source_executor) => source_executor,
@@ -672,12 +675,13 @@ impl AnalyzerContext {
                 ));
             }
         };
-        let (output_type, executor) = source_factory
+        let (output_type, source_declarations, executor) = source_factory
             .build(
                 serde_json::Value::Object(import_op.spec.source.spec),
                 self.flow_ctx.clone(),
             )
             .await?;
+        setup_declarations.extend(source_declarations);
 
         let op_name = import_op.name.clone();
         let primary_key_type = output_type
@@ -697,6 +701,7 @@ impl AnalyzerContext {
                 primary_key_type,
                 name: op_name,
                 refresh_options: import_op.spec.refresh_options,
+                execution_options: import_op.spec.execution_options,
             })
         };
         Ok(result_fut)
@@ -724,8 +729,12 @@ impl AnalyzerContext {
                             .iter()
                             .map(|field| field.analyzed_value.clone())
                             .collect();
-                        let (output_enriched_type, executor) = fn_executor
-                            .build(spec, input_field_schemas, self.flow_ctx.clone())
+                        let llm_usage_stats = Arc::new(stats::LlmUsageStats::default());
+                        let (output_enriched_type, executor) = llm::OP_LLM_USAGE_STATS
+                            .scope(
+                                llm_usage_stats.clone(),
+                                fn_executor.build(spec, input_field_schemas, self.flow_ctx.clone()),
+                            )
                             .await?;
                         let logic_fingerprinter = Fingerprinter::default()
                             .with(&op.op)?
@@ -734,11 +743,23 @@ impl AnalyzerContext {
                         let output = op_scope
                             .add_op_output(reactive_op.name.clone(), output_enriched_type)?;
                         let op_name = reactive_op.name.clone();
+                        let duration_metric = metrics::TRANSFORM_OP_DURATION.with_label_values(&[
+                            self.flow_ctx.flow_instance_name.as_str(),
+                            op_name.as_str(),
+                            op.op.kind.as_str(),
+                        ]);
+                        let cache_lookup_counters = metrics::CacheLookupCounters::new(
+                            self.flow_ctx.flow_instance_name.as_str(),
+                            op_name.as_str(),
+                        );
                         async move {
                             trace!("Start building executor for transform op `{op_name}`");
-                            let executor = executor.await.with_context(|| {
-                                format!("Failed to build executor for transform op: {op_name}")
-                            })?;
+                            let executor = llm::OP_LLM_USAGE_STATS
+                                .scope(llm_usage_stats.clone(), executor)
+                                .await
+                                .with_context(|| {
+                                    format!("Failed to build executor for transform op: {op_name}")
+                                })?;
                             let enable_cache = executor.enable_cache();
                             let behavior_version = executor.behavior_version();
                             trace!("Finished building executor for transform op `{op_name}`, enable cache: {enable_cache}, behavior version: {behavior_version:?}");
@@ -762,6 +783,9 @@ impl AnalyzerContext {
                                 function_exec_info,
                                 executor,
                                 output,
+                                duration_metric,
+                                cache_lookup_counters,
+                                llm_usage_stats,
                             }))
                         }
                         .boxed()
@@ -1014,10 +1038,11 @@ pub async fn analyze_flow(
     let root_data_scope = Arc::new(Mutex::new(DataScopeBuilder::new()));
     let root_op_scope = OpScope::new(ROOT_SCOPE_NAME.to_string(), None, root_data_scope);
     let mut import_ops_futs = Vec::with_capacity(flow_inst.import_ops.len());
+    let mut source_declarations = vec![];
     for import_op in flow_inst.import_ops.iter() {
         import_ops_futs.push(
             analyzer_ctx
-                .analyze_import_op(&root_op_scope, import_op.clone())
+                .analyze_import_op(&root_op_scope, import_op.clone(), &mut source_declarations)
                 .await?,
         );
     }
@@ -1038,7 +1063,11 @@ pub async fn analyze_flow(
             .export_op_ids
             .push(idx);
     }
-    for declaration in flow_inst.declarations.iter() {
+    for declaration in flow_inst
+        .declarations
+        .iter()
+        .chain(source_declarations.iter())
+    {
         target_op_group
             .entry(declaration.kind.clone())
             .or_default()
@@ -288,7 +288,8 @@ impl FlowBuilder {
         OpScopeRef(self.root_op_scope.clone())
     }
 
-    #[pyo3(signature = (kind, op_spec, target_scope, name, refresh_options=None))]
+    #[pyo3(signature = (kind, op_spec, target_scope, name, refresh_options=None, execution_options=None))]
+    #[allow(clippy::too_many_arguments)]
     pub fn add_source(
         &mut self,
         py: Python<'_>,
@@ -297,6 +298,7 @@ impl FlowBuilder {
         target_scope: Option<OpScopeRef>,
         name: String,
         refresh_options: Option<py::Pythonized<spec::SourceRefreshOptions>>,
+        execution_options: Option<py::Pythonized<spec::ExecutionOptions>>,
     ) -> PyResult<DataSlice> {
         if let Some(target_scope) = target_scope {
             if *target_scope != self.root_op_scope {
@@ -313,6 +315,9 @@ impl FlowBuilder {
                     spec: op_spec.into_inner(),
                 },
                 refresh_options: refresh_options.map(|o| o.into_inner()).unwrap_or_default(),
+                execution_options: execution_options
+                    .map(|o| o.into_inner())
+                    .unwrap_or_default(),
             },
         };
         let analyzer_ctx = AnalyzerContext {
@@ -320,9 +325,12 @@ impl FlowBuilder {
         };
         let analyzed = py
             .allow_threads(|| {
-                get_runtime().block_on(
-                    analyzer_ctx.analyze_import_op(&self.root_op_scope, import_op.clone()),
-                )
+                // Setup declarations are collected when the whole flow is analyzed.
+                get_runtime().block_on(analyzer_ctx.analyze_import_op(
+                    &self.root_op_scope,
+                    import_op.clone(),
+                    &mut vec![],
+                ))
             })
             .into_py_result()?;
         std::mem::drop(analyzed);
@@ -1,5 +1,7 @@
 use crate::prelude::*;
 
+use crate::execution::stats;
+use crate::metrics;
 use crate::ops::interface::*;
 use crate::utils::fingerprint::{Fingerprint, Fingerprinter};
 
@@ -56,6 +58,7 @@ pub struct AnalyzedImportOp {
     pub output: AnalyzedOpOutput,
     pub primary_key_type: schema::ValueType,
     pub refresh_options: spec::SourceRefreshOptions,
+    pub execution_options: spec::ExecutionOptions,
 }
 
 pub struct AnalyzedFunctionExecInfo {
@@ -74,6 +77,12 @@ pub struct AnalyzedTransformOp {
     pub function_exec_info: AnalyzedFunctionExecInfo,
     pub executor: Box<dyn SimpleFunctionExecutor>,
     pub output: AnalyzedOpOutput,
+    /// Records the time spent in evaluating the op.
+    pub duration_metric: prometheus::Histogram,
+    /// Records hits and misses of the evaluation cache for the op.
+    pub cache_lookup_counters: metrics::CacheLookupCounters,
+    /// LLM usage of the op, since the flow is analyzed.
+    pub llm_usage_stats: Arc<stats::LlmUsageStats>,
 }
 
 pub struct AnalyzedForEachOp {
@@ -214,7 +214,9 @@ pub async fn commit_source_tracking_info(
             db_setup.table_name
         ),
         WriteAction::Update => format!(
-            "UPDATE {} SET staging_target_keys = $3, processed_source_ordinal = $4, process_logic_fingerprint = $5, process_ordinal = $6, process_time_micros = $7, target_keys = $8 WHERE source_id = $1 AND source_key = $2",
+            "UPDATE {} SET staging_target_keys = $3, processed_source_ordinal = $4, process_logic_fingerprint = $5, process_ordinal = $6, process_time_micros = $7, target_keys = $8, \
+               failure_message = NULL, failure_op_name = NULL, failure_time_micros = NULL, num_failed_attempts = NULL \
+             WHERE source_id = $1 AND source_key = $2",
             db_setup.table_name
         ),
     };
@@ -255,6 +257,8 @@ pub struct TrackedSourceKeyMetadata {
     pub source_key: serde_json::Value,
     pub processed_source_ordinal: Option<i64>,
     pub process_logic_fingerprint: Option<Vec<u8>>,
+    pub failure_time_micros: Option<i64>,
+    pub num_failed_attempts: Option<i32>,
 }
 
 pub struct ListTrackedSourceKeyMetadataState {
@@ -275,7 +279,7 @@ impl ListTrackedSourceKeyMetadataState {
         pool: &'a PgPool,
     ) -> impl Stream<Item = Result<TrackedSourceKeyMetadata, sqlx::Error>> + 'a {
         self.query_str = format!(
-            "SELECT source_key, processed_source_ordinal, process_logic_fingerprint FROM {} WHERE source_id = $1",
+            "SELECT source_key, processed_source_ordinal, process_logic_fingerprint, failure_time_micros, num_failed_attempts FROM {} WHERE source_id = $1",
             db_setup.table_name
         );
         sqlx::query_as(&self.query_str).bind(source_id).fetch(pool)
@@ -315,7 +319,9 @@ pub async fn update_source_tracking_ordinal(
     db_executor: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
 ) -> Result<()> {
     let query_str = format!(
-        "UPDATE {} SET processed_source_ordinal = $3 WHERE source_id = $1 AND source_key = $2",
+        "UPDATE {} SET processed_source_ordinal = $3, \
+           failure_message = NULL, failure_op_name = NULL, failure_time_micros = NULL, num_failed_attempts = NULL \
+         WHERE source_id = $1 AND source_key = $2",
         db_setup.table_name
     );
     sqlx::query(&query_str)
@@ -326,3 +332,81 @@ pub async fn update_source_tracking_ordinal(
         .await?;
     Ok(())
 }
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct SourceProcessingFailureInfo {
+    pub failure_message: String,
+    pub failure_op_name: Option<String>,
+    pub failure_time_micros: i64,
+    pub num_failed_attempts: i32,
+}
+
+/// Record a failed processing attempt for the source row. Returns the total number of failed attempts so far.
+pub async fn record_source_processing_failure(
+    source_id: i32,
+    source_key_json: &serde_json::Value,
+    failure_message: &str,
+    failure_op_name: Option<&str>,
+    failure_time_micros: i64,
+    db_setup: &TrackingTableSetupState,
+    db_executor: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+) -> Result<i32> {
+    let query_str = format!(
+        "INSERT INTO {0} AS t ( \
+           source_id, source_key, max_process_ordinal, staging_target_keys, \
+           failure_message, failure_op_name, failure_time_micros, num_failed_attempts) \
+         VALUES ($1, $2, 0, '[]', $3, $4, $5, 1) \
+         ON CONFLICT (source_id, source_key) DO UPDATE SET \
+           failure_message = $3, failure_op_name = $4, failure_time_micros = $5, \
+           num_failed_attempts = COALESCE(t.num_failed_attempts, 0) + 1 \
+         RETURNING num_failed_attempts",
+        db_setup.table_name
+    );
+    let (num_failed_attempts,): (i32,) = sqlx::query_as(&query_str)
+        .bind(source_id) // $1
+        .bind(source_key_json) // $2
+        .bind(failure_message) // $3
+        .bind(failure_op_name) // $4
+        .bind(failure_time_micros) // $5
+        .fetch_one(db_executor)
+        .await?;
+    Ok(num_failed_attempts)
+}
+
+pub async fn read_source_processing_failure(
+    source_id: i32,
+    source_key_json: &serde_json::Value,
+    db_setup: &TrackingTableSetupState,
+    pool: &PgPool,
+) -> Result<Option<SourceProcessingFailureInfo>> {
+    let query_str = format!(
+        "SELECT failure_message, failure_op_name, failure_time_micros, num_failed_attempts FROM {} WHERE source_id = $1 AND source_key = $2 AND failure_message IS NOT NULL",
+        db_setup.table_name
+    );
+    let failure_info = sqlx::query_as(&query_str)
+        .bind(source_id)
+        .bind(source_key_json)
+        .fetch_optional(pool)
+        .await?;
+    Ok(failure_info)
+}
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct SourceKeyProcessingFailureInfo {
+    pub source_id: i32,
+    pub source_key: serde_json::Value,
+    #[sqlx(flatten)]
+    pub failure: SourceProcessingFailureInfo,
+}
+
+pub async fn list_source_processing_failures(
+    db_setup: &TrackingTableSetupState,
+    pool: &PgPool,
+) -> Result<Vec<SourceKeyProcessingFailureInfo>> {
+    let query_str = format!(
+        "SELECT source_id, source_key, failure_message, failure_op_name, failure_time_micros, num_failed_attempts FROM {} WHERE failure_message IS NOT NULL ORDER BY failure_time_micros DESC",
+        db_setup.table_name
+    );
+    let failures = sqlx::query_as(&query_str).fetch_all(pool).await?;
+    Ok(failures)
+}
@@ -11,7 +11,7 @@ pub fn default_tracking_table_name(flow_name: &str) -> String {
     )
 }
 
-pub const CURRENT_TRACKING_TABLE_VERSION: i32 = 1;
+pub const CURRENT_TRACKING_TABLE_VERSION: i32 = 2;
 
 async fn upgrade_tracking_table(
     pool: &PgPool,
@@ -42,6 +42,17 @@ async fn upgrade_tracking_table(
         );
         sqlx::query(&query).execute(pool).await?;
     }
+    if existing_version_id < 2 && target_version_id >= 2 {
+        let query = format!(
+            "ALTER TABLE {table_name}
+                -- Update when processing of the source row fails. Cleared after it's processed successfully.
+                ADD COLUMN IF NOT EXISTS failure_message TEXT,
+                ADD COLUMN IF NOT EXISTS failure_op_name TEXT,
+                ADD COLUMN IF NOT EXISTS failure_time_micros BIGINT,
+                ADD COLUMN IF NOT EXISTS num_failed_attempts INTEGER;",
+        );
+        sqlx::query(&query).execute(pool).await?;
+    }
 
     Ok(())
 }
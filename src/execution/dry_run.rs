@@ -0,0 +1,388 @@
+use crate::prelude::*;
+
+use futures::future::try_join_all;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+use super::db_tracking;
+use super::evaluator::SourceRowEvaluationContext;
+use super::row_indexer::{self, SourceVersion};
+use super::stats;
+use crate::builder::plan::{AnalyzedImportOp, ExecutionPlan};
+use crate::ops::interface;
+use crate::utils::concur_control::ConcurrencyController;
+
+const DEFAULT_MAX_SAMPLE_KEYS: usize = 10;
+
+fn default_max_sample_keys() -> usize {
+    DEFAULT_MAX_SAMPLE_KEYS
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DryRunOptions {
+    /// Maximum number of keys to sample for each kind of change on each target.
+    #[serde(default = "default_max_sample_keys")]
+    pub max_sample_keys: usize,
+}
+
+impl Default for DryRunOptions {
+    fn default() -> Self {
+        Self {
+            max_sample_keys: DEFAULT_MAX_SAMPLE_KEYS,
+        }
+    }
+}
+
+/// Changes that would be applied to a target.
+#[derive(Debug, Default, Serialize)]
+pub struct TargetChanges {
+    pub num_insertions: usize,
+    pub num_updates: usize,
+    pub num_deletions: usize,
+    pub sample_insertion_keys: Vec<value::KeyValue>,
+    pub sample_update_keys: Vec<value::KeyValue>,
+    pub sample_deletion_keys: Vec<value::KeyValue>,
+}
+
+impl TargetChanges {
+    pub fn is_empty(&self) -> bool {
+        self.num_insertions == 0 && self.num_updates == 0 && self.num_deletions == 0
+    }
+}
+
+impl std::fmt::Display for TargetChanges {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No changes");
+        }
+        write!(
+            f,
+            "{} rows to INSERT, {} rows to UPDATE, {} rows to DELETE",
+            self.num_insertions, self.num_updates, self.num_deletions
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TargetDryRunInfo {
+    pub target_name: String,
+    pub changes: TargetChanges,
+}
+
+impl std::fmt::Display for TargetDryRunInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.target_name, self.changes)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DryRunReport {
+    /// Stats of source rows, as if the update were performed.
+    pub sources: Vec<stats::SourceUpdateInfo>,
+    pub targets: Vec<TargetDryRunInfo>,
+}
+
+impl std::fmt::Display for DryRunReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for source in self.sources.iter() {
+            writeln!(f, "[source] {source}")?;
+        }
+        for target in self.targets.iter() {
+            writeln!(f, "[target] {target}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects changes to targets during a dry run, in place of applying them.
+pub struct DryRunCollector {
+    max_sample_keys: usize,
+    targets: Mutex<HashMap<String, TargetChanges>>,
+}
+
+impl DryRunCollector {
+    pub fn new(max_sample_keys: usize) -> Self {
+        Self {
+            max_sample_keys,
+            targets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `upserts_existing` tells whether each upsert in the mutation replaces an existing row.
+    pub fn collect(
+        &self,
+        target_name: &str,
+        mutation: &interface::ExportTargetMutation,
+        upserts_existing: &[bool],
+    ) {
+        if mutation.is_empty() {
+            return;
+        }
+        let mut targets = self.targets.lock().unwrap();
+        let changes = targets.entry(target_name.to_string()).or_default();
+        for (idx, upsert) in mutation.upserts.iter().enumerate() {
+            let (num, sample_keys) = if upserts_existing.get(idx).copied().unwrap_or(false) {
+                (&mut changes.num_updates, &mut changes.sample_update_keys)
+            } else {
+                (
+                    &mut changes.num_insertions,
+                    &mut changes.sample_insertion_keys,
+                )
+            };
+            *num += 1;
+            if sample_keys.len() < self.max_sample_keys {
+                sample_keys.push(upsert.key.clone());
+            }
+        }
+        changes.num_deletions += mutation.deletes.len();
+        let num_samples = self
+            .max_sample_keys
+            .saturating_sub(changes.sample_deletion_keys.len());
+        changes.sample_deletion_keys.extend(
+            mutation
+                .deletes
+                .iter()
+                .take(num_samples)
+                .map(|delete| delete.key.clone()),
+        );
+    }
+
+    fn into_target_infos(self, plan: &ExecutionPlan) -> Vec<TargetDryRunInfo> {
+        let mut targets = self.targets.into_inner().unwrap();
+        plan.export_ops
+            .iter()
+            .map(|export_op| TargetDryRunInfo {
+                target_name: export_op.name.clone(),
+                changes: targets.remove(&export_op.name).unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+struct DryRunner<'a> {
+    plan: &'a ExecutionPlan,
+    setup_execution_ctx: &'a exec_ctx::FlowSetupExecutionContext,
+    schema: &'a schema::FlowSchema,
+    collector: DryRunCollector,
+    pool: &'a PgPool,
+}
+
+impl DryRunner<'_> {
+    async fn dry_run_source_row(
+        &self,
+        import_op_idx: usize,
+        import_op: &AnalyzedImportOp,
+        key: &value::KeyValue,
+        concur_controller: &Arc<ConcurrencyController>,
+        update_stats: &stats::UpdateStats,
+    ) -> Result<()> {
+        let mut concur_permit = concur_controller.acquire().await?;
+        let source_data: interface::SourceData = import_op
+            .executor
+            .get_value(
+                key,
+                &interface::SourceExecutorGetOptions {
+                    include_value: true,
+                    include_ordinal: true,
+                },
+            )
+            .await?
+            .try_into()?;
+        concur_permit
+            .acquire_bytes(
+                source_data
+                    .value
+                    .as_optional()
+                    .map_or(0, |v| v.estimated_byte_size()),
+            )
+            .await?;
+        let source_version = SourceVersion::from_current_data(&source_data);
+        row_indexer::update_source_row(
+            &SourceRowEvaluationContext {
+                plan: self.plan,
+                import_op,
+                schema: self.schema,
+                key,
+                import_op_idx,
+            },
+            self.setup_execution_ctx,
+            source_data.value,
+            &source_version,
+            self.pool,
+            update_stats,
+            Some(&self.collector),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn dry_run_source(
+        &self,
+        import_op_idx: usize,
+        import_op: &AnalyzedImportOp,
+    ) -> Result<stats::SourceUpdateInfo> {
+        let update_stats = stats::UpdateStats::default();
+
+        let mut tracked_versions = HashMap::new();
+        {
+            let mut list_state = db_tracking::ListTrackedSourceKeyMetadataState::new();
+            let mut key_metadata_stream = list_state.list(
+                self.setup_execution_ctx.import_ops[import_op_idx].source_id,
+                &self.setup_execution_ctx.setup_state.tracking_table,
+                self.pool,
+            );
+            while let Some(key_metadata) = key_metadata_stream.next().await {
+                let key_metadata = key_metadata?;
+                let source_key = value::Value::<value::ScopeValue>::from_json(
+                    key_metadata.source_key,
+                    &import_op.primary_key_type,
+                )?
+                .into_key()?;
+                tracked_versions.insert(
+                    source_key,
+                    SourceVersion::from_stored(
+                        key_metadata.processed_source_ordinal,
+                        &key_metadata.process_logic_fingerprint,
+                        self.plan.logic_fingerprint,
+                    ),
+                );
+            }
+        }
+
+        // Keys of rows that may need to be processed, including ones that no longer exist in the source.
+        let mut keys = Vec::new();
+        let mut rows_stream = import_op
+            .executor
+            .list(&interface::SourceExecutorListOptions {
+                include_ordinal: true,
+            });
+        while let Some(rows) = rows_stream.next().await {
+            for row in rows? {
+                let source_version = SourceVersion::from_current_with_ordinal(
+                    row.ordinal
+                        .ok_or_else(|| anyhow::anyhow!("ordinal is not available"))?,
+                );
+                if let Some(tracked_version) = tracked_versions.remove(&row.key) {
+                    if tracked_version.should_skip(&source_version, Some(&update_stats)) {
+                        continue;
+                    }
+                }
+                keys.push(row.key);
+            }
+        }
+        keys.extend(tracked_versions.into_keys());
+
+        let concur_controller = Arc::new(ConcurrencyController::new(
+            &import_op.execution_options,
+            None,
+        ));
+        futures::stream::iter(keys)
+            .for_each_concurrent(None, |key| {
+                let concur_controller = &concur_controller;
+                let update_stats = &update_stats;
+                async move {
+                    if let Err(e) = self
+                        .dry_run_source_row(
+                            import_op_idx,
+                            import_op,
+                            &key,
+                            concur_controller,
+                            update_stats,
+                        )
+                        .await
+                    {
+                        update_stats.num_errors.inc(1);
+                        error!(
+                            "{:?}",
+                            e.context(format!(
+                                "Error in dry run of row from source `{source}` with key: {key}",
+                                source = import_op.name
+                            ))
+                        );
+                    }
+                }
+            })
+            .await;
+
+        Ok(stats::SourceUpdateInfo {
+            source_name: import_op.name.clone(),
+            stats: update_stats,
+        })
+    }
+}
+
+/// Runs the update pipeline for all sources of the flow, reporting changes to targets without
+/// writing to targets or the tracking table.
+pub async fn dry_run_update(
+    plan: &ExecutionPlan,
+    setup_execution_ctx: &exec_ctx::FlowSetupExecutionContext,
+    schema: &schema::FlowSchema,
+    options: DryRunOptions,
+    pool: &PgPool,
+) -> Result<DryRunReport> {
+    let runner = DryRunner {
+        plan,
+        setup_execution_ctx,
+        schema,
+        collector: DryRunCollector::new(options.max_sample_keys),
+        pool,
+    };
+    let sources = try_join_all(
+        plan.import_ops
+            .iter()
+            .enumerate()
+            .map(|(idx, import_op)| runner.dry_run_source(idx, import_op)),
+    )
+    .await?;
+    Ok(DryRunReport {
+        sources,
+        targets: runner.collector.into_target_infos(plan),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::interface::{
+        ExportTargetDeleteEntry, ExportTargetMutation, ExportTargetUpsertEntry,
+    };
+
+    fn key(v: i64) -> value::KeyValue {
+        value::KeyValue::Int64(v)
+    }
+
+    #[test]
+    fn test_collector_classifies_and_samples_keys() {
+        let collector = DryRunCollector::new(2);
+        let mutation = ExportTargetMutation {
+            upserts: (1..=4)
+                .map(|v| ExportTargetUpsertEntry {
+                    key: key(v),
+                    additional_key: serde_json::Value::Null,
+                    value: value::FieldValues { fields: vec![] },
+                })
+                .collect(),
+            deletes: (5..=7)
+                .map(|v| ExportTargetDeleteEntry {
+                    key: key(v),
+                    additional_key: serde_json::Value::Null,
+                })
+                .collect(),
+        };
+        collector.collect("target", &mutation, &[true, false, false, false]);
+        collector.collect("target", &ExportTargetMutation::default(), &[]);
+
+        let targets = collector.targets.into_inner().unwrap();
+        let changes = &targets["target"];
+        assert_eq!(changes.num_updates, 1);
+        assert_eq!(changes.num_insertions, 3);
+        assert_eq!(changes.num_deletions, 3);
+        assert_eq!(changes.sample_update_keys, vec![key(1)]);
+        assert_eq!(changes.sample_insertion_keys, vec![key(2), key(3)]);
+        assert_eq!(changes.sample_deletion_keys, vec![key(5), key(6)]);
+        assert_eq!(
+            changes.to_string(),
+            "3 rows to INSERT, 1 rows to UPDATE, 3 rows to DELETE"
+        );
+    }
+}
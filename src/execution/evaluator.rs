@@ -282,6 +282,18 @@ fn assemble_value(
     }
 }
 
+/// Context attached to errors from evaluating a transform op, so the failing op can be identified.
+#[derive(Debug)]
+pub struct TransformOpErrorContext {
+    pub op_name: String,
+}
+
+impl std::fmt::Display for TransformOpErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Evaluating Transform op `{}`", self.op_name)
+    }
+}
+
 fn assemble_input_values<'a>(
     value_mappings: &'a [AnalyzedValueMapping],
     scoped_entries: RefList<'a, &ScopeEntry<'a>>,
@@ -333,12 +345,18 @@ async fn evaluate_op_scope(
                     },
                     &op.function_exec_info.output_type,
                     /*ttl=*/ None,
+                    &op.cache_lookup_counters,
                 )?;
                 let output_value = evaluate_with_cell(output_value_cell.as_ref(), move || {
-                    op.executor.evaluate(input_values)
+                    let timer = op.duration_metric.start_timer();
+                    op.executor.evaluate(input_values).inspect(|_| {
+                        timer.observe_duration();
+                    })
                 })
                 .await
-                .with_context(|| format!("Evaluating Transform op `{}`", op.name,))?;
+                .with_context(|| TransformOpErrorContext {
+                    op_name: op.name.clone(),
+                })?;
                 head_scope.define_field(&op.output, &output_value)?;
             }
 
@@ -16,10 +16,40 @@ pub struct SourceRowInfo {
     pub ordinal: interface::Ordinal,
 }
 
+#[derive(Debug, Serialize)]
+pub struct SourceRowFailureInfo {
+    pub error_message: String,
+    pub op_name: Option<String>,
+    pub num_attempts: i32,
+    pub last_attempt_time: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<db_tracking::SourceProcessingFailureInfo> for SourceRowFailureInfo {
+    fn from(info: db_tracking::SourceProcessingFailureInfo) -> Self {
+        Self {
+            error_message: info.failure_message,
+            op_name: info.failure_op_name,
+            num_attempts: info.num_failed_attempts,
+            last_attempt_time: chrono::DateTime::<chrono::Utc>::from_timestamp_micros(
+                info.failure_time_micros,
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct SourceRowIndexingStatus {
     pub last_processed: Option<SourceRowLastProcessedInfo>,
     pub current: Option<SourceRowInfo>,
+    pub failure: Option<SourceRowFailureInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SourceRowFailure {
+    pub source_name: String,
+    pub key: serde_json::Value,
+    #[serde(flatten)]
+    pub failure: SourceRowFailureInfo,
 }
 
 pub async fn get_source_row_indexing_status(
@@ -28,8 +58,15 @@ pub async fn get_source_row_indexing_status(
     pool: &sqlx::PgPool,
 ) -> Result<SourceRowIndexingStatus> {
     let source_key_json = serde_json::to_value(src_eval_ctx.key)?;
+    let source_id = setup_execution_ctx.import_ops[src_eval_ctx.import_op_idx].source_id;
     let last_processed_fut = db_tracking::read_source_last_processed_info(
-        setup_execution_ctx.import_ops[src_eval_ctx.import_op_idx].source_id,
+        source_id,
+        &source_key_json,
+        &setup_execution_ctx.setup_state.tracking_table,
+        pool,
+    );
+    let failure_fut = db_tracking::read_source_processing_failure(
+        source_id,
         &source_key_json,
         &setup_execution_ctx.setup_state.tracking_table,
         pool,
@@ -41,7 +78,8 @@ pub async fn get_source_row_indexing_status(
             include_ordinal: true,
         },
     );
-    let (last_processed, current) = try_join!(last_processed_fut, current_fut)?;
+    let (last_processed, current, failure) =
+        try_join!(last_processed_fut, current_fut, failure_fut)?;
 
     let last_processed = last_processed.map(|l| SourceRowLastProcessedInfo {
         source_ordinal: interface::Ordinal(l.processed_source_ordinal),
@@ -60,5 +98,35 @@ pub async fn get_source_row_indexing_status(
     Ok(SourceRowIndexingStatus {
         last_processed,
         current: Some(current),
+        failure: failure.map(SourceRowFailureInfo::from),
     })
 }
+
+pub async fn list_source_row_failures(
+    flow: &builder::AnalyzedFlow,
+    setup_execution_ctx: &exec_ctx::FlowSetupExecutionContext,
+    pool: &sqlx::PgPool,
+) -> Result<Vec<SourceRowFailure>> {
+    let failures = db_tracking::list_source_processing_failures(
+        &setup_execution_ctx.setup_state.tracking_table,
+        pool,
+    )
+    .await?;
+    let source_names: HashMap<i32, &str> = std::iter::zip(
+        setup_execution_ctx.import_ops.iter(),
+        flow.flow_instance.import_ops.iter(),
+    )
+    .map(|(import_op_exec_ctx, import_op)| (import_op_exec_ctx.source_id, import_op.name.as_str()))
+    .collect();
+    Ok(failures
+        .into_iter()
+        // Skip failures for sources that are no longer in the flow.
+        .filter_map(|f| {
+            Some(SourceRowFailure {
+                source_name: source_names.get(&f.source_id)?.to_string(),
+                key: f.source_key,
+                failure: f.failure.into(),
+            })
+        })
+        .collect())
+}
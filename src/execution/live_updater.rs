@@ -1,6 +1,8 @@
 use crate::prelude::*;
 
 use super::stats;
+use crate::llm;
+use crate::utils::concur_control::ConcurrencyController;
 use futures::future::try_join_all;
 use sqlx::PgPool;
 use std::time::Instant;
@@ -10,6 +12,11 @@ pub struct FlowLiveUpdater {
     flow_ctx: Arc<FlowContext>,
     tasks: JoinSet<Result<()>>,
     sources_update_stats: Vec<Arc<stats::UpdateStats>>,
+    llm_usage: Arc<stats::UpdateLlmUsage>,
+    /// Aborts the update once the LLM token budget is exceeded.
+    llm_token_budget_monitor: JoinSet<()>,
+    /// Set by the monitor before aborting the update.
+    llm_token_budget_exceeded: Arc<OnceLock<String>>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -20,6 +27,14 @@ pub struct FlowLiveUpdaterOptions {
 
     /// If true, stats will be printed to the console.
     pub print_stats: bool,
+
+    /// Limits shared by all sources of the flow.
+    #[serde(flatten)]
+    pub execution_options: spec::ExecutionOptions,
+
+    /// Maximum number of LLM tokens (input and output) consumed by the flow during the update.
+    /// The update is aborted once exceeded.
+    pub llm_token_budget: Option<u64>,
 }
 
 struct StatsReportState {
@@ -27,6 +42,52 @@ struct StatsReportState {
     last_stats: stats::UpdateStats,
 }
 
+fn collect_ops_llm_usage(op_scope: &plan::AnalyzedOpScope, llm_usage: &mut stats::UpdateLlmUsage) {
+    for reactive_op in op_scope.reactive_ops.iter() {
+        match reactive_op {
+            plan::AnalyzedReactiveOp::Transform(op) => {
+                llm_usage.add_op(op.name.clone(), op.llm_usage_stats.clone())
+            }
+            plan::AnalyzedReactiveOp::ForEach(op) => collect_ops_llm_usage(&op.op_scope, llm_usage),
+            plan::AnalyzedReactiveOp::Collect(_) => {}
+        }
+    }
+}
+
+const LLM_TOKEN_BUDGET_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+async fn monitor_llm_token_budget(
+    budget: u64,
+    llm_usage: Arc<stats::UpdateLlmUsage>,
+    source_tasks: Vec<tokio::task::AbortHandle>,
+    exceeded: Arc<OnceLock<String>>,
+) {
+    let mut interval = tokio::time::interval(LLM_TOKEN_BUDGET_CHECK_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let used_tokens = llm_usage.total_tokens();
+        if used_tokens > budget as i64 {
+            // Recorded before aborting, so `wait()` sees it once the source tasks end.
+            let _ = exceeded.set(format!(
+                "Update aborted: {used_tokens} LLM tokens used, exceeding the budget of {budget}"
+            ));
+            for task in source_tasks.iter() {
+                task.abort();
+            }
+            return;
+        }
+    }
+}
+
+fn log_row_task_result(result: Result<(), tokio::task::JoinError>) {
+    if let Err(e) = result {
+        if !e.is_cancelled() {
+            error!("{:?}", e);
+        }
+    }
+}
+
 const MIN_REPORT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
 const REPORT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
 
@@ -59,12 +120,14 @@ impl SharedAckFn {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn update_source(
     flow: Arc<builder::AnalyzedFlow>,
     plan: Arc<plan::ExecutionPlan>,
     execution_ctx: Arc<tokio::sync::OwnedRwLockReadGuard<crate::lib_context::FlowExecutionContext>>,
     source_update_stats: Arc<stats::UpdateStats>,
     source_idx: usize,
+    flow_concur_controller: Arc<ConcurrencyController>,
     pool: PgPool,
     options: FlowLiveUpdaterOptions,
 ) -> Result<()> {
@@ -73,6 +136,10 @@ async fn update_source(
         .await?;
 
     let import_op = &plan.import_ops[source_idx];
+    let concur_controller = Arc::new(ConcurrencyController::new(
+        &import_op.execution_options,
+        Some(flow_concur_controller),
+    ));
 
     let stats_report_state = Mutex::new(StatsReportState {
         last_report_time: None,
@@ -110,9 +177,12 @@ async fn update_source(
         if let Some(change_stream) = import_op.executor.change_stream().await? {
             let pool = pool.clone();
             let source_update_stats = source_update_stats.clone();
+            let concur_controller = concur_controller.clone();
             futs.push(
                 async move {
                     let mut change_stream = change_stream;
+                    // Rows being processed. They're aborted together with the update.
+                    let mut row_tasks = JoinSet::new();
                     let retry_options = retryable::RetryOptions {
                         max_retries: None,
                         initial_backoff: std::time::Duration::from_secs(5),
@@ -145,19 +215,29 @@ async fn update_source(
                                 ack_fn,
                             )))
                         });
+                        while let Some(result) = row_tasks.try_join_next() {
+                            log_row_task_result(result);
+                        }
                         for change in change_msg.changes {
                             let ack_fn = ack_fn.clone();
-                            tokio::spawn(source_context.clone().process_source_key(
-                                change.key,
-                                change.data,
-                                source_update_stats.clone(),
-                                ack_fn.map(|ack_fn| {
-                                    move || async move { SharedAckFn::ack(&ack_fn).await }
-                                }),
-                                pool.clone(),
+                            let concur_permit = concur_controller.acquire().await?;
+                            row_tasks.spawn(llm::in_current_update_scope(
+                                source_context.clone().process_source_key(
+                                    change.key,
+                                    change.data,
+                                    source_update_stats.clone(),
+                                    Some(concur_permit),
+                                    ack_fn.map(|ack_fn| {
+                                        move || async move { SharedAckFn::ack(&ack_fn).await }
+                                    }),
+                                    pool.clone(),
+                                ),
                             ));
                         }
                     }
+                    while let Some(result) = row_tasks.join_next().await {
+                        log_row_task_result(result);
+                    }
                     Ok(())
                 }
                 .boxed(),
@@ -181,7 +261,9 @@ async fn update_source(
     let source_update_stats = source_update_stats.clone();
     futs.push(
         async move {
-            source_context.update(&pool, &source_update_stats).await?;
+            source_context
+                .update(&pool, &source_update_stats, &concur_controller)
+                .await?;
             report_stats();
 
             if let (true, Some(refresh_interval)) = (
@@ -193,7 +275,9 @@ async fn update_source(
                 interval.tick().await;
                 loop {
                     interval.tick().await;
-                    source_context.update(&pool, &source_update_stats).await?;
+                    source_context
+                        .update(&pool, &source_update_stats, &concur_controller)
+                        .await?;
                     report_stats();
                 }
             }
@@ -215,26 +299,52 @@ impl FlowLiveUpdater {
         let plan = flow_ctx.flow.get_execution_plan().await?;
         let execution_ctx = Arc::new(flow_ctx.use_owned_execution_ctx().await?);
 
+        let mut llm_usage = stats::UpdateLlmUsage::default();
+        collect_ops_llm_usage(&plan.op_scope, &mut llm_usage);
+        let llm_usage = Arc::new(llm_usage);
+
         let mut tasks = JoinSet::new();
+        let mut source_tasks = Vec::new();
+        let flow_concur_controller =
+            Arc::new(ConcurrencyController::new(&options.execution_options, None));
         let sources_update_stats = (0..plan.import_ops.len())
             .map(|source_idx| {
                 let source_update_stats = Arc::new(stats::UpdateStats::default());
-                tasks.spawn(update_source(
-                    flow_ctx.flow.clone(),
-                    plan.clone(),
-                    execution_ctx.clone(),
-                    source_update_stats.clone(),
-                    source_idx,
-                    pool.clone(),
-                    options.clone(),
+                let source_task = tasks.spawn(llm::UPDATE_LLM_USAGE.scope(
+                    Some(llm_usage.clone()),
+                    update_source(
+                        flow_ctx.flow.clone(),
+                        plan.clone(),
+                        execution_ctx.clone(),
+                        source_update_stats.clone(),
+                        source_idx,
+                        flow_concur_controller.clone(),
+                        pool.clone(),
+                        options.clone(),
+                    ),
                 ));
+                source_tasks.push(source_task);
                 source_update_stats
             })
             .collect();
+
+        let mut llm_token_budget_monitor = JoinSet::new();
+        let llm_token_budget_exceeded = Arc::new(OnceLock::new());
+        if let Some(budget) = options.llm_token_budget {
+            llm_token_budget_monitor.spawn(monitor_llm_token_budget(
+                budget,
+                llm_usage.clone(),
+                source_tasks,
+                llm_token_budget_exceeded.clone(),
+            ));
+        }
         Ok(Self {
             flow_ctx,
             tasks,
             sources_update_stats,
+            llm_usage,
+            llm_token_budget_monitor,
+            llm_token_budget_exceeded,
         })
     }
 
@@ -256,11 +366,16 @@ impl FlowLiveUpdater {
                 _ => {}
             }
         }
+        self.llm_token_budget_monitor.abort_all();
+        if let Some(message) = self.llm_token_budget_exceeded.get() {
+            api_bail!("{message}");
+        }
         Ok(())
     }
 
     pub fn abort(&mut self) {
         self.tasks.abort_all();
+        self.llm_token_budget_monitor.abort_all();
     }
 
     pub fn index_update_info(&self) -> stats::IndexUpdateInfo {
@@ -274,6 +389,16 @@ impl FlowLiveUpdater {
                 stats: (**stats).clone(),
             })
             .collect(),
+            ops: self
+                .llm_usage
+                .ops
+                .iter()
+                .filter(|op| !op.stats.is_zero())
+                .map(|op| stats::OpUpdateInfo {
+                    op_name: op.op_name.clone(),
+                    llm_usage: op.stats.clone(),
+                })
+                .collect(),
         }
     }
 }
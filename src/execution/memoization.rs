@@ -9,6 +9,7 @@ use std::{
 
 use crate::{
     base::{schema, value},
+    metrics,
     service::error::{SharedError, SharedResultExtRef},
     utils::fingerprint::{Fingerprint, Fingerprinter},
 };
@@ -171,6 +172,7 @@ impl EvaluationMemory {
         key: impl FnOnce() -> Result<Fingerprint>,
         typ: &schema::ValueType,
         ttl: Option<chrono::Duration>,
+        lookup_counters: &metrics::CacheLookupCounters,
     ) -> Result<Option<CacheEntryCell>> {
         let mut cache = if let Some(cache) = &self.cache {
             cache.lock().unwrap()
@@ -184,7 +186,7 @@ impl EvaluationMemory {
                     .unwrap_or(false) =>
             {
                 let entry_mut = &mut entry.get_mut();
-                match &mut entry_mut.data {
+                let cell = match &mut entry_mut.data {
                     CacheData::Previous(value) => {
                         let value = value::Value::from_json(std::mem::take(value), typ)?;
                         let cell = Arc::new(tokio::sync::OnceCell::from(Ok(value)));
@@ -196,9 +198,12 @@ impl EvaluationMemory {
                         cell
                     }
                     CacheData::Current(cell) => cell.clone(),
-                }
+                };
+                lookup_counters.hit.inc();
+                cell
             }
             entry => {
+                lookup_counters.miss.inc();
                 let cell = Arc::new(tokio::sync::OnceCell::new());
                 entry.insert_entry(CacheEntry {
                     time: self.current_time,
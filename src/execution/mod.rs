@@ -1,8 +1,10 @@
 pub(crate) mod db_tracking_setup;
+pub(crate) mod dry_run;
 pub(crate) mod dumper;
 pub(crate) mod evaluator;
 pub(crate) mod indexing_status;
 pub(crate) mod memoization;
+pub(crate) mod query;
 pub(crate) mod row_indexer;
 pub(crate) mod source_indexer;
 pub(crate) mod stats;
@@ -0,0 +1,153 @@
+use crate::prelude::*;
+
+use super::evaluator::evaluate_transient_flow;
+use crate::base::{schema, spec::VectorSimilarityMetric, value};
+use crate::builder::{AnalyzedFlow, AnalyzedTransientFlow};
+use crate::ops::interface::{QueryResults, QueryTarget, VectorMatchQuery};
+
+pub struct SimpleSemanticsQueryHandler {
+    pub spec: spec::SimpleSemanticsQueryHandlerSpec,
+    query_transform_flow: Arc<AnalyzedTransientFlow>,
+    query_target: Arc<dyn QueryTarget>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimpleSemanticsQueryInfo {
+    pub vector_field_name: String,
+    pub similarity_metric: VectorSimilarityMetric,
+    pub query_vector: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimpleSemanticsQueryResponse {
+    pub results: QueryResults,
+    pub info: SimpleSemanticsQueryInfo,
+}
+
+fn to_query_vector(value: value::Value) -> Result<Vec<f32>> {
+    value
+        .as_vector()?
+        .iter()
+        .map(|v| {
+            Ok(match v {
+                value::BasicValue::Float32(v) => *v,
+                value::BasicValue::Float64(v) => *v as f32,
+                value::BasicValue::Int64(v) => *v as f32,
+                v => bail!("unexpected vector element type: {}", v.kind()),
+            })
+        })
+        .collect()
+}
+
+impl SimpleSemanticsQueryHandler {
+    pub async fn new(
+        flow: &AnalyzedFlow,
+        query_transform_flow: Arc<AnalyzedTransientFlow>,
+        spec: spec::SimpleSemanticsQueryHandlerSpec,
+    ) -> Result<Self> {
+        match query_transform_flow
+            .transient_flow_instance
+            .input_fields
+            .as_slice()
+        {
+            [input]
+                if matches!(
+                    input.value_type.typ,
+                    schema::ValueType::Basic(schema::BasicValueType::Str)
+                ) => {}
+            _ => api_bail!("Query transform flow must take exactly one `Str` input"),
+        }
+        if !matches!(
+            query_transform_flow.output_type.typ,
+            schema::ValueType::Basic(schema::BasicValueType::Vector(_))
+        ) {
+            api_bail!(
+                "Query transform flow must output a vector, got {}",
+                query_transform_flow.output_type.typ
+            );
+        }
+
+        let execution_plan = flow.get_execution_plan().await?;
+        let export_op = execution_plan
+            .export_ops
+            .iter()
+            .find(|op| op.name == spec.export_target_name)
+            .ok_or_else(|| {
+                api_error!(
+                    "Export target `{}` not found in flow `{}`",
+                    spec.export_target_name,
+                    spec.flow_instance_name
+                )
+            })?;
+        let query_target = export_op
+            .export_target_factory
+            .build_query_target(export_op.export_context.clone())?
+            .ok_or_else(|| {
+                api_error!(
+                    "Export target `{}` doesn't support querying",
+                    spec.export_target_name
+                )
+            })?;
+        Ok(Self {
+            spec,
+            query_transform_flow,
+            query_target,
+        })
+    }
+
+    pub async fn search(
+        &self,
+        query: String,
+        limit: u32,
+        vector_field_name: Option<String>,
+        similarity_metric: Option<VectorSimilarityMetric>,
+    ) -> Result<SimpleSemanticsQueryResponse> {
+        let query_vector = to_query_vector(
+            evaluate_transient_flow(
+                &self.query_transform_flow,
+                &vec![value::Value::Basic(value::BasicValue::Str(query.into()))],
+            )
+            .await?,
+        )?;
+        let vector_field_name = match vector_field_name {
+            Some(name) => name,
+            None => self.query_target.default_vector_field_name()?,
+        };
+        let similarity_metric = similarity_metric.unwrap_or(self.spec.default_similarity_metric);
+        let results = self
+            .query_target
+            .search(VectorMatchQuery {
+                vector_field_name: vector_field_name.clone(),
+                vector: query_vector.clone(),
+                similarity_metric,
+                limit,
+            })
+            .await?;
+        Ok(SimpleSemanticsQueryResponse {
+            results,
+            info: SimpleSemanticsQueryInfo {
+                vector_field_name,
+                similarity_metric,
+                query_vector,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_query_vector() {
+        let vector = value::Value::Basic(value::BasicValue::Vector(Arc::from(vec![
+            value::BasicValue::Float32(0.5),
+            value::BasicValue::Float64(1.5),
+            value::BasicValue::Int64(2),
+        ])));
+        assert_eq!(to_query_vector(vector).unwrap(), vec![0.5, 1.5, 2.0]);
+
+        let not_vector = value::Value::Basic(value::BasicValue::Str("hello".into()));
+        assert!(to_query_vector(not_vector).is_err());
+    }
+}
@@ -6,6 +6,7 @@ use std::collections::{HashMap, HashSet};
 
 use super::db_tracking::{self, TrackedTargetKeyInfo, read_source_tracking_info_for_processing};
 use super::db_tracking_setup;
+use super::dry_run::DryRunCollector;
 use super::evaluator::{
     EvaluateSourceEntryOutput, SourceRowEvaluationContext, evaluate_source_entry,
 };
@@ -157,6 +158,8 @@ struct TrackingInfoForTarget<'a> {
 
     // Mutation to apply to the target storage.
     mutation: ExportTargetMutation,
+    // Whether each entry in `mutation.upserts` replaces a row previously exported for the same key.
+    upserts_existing: Vec<bool>,
 }
 
 #[derive(Debug)]
@@ -173,6 +176,7 @@ struct PrecommitMetadata {
 struct PrecommitOutput {
     metadata: PrecommitMetadata,
     target_mutations: HashMap<i32, ExportTargetMutation>,
+    target_upserts_existing: HashMap<i32, Vec<bool>>,
 }
 
 async fn precommit_source_tracking_info(
@@ -186,6 +190,7 @@ async fn precommit_source_tracking_info(
     export_ops: &[AnalyzedExportOp],
     export_ops_exec_ctx: &[exec_ctx::ExportOpExecutionContext],
     update_stats: &stats::UpdateStats,
+    dry_run: bool,
     pool: &PgPool,
 ) -> Result<SkippedOr<PrecommitOutput>> {
     let mut txn = pool.begin().await?;
@@ -326,6 +331,11 @@ async fn precommit_source_tracking_info(
                     });
                 } else {
                     // Entry with new value. Needs to be upserted.
+                    target_info.upserts_existing.push(
+                        existing_target_keys
+                            .as_ref()
+                            .is_some_and(|keys| !keys.is_empty()),
+                    );
                     let tracked_target_key = TrackedTargetKeyInfo {
                         key: target_key_pair.key.clone(),
                         additional_key: target_key_pair.additional_key.clone(),
@@ -349,6 +359,7 @@ async fn precommit_source_tracking_info(
 
     let mut new_staging_target_keys = db_tracking::TrackedTargetKeyForSource::default();
     let mut target_mutations = HashMap::with_capacity(export_ops.len());
+    let mut target_upserts_existing = HashMap::with_capacity(export_ops.len());
     for (target_id, target_tracking_info) in tracking_info_for_targets.into_iter() {
         let legacy_keys: HashSet<TargetKeyPair> = target_tracking_info
             .existing_keys_info
@@ -381,26 +392,30 @@ async fn precommit_source_tracking_info(
                 });
             }
             target_mutations.insert(target_id, mutation);
+            target_upserts_existing.insert(target_id, target_tracking_info.upserts_existing);
         }
     }
 
-    db_tracking::precommit_source_tracking_info(
-        source_id,
-        source_key_json,
-        process_ordinal,
-        new_staging_target_keys,
-        data.as_ref().map(|data| data.memoization_info),
-        db_setup,
-        &mut *txn,
-        if tracking_info_exists {
-            WriteAction::Update
-        } else {
-            WriteAction::Insert
-        },
-    )
-    .await?;
+    // For dry run, the transaction is rolled back on drop, leaving the tracking table untouched.
+    if !dry_run {
+        db_tracking::precommit_source_tracking_info(
+            source_id,
+            source_key_json,
+            process_ordinal,
+            new_staging_target_keys,
+            data.as_ref().map(|data| data.memoization_info),
+            db_setup,
+            &mut *txn,
+            if tracking_info_exists {
+                WriteAction::Update
+            } else {
+                WriteAction::Insert
+            },
+        )
+        .await?;
 
-    txn.commit().await?;
+        txn.commit().await?;
+    }
 
     Ok(SkippedOr::Normal(PrecommitOutput {
         metadata: PrecommitMetadata {
@@ -410,6 +425,7 @@ async fn precommit_source_tracking_info(
             new_target_keys: new_target_keys_info,
         },
         target_mutations,
+        target_upserts_existing,
     }))
 }
 
@@ -510,6 +526,7 @@ async fn try_content_hash_optimization(
     existing_version: &Option<SourceVersion>,
     db_setup: &db_tracking_setup::TrackingTableSetupState,
     update_stats: &stats::UpdateStats,
+    dry_run: bool,
     pool: &PgPool,
 ) -> Result<Option<SkippedOr<()>>> {
     // Check if we can use content hash optimization
@@ -569,16 +586,18 @@ async fn try_content_hash_optimization(
     }
 
     // Safe to apply optimization - just update tracking table
-    db_tracking::update_source_tracking_ordinal(
-        source_id,
-        source_key_json,
-        source_version.ordinal.0,
-        db_setup,
-        &mut *txn,
-    )
-    .await?;
+    if !dry_run {
+        db_tracking::update_source_tracking_ordinal(
+            source_id,
+            source_key_json,
+            source_version.ordinal.0,
+            db_setup,
+            &mut *txn,
+        )
+        .await?;
 
-    txn.commit().await?;
+        txn.commit().await?;
+    }
     update_stats.num_no_change.inc(1);
     Ok(Some(SkippedOr::Normal(())))
 }
@@ -635,6 +654,7 @@ pub async fn update_source_row(
     source_version: &SourceVersion,
     pool: &PgPool,
     update_stats: &stats::UpdateStats,
+    dry_run: Option<&DryRunCollector>,
 ) -> Result<SkippedOr<()>> {
     let source_key_json = serde_json::to_value(src_eval_ctx.key)?;
     let process_time = chrono::Utc::now();
@@ -689,6 +709,7 @@ pub async fn update_source_row(
             &existing_version,
             &setup_execution_ctx.setup_state.tracking_table,
             update_stats,
+            dry_run.is_some(),
             pool,
         )
         .await?
@@ -739,6 +760,7 @@ pub async fn update_source_row(
         &src_eval_ctx.plan.export_ops,
         &setup_execution_ctx.export_ops,
         update_stats,
+        dry_run.is_some(),
         pool,
     )
     .await?;
@@ -747,49 +769,71 @@ pub async fn update_source_row(
         SkippedOr::Skipped(source_version) => return Ok(SkippedOr::Skipped(source_version)),
     };
 
-    // Phase 3: Apply changes to the target storage, including upserting new target records and removing existing ones.
-    let mut target_mutations = precommit_output.target_mutations;
-    let apply_futs = src_eval_ctx
-        .plan
-        .export_op_groups
-        .iter()
-        .filter_map(|export_op_group| {
-            let mutations_w_ctx: Vec<_> = export_op_group
-                .op_idx
-                .iter()
-                .filter_map(|export_op_idx| {
-                    let export_op = &src_eval_ctx.plan.export_ops[*export_op_idx];
-                    target_mutations
-                        .remove(&setup_execution_ctx.export_ops[*export_op_idx].target_id)
-                        .filter(|m| !m.is_empty())
-                        .map(|mutation| interface::ExportTargetMutationWithContext {
-                            mutation,
-                            export_context: export_op.export_context.as_ref(),
-                        })
+    if let Some(dry_run_collector) = dry_run {
+        // Dry run: report the changes instead of applying them.
+        let mut target_upserts_existing = precommit_output.target_upserts_existing;
+        for (export_op, export_op_exec_ctx) in std::iter::zip(
+            &src_eval_ctx.plan.export_ops,
+            &setup_execution_ctx.export_ops,
+        ) {
+            if let Some(mutation) = precommit_output
+                .target_mutations
+                .get(&export_op_exec_ctx.target_id)
+            {
+                dry_run_collector.collect(
+                    &export_op.name,
+                    mutation,
+                    &target_upserts_existing
+                        .remove(&export_op_exec_ctx.target_id)
+                        .unwrap_or_default(),
+                );
+            }
+        }
+    } else {
+        // Phase 3: Apply changes to the target storage, including upserting new target records and removing existing ones.
+        let mut target_mutations = precommit_output.target_mutations;
+        let apply_futs = src_eval_ctx
+            .plan
+            .export_op_groups
+            .iter()
+            .filter_map(|export_op_group| {
+                let mutations_w_ctx: Vec<_> = export_op_group
+                    .op_idx
+                    .iter()
+                    .filter_map(|export_op_idx| {
+                        let export_op = &src_eval_ctx.plan.export_ops[*export_op_idx];
+                        target_mutations
+                            .remove(&setup_execution_ctx.export_ops[*export_op_idx].target_id)
+                            .filter(|m| !m.is_empty())
+                            .map(|mutation| interface::ExportTargetMutationWithContext {
+                                mutation,
+                                export_context: export_op.export_context.as_ref(),
+                            })
+                    })
+                    .collect();
+                (!mutations_w_ctx.is_empty()).then(|| {
+                    export_op_group
+                        .target_factory
+                        .apply_mutation(mutations_w_ctx)
                 })
-                .collect();
-            (!mutations_w_ctx.is_empty()).then(|| {
-                export_op_group
-                    .target_factory
-                    .apply_mutation(mutations_w_ctx)
-            })
-        });
-
-    // TODO: Handle errors.
-    try_join_all(apply_futs).await?;
-
-    // Phase 4: Update the tracking record.
-    commit_source_tracking_info(
-        source_id,
-        &source_key_json,
-        source_version,
-        &src_eval_ctx.plan.logic_fingerprint.0,
-        precommit_output.metadata,
-        &process_time,
-        &setup_execution_ctx.setup_state.tracking_table,
-        pool,
-    )
-    .await?;
+            });
+
+        // TODO: Handle errors.
+        try_join_all(apply_futs).await?;
+
+        // Phase 4: Update the tracking record.
+        commit_source_tracking_info(
+            source_id,
+            &source_key_json,
+            source_version,
+            &src_eval_ctx.plan.logic_fingerprint.0,
+            precommit_output.metadata,
+            &process_time,
+            &setup_execution_ctx.setup_state.tracking_table,
+            pool,
+        )
+        .await?;
+    }
 
     if let Some(existing_version) = existing_version {
         if output.is_some() {
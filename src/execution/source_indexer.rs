@@ -7,16 +7,22 @@ use tokio::{sync::Semaphore, task::JoinSet};
 
 use super::{
     db_tracking,
-    evaluator::SourceRowEvaluationContext,
+    evaluator::{SourceRowEvaluationContext, TransformOpErrorContext},
     row_indexer::{self, SkippedOr, SourceVersion},
     stats,
 };
 
+use crate::llm;
+use crate::metrics;
 use crate::ops::interface;
+use crate::utils::concur_control::{ConcurrencyController, ConcurrencyControllerPermit};
 struct SourceRowIndexingState {
     source_version: SourceVersion,
     processing_sem: Arc<Semaphore>,
     touched_generation: usize,
+    /// Set if the last processing of the row failed.
+    /// Before this time, the row is only retried on changes from the source.
+    retry_after: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl Default for SourceRowIndexingState {
@@ -25,10 +31,25 @@ impl Default for SourceRowIndexingState {
             source_version: SourceVersion::default(),
             processing_sem: Arc::new(Semaphore::new(1)),
             touched_generation: 0,
+            retry_after: None,
         }
     }
 }
 
+const FAILURE_RETRY_INITIAL_BACKOFF: std::time::Duration = std::time::Duration::from_secs(30);
+const FAILURE_RETRY_MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(3600);
+
+fn failure_retry_time(
+    failure_time: chrono::DateTime<chrono::Utc>,
+    num_failed_attempts: i32,
+) -> chrono::DateTime<chrono::Utc> {
+    let exponent = num_failed_attempts.clamp(1, 16) as u32 - 1;
+    let backoff = FAILURE_RETRY_INITIAL_BACKOFF
+        .saturating_mul(1 << exponent)
+        .min(FAILURE_RETRY_MAX_BACKOFF);
+    failure_time + chrono::TimeDelta::from_std(backoff).unwrap_or(chrono::TimeDelta::MAX)
+}
+
 struct SourceIndexingState {
     rows: HashMap<value::KeyValue, SourceRowIndexingState>,
     scan_generation: usize,
@@ -77,6 +98,15 @@ impl SourceIndexingContext {
                         ),
                         processing_sem: Arc::new(Semaphore::new(1)),
                         touched_generation: scan_generation,
+                        retry_after: key_metadata
+                            .failure_time_micros
+                            .and_then(chrono::DateTime::from_timestamp_micros)
+                            .map(|failure_time| {
+                                failure_retry_time(
+                                    failure_time,
+                                    key_metadata.num_failed_attempts.unwrap_or(1),
+                                )
+                            }),
                     },
                 );
             }
@@ -100,9 +130,11 @@ impl SourceIndexingContext {
         key: value::KeyValue,
         source_data: Option<interface::SourceData>,
         update_stats: Arc<stats::UpdateStats>,
+        mut concur_permit: Option<ConcurrencyControllerPermit>,
         ack_fn: Option<AckFn>,
         pool: PgPool,
     ) {
+        let row_stats = stats::UpdateStats::default();
         let process = async {
             let plan = self.flow.get_execution_plan().await?;
             let import_op = &plan.import_ops[self.source_idx];
@@ -121,14 +153,26 @@ impl SourceIndexingContext {
                     .await?
                     .try_into()?,
             };
+            if let Some(concur_permit) = &mut concur_permit {
+                concur_permit
+                    .acquire_bytes(
+                        source_data
+                            .value
+                            .as_optional()
+                            .map_or(0, |v| v.estimated_byte_size()),
+                    )
+                    .await?;
+            }
 
             let source_version = SourceVersion::from_current_data(&source_data);
             let processing_sem = {
                 let mut state = self.state.lock().unwrap();
                 let row_state = state.rows.entry(key.clone()).or_default();
-                if row_state
-                    .source_version
-                    .should_skip(&source_version, Some(update_stats.as_ref()))
+                // The in-memory version of a failed row isn't processed. Leave it to the checks against the tracking table.
+                if row_state.retry_after.is_none()
+                    && row_state
+                        .source_version
+                        .should_skip(&source_version, Some(&row_stats))
                 {
                     return anyhow::Ok(());
                 }
@@ -149,7 +193,8 @@ impl SourceIndexingContext {
                 source_data.value,
                 &source_version,
                 &pool,
-                &update_stats,
+                &row_stats,
+                None,
             )
             .await?;
             let target_source_version = if let SkippedOr::Skipped(existing_source_version) = result
@@ -191,14 +236,25 @@ impl SourceIndexingContext {
                     }
                 }
             }
+            if let Some(row_state) = self.state.lock().unwrap().rows.get_mut(&key) {
+                row_state.retry_after = None;
+            }
             drop(permit);
             if let Some(ack_fn) = ack_fn {
                 ack_fn().await?;
             }
             anyhow::Ok(())
         };
-        if let Err(e) = process.await {
-            update_stats.num_errors.inc(1);
+        let result = process.await;
+        drop(concur_permit);
+        if let Err(e) = result {
+            row_stats.num_errors.inc(1);
+            if let Err(record_err) = self.record_failure(&key, &e, &pool).await {
+                error!(
+                    "{:?}",
+                    record_err.context("Failed to record the processing failure")
+                );
+            }
             error!(
                 "{:?}",
                 e.context(format!(
@@ -207,38 +263,91 @@ impl SourceIndexingContext {
                 ))
             );
         }
+        self.merge_row_stats(&update_stats, &row_stats);
+    }
+
+    /// Merges stats of a row into the update's stats, and records them in metrics.
+    fn merge_row_stats(&self, update_stats: &stats::UpdateStats, row_stats: &stats::UpdateStats) {
+        update_stats.merge(row_stats);
+        metrics::record_source_row_stats(
+            &self.flow.flow_instance.name,
+            &self.flow.flow_instance.import_ops[self.source_idx].name,
+            row_stats,
+        );
+    }
+
+    async fn record_failure(
+        &self,
+        key: &value::KeyValue,
+        error: &anyhow::Error,
+        pool: &PgPool,
+    ) -> Result<()> {
+        let failure_time = chrono::Utc::now();
+        let num_failed_attempts = db_tracking::record_source_processing_failure(
+            self.setup_execution_ctx.import_ops[self.source_idx].source_id,
+            &serde_json::to_value(key)?,
+            &format!("{error:#}"),
+            error
+                .downcast_ref::<TransformOpErrorContext>()
+                .map(|c| c.op_name.as_str()),
+            failure_time.timestamp_micros(),
+            &self.setup_execution_ctx.setup_state.tracking_table,
+            pool,
+        )
+        .await?;
+        let mut state = self.state.lock().unwrap();
+        let row_state = state.rows.entry(key.clone()).or_default();
+        row_state.retry_after = Some(failure_retry_time(failure_time, num_failed_attempts));
+        Ok(())
     }
 
     // Expected to be called during scan, which has no value.
-    fn process_source_key_if_newer(
+    async fn process_source_key_if_newer(
         self: &Arc<Self>,
         key: value::KeyValue,
         source_version: SourceVersion,
         update_stats: &Arc<stats::UpdateStats>,
+        concur_controller: &Arc<ConcurrencyController>,
         pool: &PgPool,
-    ) -> Option<impl Future<Output = ()> + Send + 'static> {
+    ) -> Result<Option<impl Future<Output = ()> + Send + 'static>> {
         {
+            let row_stats = stats::UpdateStats::default();
             let mut state = self.state.lock().unwrap();
             let scan_generation = state.scan_generation;
             let row_state = state.rows.entry(key.clone()).or_default();
             row_state.touched_generation = scan_generation;
-            if row_state
-                .source_version
-                .should_skip(&source_version, Some(update_stats.as_ref()))
-            {
-                return None;
+            let should_skip = match row_state.retry_after {
+                // Failed rows are retried when they're due, or when there're changes from the source.
+                Some(retry_after) => {
+                    retry_after > chrono::Utc::now()
+                        && row_state.source_version.should_skip(&source_version, None)
+                }
+                None => row_state
+                    .source_version
+                    .should_skip(&source_version, Some(&row_stats)),
+            };
+            if should_skip {
+                self.merge_row_stats(update_stats, &row_stats);
+                return Ok(None);
             }
         }
-        Some(
-            self.clone()
-                .process_source_key(key, None, update_stats.clone(), NO_ACK, pool.clone()),
-        )
+        // Waiting for the permit pauses the scan when there're too many rows in flight.
+        let concur_permit = concur_controller.acquire().await?;
+        Ok(Some(self.clone().process_source_key(
+            key,
+            None,
+            update_stats.clone(),
+            Some(concur_permit),
+            NO_ACK,
+            pool.clone(),
+        )))
     }
 
     pub async fn update(
         self: &Arc<Self>,
         pool: &PgPool,
         update_stats: &Arc<stats::UpdateStats>,
+        concur_controller: &Arc<ConcurrencyController>,
     ) -> Result<()> {
         let plan = self.flow.get_execution_plan().await?;
         let import_op = &plan.import_ops[self.source_idx];
@@ -262,9 +371,11 @@ impl SourceIndexingContext {
                             .ok_or_else(|| anyhow::anyhow!("ordinal is not available"))?,
                     ),
                     update_stats,
+                    concur_controller,
                     pool,
                 )
-                .map(|fut| join_set.spawn(fut));
+                .await?
+                .map(|fut| join_set.spawn(llm::in_current_update_scope(fut)));
             }
         }
         while let Some(result) = join_set.join_next().await {
@@ -293,10 +404,57 @@ impl SourceIndexingContext {
                     value: interface::SourceValue::NonExistence,
                     ordinal: source_ordinal,
                 });
+            let concur_permit = concur_controller.acquire().await?;
+            join_set.spawn(llm::in_current_update_scope(
+                self.clone().process_source_key(
+                    key,
+                    source_data,
+                    update_stats.clone(),
+                    Some(concur_permit),
+                    NO_ACK,
+                    pool.clone(),
+                ),
+            ));
+        }
+        while let Some(result) = join_set.join_next().await {
+            if let Err(e) = result {
+                if !e.is_cancelled() {
+                    error!("{:?}", e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reprocess all rows whose last processing failed, without waiting for the retry backoff.
+    pub async fn retry_failed_rows(
+        self: &Arc<Self>,
+        pool: &PgPool,
+        update_stats: &Arc<stats::UpdateStats>,
+    ) -> Result<()> {
+        let plan = self.flow.get_execution_plan().await?;
+        let concur_controller = Arc::new(ConcurrencyController::new(
+            &plan.import_ops[self.source_idx].execution_options,
+            None,
+        ));
+        let failed_keys: Vec<_> = {
+            let state = self.state.lock().unwrap();
+            state
+                .rows
+                .iter()
+                .filter(|(_, row_state)| row_state.retry_after.is_some())
+                .map(|(key, _)| key.clone())
+                .collect()
+        };
+        let mut join_set = JoinSet::new();
+        for key in failed_keys {
+            let concur_permit = concur_controller.acquire().await?;
             join_set.spawn(self.clone().process_source_key(
                 key,
-                source_data,
+                None,
                 update_stats.clone(),
+                Some(concur_permit),
                 NO_ACK,
                 pool.clone(),
             ));
@@ -308,7 +466,49 @@ impl SourceIndexingContext {
                 }
             }
         }
-
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_retry_time_backoff() {
+        let failure_time = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        assert_eq!(
+            failure_retry_time(failure_time, 1) - failure_time,
+            chrono::TimeDelta::seconds(30)
+        );
+        assert_eq!(
+            failure_retry_time(failure_time, 3) - failure_time,
+            chrono::TimeDelta::seconds(120)
+        );
+        // Capped by the max backoff.
+        assert_eq!(
+            failure_retry_time(failure_time, 100) - failure_time,
+            chrono::TimeDelta::seconds(3600)
+        );
+    }
+
+    #[test]
+    fn test_failed_op_name_from_error() {
+        let error = anyhow!("connection reset")
+            .context(TransformOpErrorContext {
+                op_name: "embed".to_string(),
+            })
+            .context("Evaluating ForEach op `chunks`")
+            .context("Evaluating in scope with key 1");
+        assert_eq!(
+            error
+                .downcast_ref::<TransformOpErrorContext>()
+                .map(|c| c.op_name.as_str()),
+            Some("embed")
+        );
+        assert_eq!(
+            format!("{error:#}"),
+            "Evaluating in scope with key 1: Evaluating ForEach op `chunks`: Evaluating Transform op `embed`: connection reset"
+        );
+    }
+}
@@ -74,6 +74,16 @@ impl UpdateStats {
         }
     }
 
+    /// Adds counts from `other` into this one.
+    pub fn merge(&self, other: &Self) {
+        self.num_no_change.inc(other.num_no_change.get());
+        self.num_insertions.inc(other.num_insertions.get());
+        self.num_deletions.inc(other.num_deletions.get());
+        self.num_updates.inc(other.num_updates.get());
+        self.num_reprocesses.inc(other.num_reprocesses.get());
+        self.num_errors.inc(other.num_errors.get());
+    }
+
     pub fn is_zero(&self) -> bool {
         self.num_no_change.get() == 0
             && self.num_insertions.get() == 0
@@ -118,6 +128,71 @@ impl std::fmt::Display for UpdateStats {
     }
 }
 
+/// LLM usage of an op.
+#[derive(Debug, Serialize, Default, Clone)]
+pub struct LlmUsageStats {
+    pub num_requests: Counter,
+    pub input_tokens: Counter,
+    pub output_tokens: Counter,
+}
+
+impl LlmUsageStats {
+    pub fn total_tokens(&self) -> i64 {
+        self.input_tokens.get() + self.output_tokens.get()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num_requests.get() == 0 && self.total_tokens() == 0
+    }
+}
+
+impl std::fmt::Display for LlmUsageStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} LLM requests ({} input tokens, {} output tokens)",
+            self.num_requests, self.input_tokens, self.output_tokens
+        )
+    }
+}
+
+/// LLM usage of an op during an update.
+pub struct OpLlmUsage {
+    pub op_name: String,
+    /// Usage of the op since the flow is analyzed, used to identify the op.
+    op_stats: Arc<LlmUsageStats>,
+    pub stats: LlmUsageStats,
+}
+
+/// LLM usage of ops during an update, e.g. by a `FlowLiveUpdater`.
+/// Kept separately from the ops' own stats, as multiple updates of a flow may run at the same time.
+#[derive(Default)]
+pub struct UpdateLlmUsage {
+    pub ops: Vec<OpLlmUsage>,
+}
+
+impl UpdateLlmUsage {
+    pub fn add_op(&mut self, op_name: String, op_stats: Arc<LlmUsageStats>) {
+        self.ops.push(OpLlmUsage {
+            op_name,
+            op_stats,
+            stats: LlmUsageStats::default(),
+        });
+    }
+
+    /// Stats of the op during the update, if it's part of the update.
+    pub fn op_stats(&self, op_stats: &Arc<LlmUsageStats>) -> Option<&LlmUsageStats> {
+        self.ops
+            .iter()
+            .find(|op| Arc::ptr_eq(&op.op_stats, op_stats))
+            .map(|op| &op.stats)
+    }
+
+    pub fn total_tokens(&self) -> i64 {
+        self.ops.iter().map(|op| op.stats.total_tokens()).sum()
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct SourceUpdateInfo {
     pub source_name: String,
@@ -130,9 +205,24 @@ impl std::fmt::Display for SourceUpdateInfo {
     }
 }
 
+#[derive(Debug, Serialize)]
+pub struct OpUpdateInfo {
+    pub op_name: String,
+    pub llm_usage: LlmUsageStats,
+}
+
+impl std::fmt::Display for OpUpdateInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.op_name, self.llm_usage)
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct IndexUpdateInfo {
     pub sources: Vec<SourceUpdateInfo>,
+    /// Ops with LLM usage during the update.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ops: Vec<OpUpdateInfo>,
 }
 
 impl std::fmt::Display for IndexUpdateInfo {
@@ -140,6 +230,9 @@ impl std::fmt::Display for IndexUpdateInfo {
         for source in self.sources.iter() {
             writeln!(f, "{}", source)?;
         }
+        for op in self.ops.iter() {
+            writeln!(f, "{}", op)?;
+        }
         Ok(())
     }
 }
@@ -3,6 +3,7 @@ mod builder;
 mod execution;
 mod lib_context;
 mod llm;
+mod metrics;
 mod ops;
 mod prelude;
 mod py;
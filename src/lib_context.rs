@@ -1,6 +1,7 @@
 use crate::prelude::*;
 
 use crate::builder::AnalyzedFlow;
+use crate::execution::query::SimpleSemanticsQueryHandler;
 use crate::execution::source_indexer::SourceIndexingContext;
 use crate::service::error::ApiError;
 use crate::settings;
@@ -97,6 +98,7 @@ impl FlowExecutionContext {
 pub struct FlowContext {
     pub flow: Arc<AnalyzedFlow>,
     execution_ctx: Arc<tokio::sync::RwLock<FlowExecutionContext>>,
+    pub query_handlers: RwLock<BTreeMap<String, Arc<SimpleSemanticsQueryHandler>>>,
 }
 
 impl FlowContext {
@@ -114,6 +116,7 @@ impl FlowContext {
         Ok(Self {
             flow,
             execution_ctx,
+            query_handlers: RwLock::new(BTreeMap::new()),
         })
     }
 
@@ -143,6 +146,20 @@ impl FlowContext {
         Ok(execution_ctx)
     }
 
+    pub fn get_query_handler(&self, name: &str) -> Result<Arc<SimpleSemanticsQueryHandler>> {
+        let query_handlers = self.query_handlers.read().unwrap();
+        let query_handler = query_handlers
+            .get(name)
+            .ok_or_else(|| {
+                ApiError::new(
+                    &format!("Query handler not found: {name}"),
+                    StatusCode::NOT_FOUND,
+                )
+            })?
+            .clone();
+        Ok(query_handler)
+    }
+
     pub fn get_execution_ctx_for_setup(&self) -> &tokio::sync::RwLock<FlowExecutionContext> {
         &self.execution_ctx
     }
@@ -1,5 +1,6 @@
 use crate::llm::{
-    LlmGenerateRequest, LlmGenerateResponse, LlmGenerationClient, OutputFormat, ToJsonSchemaOptions,
+    LlmGenerateRequest, LlmGenerateResponse, LlmGenerationClient, LlmUsage, OutputFormat,
+    ToJsonSchemaOptions,
 };
 use anyhow::{Context, Result, bail};
 use async_trait::async_trait;
@@ -91,6 +92,11 @@ impl LlmGenerationClient for Client {
         // Debug print full response
         // println!("Anthropic API full response: {resp_json:?}");
 
+        let usage = resp_json.get("usage").map(|usage| LlmUsage {
+            input_tokens: usage["input_tokens"].as_u64().unwrap_or(0),
+            output_tokens: usage["output_tokens"].as_u64().unwrap_or(0),
+        });
+
         let resp_content = &resp_json["content"];
         let tool_name = "report_result";
         let mut extracted_json: Option<Value> = None;
@@ -140,7 +146,7 @@ impl LlmGenerationClient for Client {
             }
         };
 
-        Ok(LlmGenerateResponse { text })
+        Ok(LlmGenerateResponse { text, usage })
     }
 
     fn json_schema_options(&self) -> ToJsonSchemaOptions {
@@ -1,8 +1,8 @@
 use crate::prelude::*;
 
 use crate::llm::{
-    LlmEmbeddingClient, LlmGenerateRequest, LlmGenerateResponse, LlmGenerationClient, OutputFormat,
-    ToJsonSchemaOptions,
+    LlmEmbeddingClient, LlmGenerateRequest, LlmGenerateResponse, LlmGenerationClient, LlmUsage,
+    OutputFormat, ToJsonSchemaOptions,
 };
 use phf::phf_map;
 use serde_json::Value;
@@ -14,6 +14,8 @@ static DEFAULT_EMBEDDING_DIMENSIONS: phf::Map<&str, u32> = phf_map! {
     "embedding-001" => 768,
 };
 
+const MAX_EMBEDDING_BATCH_SIZE: usize = 100;
+
 pub struct Client {
     api_key: String,
     client: reqwest::Client,
@@ -114,13 +116,17 @@ impl LlmGenerationClient for Client {
         if let Some(error) = resp_json.get("error") {
             bail!("Gemini API error: {:?}", error);
         }
+        let usage = resp_json.get("usageMetadata").map(|usage| LlmUsage {
+            input_tokens: usage["promptTokenCount"].as_u64().unwrap_or(0),
+            output_tokens: usage["candidatesTokenCount"].as_u64().unwrap_or(0),
+        });
         let mut resp_json = resp_json;
         let text = match &mut resp_json["candidates"][0]["content"]["parts"][0]["text"] {
             Value::String(s) => std::mem::take(s),
             _ => bail!("No text in response"),
         };
 
-        Ok(LlmGenerateResponse { text })
+        Ok(LlmGenerateResponse { text, usage })
     }
 
     fn json_schema_options(&self) -> ToJsonSchemaOptions {
@@ -138,8 +144,8 @@ struct ContentEmbedding {
     values: Vec<f32>,
 }
 #[derive(Deserialize)]
-struct EmbedContentResponse {
-    embedding: ContentEmbedding,
+struct BatchEmbedContentsResponse {
+    embeddings: Vec<ContentEmbedding>,
 }
 
 #[async_trait]
@@ -148,31 +154,53 @@ impl LlmEmbeddingClient for Client {
         &self,
         request: super::LlmEmbeddingRequest<'req>,
     ) -> Result<super::LlmEmbeddingResponse> {
-        let url = self.get_api_url(request.model, "embedContent");
-        let mut payload = serde_json::json!({
-            "model": request.model,
-            "content": { "parts": [{ "text": request.text }] },
-        });
-        if let Some(task_type) = request.task_type {
-            payload["taskType"] = serde_json::Value::String(task_type.into());
-        }
-        let resp = self
-            .client
-            .post(&url)
-            .json(&payload)
-            .send()
-            .await
-            .context("HTTP error")?;
-        if !resp.status().is_success() {
-            bail!(
-                "Gemini API error: {:?}\n{}\n",
-                resp.status(),
-                resp.text().await?
-            );
+        let url = self.get_api_url(request.model, "batchEmbedContents");
+        let model_name = format!("models/{}", request.model);
+        let mut embeddings = Vec::with_capacity(request.texts.len());
+        for texts in request.texts.chunks(MAX_EMBEDDING_BATCH_SIZE) {
+            let requests = texts
+                .iter()
+                .map(|text| {
+                    let mut req = serde_json::json!({
+                        "model": model_name,
+                        "content": { "parts": [{ "text": text }] },
+                    });
+                    if let Some(task_type) = &request.task_type {
+                        req["taskType"] = serde_json::Value::String(task_type.to_string());
+                    }
+                    req
+                })
+                .collect::<Vec<_>>();
+            let payload = serde_json::json!({ "requests": requests });
+            let resp = self
+                .client
+                .post(&url)
+                .json(&payload)
+                .send()
+                .await
+                .context("HTTP error")?;
+            if !resp.status().is_success() {
+                bail!(
+                    "Gemini API error: {:?}\n{}\n",
+                    resp.status(),
+                    resp.text().await?
+                );
+            }
+            let embedding_resp: BatchEmbedContentsResponse =
+                resp.json().await.context("Invalid JSON")?;
+            if embedding_resp.embeddings.len() != texts.len() {
+                bail!(
+                    "Gemini returned {} embeddings for {} texts",
+                    embedding_resp.embeddings.len(),
+                    texts.len()
+                );
+            }
+            embeddings.extend(embedding_resp.embeddings.into_iter().map(|e| e.values));
         }
-        let embedding_resp: EmbedContentResponse = resp.json().await.context("Invalid JSON")?;
+        // `batchEmbedContents` doesn't report token usage.
         Ok(super::LlmEmbeddingResponse {
-            embedding: embedding_resp.embedding.values,
+            embeddings,
+            usage: None,
         })
     }
 
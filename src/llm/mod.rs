@@ -1,6 +1,8 @@
 use crate::prelude::*;
 
 use crate::base::json_schema::ToJsonSchemaOptions;
+use crate::execution::stats::{LlmUsageStats, UpdateLlmUsage};
+use crate::metrics;
 use schemars::schema::SchemaObject;
 use std::borrow::Cow;
 
@@ -38,9 +40,25 @@ pub struct LlmGenerateRequest<'a> {
     pub output_format: Option<OutputFormat<'a>>,
 }
 
+/// Number of tokens consumed by a request, as reported by the provider.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LlmUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl std::ops::AddAssign for LlmUsage {
+    fn add_assign(&mut self, rhs: Self) {
+        self.input_tokens += rhs.input_tokens;
+        self.output_tokens += rhs.output_tokens;
+    }
+}
+
 #[derive(Debug)]
 pub struct LlmGenerateResponse {
     pub text: String,
+    /// None if the provider doesn't report usage.
+    pub usage: Option<LlmUsage>,
 }
 
 #[async_trait]
@@ -56,17 +74,21 @@ pub trait LlmGenerationClient: Send + Sync {
 #[derive(Debug)]
 pub struct LlmEmbeddingRequest<'a> {
     pub model: &'a str,
-    pub text: Cow<'a, str>,
+    pub texts: Vec<Cow<'a, str>>,
     pub output_dimension: Option<u32>,
     pub task_type: Option<Cow<'a, str>>,
 }
 
 pub struct LlmEmbeddingResponse {
-    pub embedding: Vec<f32>,
+    /// One embedding for each text in the request, in the same order.
+    pub embeddings: Vec<Vec<f32>>,
+    /// None if the provider doesn't report usage.
+    pub usage: Option<LlmUsage>,
 }
 
 #[async_trait]
 pub trait LlmEmbeddingClient: Send + Sync {
+    /// Texts exceeding the provider's max batch size are split into multiple API calls.
     async fn embed_text<'req>(
         &self,
         request: LlmEmbeddingRequest<'req>,
@@ -75,6 +97,125 @@ pub trait LlmEmbeddingClient: Send + Sync {
     fn get_default_embedding_dimension(&self, model: &str) -> Option<u32>;
 }
 
+tokio::task_local! {
+    /// LLM usage stats of the op being built.
+    /// Clients created within its scope record their usage into it.
+    pub static OP_LLM_USAGE_STATS: Arc<LlmUsageStats>;
+
+    /// LLM usage of the update running the current task, if any.
+    /// Requests sent within its scope are also recorded into it, besides the op's own stats.
+    pub static UPDATE_LLM_USAGE: Option<Arc<UpdateLlmUsage>>;
+}
+
+/// Keeps the LLM usage scope of the current update for the future, e.g. when it's spawned as a separate task.
+pub fn in_current_update_scope<F: Future>(fut: F) -> impl Future<Output = F::Output> {
+    UPDATE_LLM_USAGE.scope(
+        UPDATE_LLM_USAGE
+            .try_with(|usage| usage.clone())
+            .ok()
+            .flatten(),
+        fut,
+    )
+}
+
+/// Wraps a client to record metrics and usage of requests sent by it.
+struct MeteredClient<C: ?Sized> {
+    api_type: LlmApiType,
+    client: Box<C>,
+    usage_stats: Option<Arc<LlmUsageStats>>,
+}
+
+impl<C: ?Sized> MeteredClient<C> {
+    fn new(api_type: LlmApiType, client: Box<C>) -> Self {
+        Self {
+            api_type,
+            client,
+            usage_stats: OP_LLM_USAGE_STATS.try_with(|stats| stats.clone()).ok(),
+        }
+    }
+
+    fn record_request(
+        &self,
+        request_type: &str,
+        model: &str,
+        usage: std::result::Result<Option<LlmUsage>, &anyhow::Error>,
+    ) {
+        let api_type = format!("{:?}", self.api_type);
+        metrics::LLM_REQUESTS
+            .with_label_values(&[
+                api_type.as_str(),
+                request_type,
+                if usage.is_ok() { "success" } else { "error" },
+            ])
+            .inc();
+        if let Ok(Some(usage)) = &usage {
+            for (token_type, count) in [
+                ("input", usage.input_tokens),
+                ("output", usage.output_tokens),
+            ] {
+                metrics::LLM_TOKENS
+                    .with_label_values(&[api_type.as_str(), request_type, model, token_type])
+                    .inc_by(count);
+            }
+        }
+        let Some(usage_stats) = &self.usage_stats else {
+            return;
+        };
+        let usage = usage.ok().flatten();
+        Self::record_usage(usage_stats, usage.as_ref());
+        let _ = UPDATE_LLM_USAGE.try_with(|update_usage| {
+            if let Some(stats) = update_usage
+                .as_ref()
+                .and_then(|update_usage| update_usage.op_stats(usage_stats))
+            {
+                Self::record_usage(stats, usage.as_ref());
+            }
+        });
+    }
+
+    fn record_usage(stats: &LlmUsageStats, usage: Option<&LlmUsage>) {
+        stats.num_requests.inc(1);
+        if let Some(usage) = usage {
+            stats.input_tokens.inc(usage.input_tokens as i64);
+            stats.output_tokens.inc(usage.output_tokens as i64);
+        }
+    }
+}
+
+#[async_trait]
+impl LlmGenerationClient for MeteredClient<dyn LlmGenerationClient> {
+    async fn generate<'req>(
+        &self,
+        request: LlmGenerateRequest<'req>,
+    ) -> Result<LlmGenerateResponse> {
+        let model = request.model;
+        let result = self.client.generate(request).await;
+        self.record_request("generate", model, result.as_ref().map(|r| r.usage));
+        result
+    }
+
+    fn json_schema_options(&self) -> ToJsonSchemaOptions {
+        self.client.json_schema_options()
+    }
+}
+
+#[async_trait]
+impl LlmEmbeddingClient for MeteredClient<dyn LlmEmbeddingClient> {
+    async fn embed_text<'req>(
+        &self,
+        request: LlmEmbeddingRequest<'req>,
+    ) -> Result<LlmEmbeddingResponse> {
+        let model = request.model;
+        let result = self.client.embed_text(request).await;
+        self.record_request("embed", model, result.as_ref().map(|r| r.usage));
+        result
+    }
+
+    fn get_default_embedding_dimension(&self, model: &str) -> Option<u32> {
+        self.client.get_default_embedding_dimension(model)
+    }
+}
+
 mod anthropic;
 mod gemini;
 mod litellm;
@@ -109,14 +250,17 @@ pub async fn new_llm_generation_client(
             api_bail!("Voyage is not supported for generation")
         }
     };
-    Ok(client)
+    Ok(Box::new(MeteredClient::new(api_type, client)))
 }
 
-pub fn new_llm_embedding_client(
+pub async fn new_llm_embedding_client(
     api_type: LlmApiType,
     address: Option<String>,
 ) -> Result<Box<dyn LlmEmbeddingClient>> {
     let client = match api_type {
+        LlmApiType::Ollama => {
+            Box::new(ollama::Client::new(address).await?) as Box<dyn LlmEmbeddingClient>
+        }
         LlmApiType::Gemini => {
             Box::new(gemini::Client::new(address)?) as Box<dyn LlmEmbeddingClient>
         }
@@ -126,12 +270,98 @@ pub fn new_llm_embedding_client(
         LlmApiType::Voyage => {
             Box::new(voyage::Client::new(address)?) as Box<dyn LlmEmbeddingClient>
         }
-        LlmApiType::Ollama
-        | LlmApiType::OpenRouter
-        | LlmApiType::LiteLlm
-        | LlmApiType::Anthropic => {
+        LlmApiType::OpenRouter | LlmApiType::LiteLlm | LlmApiType::Anthropic => {
             api_bail!("Embedding is not supported for API type {:?}", api_type)
         }
     };
-    Ok(client)
+    Ok(Box::new(MeteredClient::new(api_type, client)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeGenerationClient;
+
+    #[async_trait]
+    impl LlmGenerationClient for FakeGenerationClient {
+        async fn generate<'req>(
+            &self,
+            request: LlmGenerateRequest<'req>,
+        ) -> Result<LlmGenerateResponse> {
+            Ok(LlmGenerateResponse {
+                text: request.user_prompt.into_owned(),
+                usage: Some(LlmUsage {
+                    input_tokens: 3,
+                    output_tokens: 5,
+                }),
+            })
+        }
+
+        fn json_schema_options(&self) -> ToJsonSchemaOptions {
+            ToJsonSchemaOptions {
+                fields_always_required: false,
+                supports_format: true,
+                extract_descriptions: false,
+                top_level_must_be_object: false,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metered_client_records_usage() {
+        let usage_stats = Arc::new(LlmUsageStats::default());
+        let client = OP_LLM_USAGE_STATS.sync_scope(usage_stats.clone(), || {
+            MeteredClient::<dyn LlmGenerationClient>::new(
+                LlmApiType::OpenAi,
+                Box::new(FakeGenerationClient),
+            )
+        });
+        for _ in 0..2 {
+            client
+                .generate(LlmGenerateRequest {
+                    model: "fake-model",
+                    system_prompt: None,
+                    user_prompt: "hello".into(),
+                    output_format: None,
+                })
+                .await
+                .unwrap();
+        }
+        assert_eq!(usage_stats.num_requests.get(), 2);
+        assert_eq!(usage_stats.input_tokens.get(), 6);
+        assert_eq!(usage_stats.output_tokens.get(), 10);
+
+        // Requests sent within an update's scope (including tasks spawned in it) are also recorded
+        // into the update's usage.
+        let mut update_usage = UpdateLlmUsage::default();
+        update_usage.add_op("op".to_string(), usage_stats.clone());
+        let update_usage = Arc::new(update_usage);
+        UPDATE_LLM_USAGE
+            .scope(Some(update_usage.clone()), async move {
+                tokio::spawn(in_current_update_scope(async move {
+                    client
+                        .generate(LlmGenerateRequest {
+                            model: "fake-model",
+                            system_prompt: None,
+                            user_prompt: "hello".into(),
+                            output_format: None,
+                        })
+                        .await
+                }))
+                .await
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(update_usage.total_tokens(), 8);
+        assert_eq!(usage_stats.num_requests.get(), 3);
+
+        // Clients created out of the scope don't record usage into op stats.
+        let client = MeteredClient::<dyn LlmGenerationClient>::new(
+            LlmApiType::OpenAi,
+            Box::new(FakeGenerationClient),
+        );
+        assert!(client.usage_stats.is_none());
+    }
 }
@@ -1,10 +1,24 @@
 use crate::prelude::*;
 
-use super::LlmGenerationClient;
+use super::{LlmEmbeddingClient, LlmGenerationClient};
+use phf::phf_map;
 use schemars::schema::SchemaObject;
 
+static DEFAULT_EMBEDDING_DIMENSIONS: phf::Map<&str, u32> = phf_map! {
+    "nomic-embed-text" => 768,
+    "mxbai-embed-large" => 1024,
+    "all-minilm" => 384,
+    "snowflake-arctic-embed" => 1024,
+    "snowflake-arctic-embed2" => 1024,
+    "bge-m3" => 1024,
+    "bge-large" => 1024,
+    "paraphrase-multilingual" => 768,
+    "granite-embedding" => 384,
+};
+
 pub struct Client {
     generate_url: String,
+    embed_url: String,
     reqwest_client: reqwest::Client,
 }
 
@@ -26,6 +40,20 @@ struct OllamaRequest<'a> {
 #[derive(Debug, Deserialize)]
 struct OllamaResponse {
     pub response: String,
+    pub prompt_eval_count: Option<u64>,
+    pub eval_count: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaEmbeddingRequest<'a> {
+    pub model: &'a str,
+    pub input: &'a [Cow<'a, str>],
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaEmbeddingResponse {
+    pub embeddings: Vec<Vec<f32>>,
+    pub prompt_eval_count: Option<u64>,
 }
 
 const OLLAMA_DEFAULT_ADDRESS: &str = "http://localhost:11434";
@@ -38,6 +66,7 @@ impl Client {
         };
         Ok(Self {
             generate_url: format!("{}/api/generate", address),
+            embed_url: format!("{}/api/embed", address),
             reqwest_client: reqwest::Client::new(),
         })
     }
@@ -76,6 +105,10 @@ impl LlmGenerationClient for Client {
         let json: OllamaResponse = res.json().await?;
         Ok(super::LlmGenerateResponse {
             text: json.response,
+            usage: Some(super::LlmUsage {
+                input_tokens: json.prompt_eval_count.unwrap_or(0),
+                output_tokens: json.eval_count.unwrap_or(0),
+            }),
         })
     }
 
@@ -88,3 +121,50 @@ impl LlmGenerationClient for Client {
         }
     }
 }
+
+#[async_trait]
+impl LlmEmbeddingClient for Client {
+    async fn embed_text<'req>(
+        &self,
+        request: super::LlmEmbeddingRequest<'req>,
+    ) -> Result<super::LlmEmbeddingResponse> {
+        let req = OllamaEmbeddingRequest {
+            model: request.model,
+            input: &request.texts,
+        };
+        let res = self
+            .reqwest_client
+            .post(self.embed_url.as_str())
+            .json(&req)
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            bail!(
+                "Ollama API error: {:?}\n{}\n",
+                res.status(),
+                res.text().await?
+            );
+        }
+        let json: OllamaEmbeddingResponse = res.json().await?;
+        if json.embeddings.len() != request.texts.len() {
+            bail!(
+                "Ollama returned {} embeddings for {} texts",
+                json.embeddings.len(),
+                request.texts.len()
+            );
+        }
+        Ok(super::LlmEmbeddingResponse {
+            embeddings: json.embeddings,
+            usage: json.prompt_eval_count.map(|input_tokens| super::LlmUsage {
+                input_tokens,
+                output_tokens: 0,
+            }),
+        })
+    }
+
+    fn get_default_embedding_dimension(&self, model: &str) -> Option<u32> {
+        // `ollama pull` tags the model with `latest` by default.
+        let model = model.strip_suffix(":latest").unwrap_or(model);
+        DEFAULT_EMBEDDING_DIMENSIONS.get(model).copied()
+    }
+}
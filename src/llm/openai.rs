@@ -1,7 +1,7 @@
 use crate::api_bail;
 
 use super::{LlmEmbeddingClient, LlmGenerationClient};
-use anyhow::Result;
+use anyhow::{Result, bail};
 use async_openai::{
     Client as OpenAIClient,
     config::OpenAIConfig,
@@ -21,6 +21,8 @@ static DEFAULT_EMBEDDING_DIMENSIONS: phf::Map<&str, u32> = phf_map! {
     "text-embedding-ada-002" => 1536,
 };
 
+const MAX_EMBEDDING_BATCH_SIZE: usize = 2048;
+
 pub struct Client {
     client: async_openai::Client<OpenAIConfig>,
 }
@@ -96,6 +98,11 @@ impl LlmGenerationClient for Client {
         // Send request and get response
         let response = self.client.chat().create(request).await?;
 
+        let usage = response.usage.map(|usage| super::LlmUsage {
+            input_tokens: usage.prompt_tokens as u64,
+            output_tokens: usage.completion_tokens as u64,
+        });
+
         // Extract the response text from the first choice
         let text = response
             .choices
@@ -104,7 +111,7 @@ impl LlmGenerationClient for Client {
             .and_then(|choice| choice.message.content)
             .ok_or_else(|| anyhow::anyhow!("No response from OpenAI"))?;
 
-        Ok(super::LlmGenerateResponse { text })
+        Ok(super::LlmGenerateResponse { text, usage })
     }
 
     fn json_schema_options(&self) -> super::ToJsonSchemaOptions {
@@ -123,23 +130,39 @@ impl LlmEmbeddingClient for Client {
         &self,
         request: super::LlmEmbeddingRequest<'req>,
     ) -> Result<super::LlmEmbeddingResponse> {
-        let response = self
-            .client
-            .embeddings()
-            .create(CreateEmbeddingRequest {
-                model: request.model.to_string(),
-                input: EmbeddingInput::String(request.text.to_string()),
-                dimensions: request.output_dimension,
-                ..Default::default()
-            })
-            .await?;
+        let mut embeddings = Vec::with_capacity(request.texts.len());
+        let mut usage = super::LlmUsage::default();
+        for texts in request.texts.chunks(MAX_EMBEDDING_BATCH_SIZE) {
+            let response = self
+                .client
+                .embeddings()
+                .create(CreateEmbeddingRequest {
+                    model: request.model.to_string(),
+                    input: EmbeddingInput::StringArray(
+                        texts.iter().map(|t| t.to_string()).collect(),
+                    ),
+                    dimensions: request.output_dimension,
+                    ..Default::default()
+                })
+                .await?;
+            if response.data.len() != texts.len() {
+                bail!(
+                    "OpenAI returned {} embeddings for {} texts",
+                    response.data.len(),
+                    texts.len()
+                );
+            }
+            usage += super::LlmUsage {
+                input_tokens: response.usage.prompt_tokens as u64,
+                output_tokens: 0,
+            };
+            let mut data = response.data;
+            data.sort_by_key(|e| e.index);
+            embeddings.extend(data.into_iter().map(|e| e.embedding));
+        }
         Ok(super::LlmEmbeddingResponse {
-            embedding: response
-                .data
-                .into_iter()
-                .next()
-                .ok_or_else(|| anyhow::anyhow!("No embedding returned from OpenAI"))?
-                .embedding,
+            embeddings,
+            usage: Some(usage),
         })
     }
 
@@ -1,6 +1,6 @@
 use crate::prelude::*;
 
-use crate::llm::{LlmEmbeddingClient, LlmEmbeddingRequest, LlmEmbeddingResponse};
+use crate::llm::{LlmEmbeddingClient, LlmEmbeddingRequest, LlmEmbeddingResponse, LlmUsage};
 use phf::phf_map;
 
 static DEFAULT_EMBEDDING_DIMENSIONS: phf::Map<&str, u32> = phf_map! {
@@ -27,6 +27,8 @@ static DEFAULT_EMBEDDING_DIMENSIONS: phf::Map<&str, u32> = phf_map! {
     "voyage-lite-01-instruct" => 1024,
 };
 
+const MAX_EMBEDDING_BATCH_SIZE: usize = 1000;
+
 pub struct Client {
     api_key: String,
     client: reqwest::Client,
@@ -51,11 +53,18 @@ impl Client {
 #[derive(Deserialize)]
 struct EmbeddingData {
     embedding: Vec<f32>,
+    index: usize,
+}
+
+#[derive(Deserialize)]
+struct EmbedUsage {
+    total_tokens: u64,
 }
 
 #[derive(Deserialize)]
 struct EmbedResponse {
     data: Vec<EmbeddingData>,
+    usage: Option<EmbedUsage>,
 }
 
 #[async_trait]
@@ -66,41 +75,55 @@ impl LlmEmbeddingClient for Client {
     ) -> Result<LlmEmbeddingResponse> {
         let url = "https://api.voyageai.com/v1/embeddings";
 
-        let mut payload = serde_json::json!({
-            "input": request.text,
-            "model": request.model,
-        });
-
-        if let Some(task_type) = request.task_type {
-            payload["input_type"] = serde_json::Value::String(task_type.into());
+        let mut embeddings = Vec::with_capacity(request.texts.len());
+        let mut usage: Option<LlmUsage> = None;
+        for texts in request.texts.chunks(MAX_EMBEDDING_BATCH_SIZE) {
+            let mut payload = serde_json::json!({
+                "input": texts,
+                "model": request.model,
+            });
+
+            if let Some(task_type) = &request.task_type {
+                payload["input_type"] = serde_json::Value::String(task_type.to_string());
+            }
+
+            let resp = self
+                .client
+                .post(url)
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .json(&payload)
+                .send()
+                .await
+                .context("HTTP error")?;
+
+            if !resp.status().is_success() {
+                bail!(
+                    "Voyage AI API error: {:?}\n{}\n",
+                    resp.status(),
+                    resp.text().await?
+                );
+            }
+
+            let mut embedding_resp: EmbedResponse = resp.json().await.context("Invalid JSON")?;
+
+            if embedding_resp.data.len() != texts.len() {
+                bail!(
+                    "Voyage AI returned {} embeddings for {} texts",
+                    embedding_resp.data.len(),
+                    texts.len()
+                );
+            }
+            if let Some(embed_usage) = &embedding_resp.usage {
+                *usage.get_or_insert_default() += LlmUsage {
+                    input_tokens: embed_usage.total_tokens,
+                    output_tokens: 0,
+                };
+            }
+            embedding_resp.data.sort_by_key(|d| d.index);
+            embeddings.extend(embedding_resp.data.into_iter().map(|d| d.embedding));
         }
 
-        let resp = self
-            .client
-            .post(url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&payload)
-            .send()
-            .await
-            .context("HTTP error")?;
-
-        if !resp.status().is_success() {
-            bail!(
-                "Voyage AI API error: {:?}\n{}\n",
-                resp.status(),
-                resp.text().await?
-            );
-        }
-
-        let embedding_resp: EmbedResponse = resp.json().await.context("Invalid JSON")?;
-
-        if embedding_resp.data.is_empty() {
-            bail!("No embedding data in response");
-        }
-
-        Ok(LlmEmbeddingResponse {
-            embedding: embedding_resp.data[0].embedding.clone(),
-        })
+        Ok(LlmEmbeddingResponse { embeddings, usage })
     }
 
     fn get_default_embedding_dimension(&self, model: &str) -> Option<u32> {
@@ -0,0 +1,152 @@
+use crate::prelude::*;
+
+use crate::execution::stats::UpdateStats;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry, TextEncoder,
+    core::Collector,
+};
+use std::sync::LazyLock;
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+fn register<C: Collector + Clone + 'static>(collector: C) -> C {
+    REGISTRY
+        .register(Box::new(collector.clone()))
+        .expect("metric is registered only once");
+    collector
+}
+
+/// Number of processed source rows, by outcome.
+pub static SOURCE_ROWS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(
+        IntCounterVec::new(
+            Opts::new(
+                "cocoindex_source_rows_total",
+                "Number of source rows processed, by outcome.",
+            ),
+            &["flow", "source", "outcome"],
+        )
+        .unwrap(),
+    )
+});
+
+/// Latency of transform ops, only counting actual evaluations (not cache hits).
+pub static TRANSFORM_OP_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(
+        HistogramVec::new(
+            HistogramOpts::new(
+                "cocoindex_transform_op_duration_seconds",
+                "Time spent in evaluating transform ops.",
+            )
+            .buckets(vec![
+                0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+            ]),
+            &["flow", "op", "function"],
+        )
+        .unwrap(),
+    )
+});
+
+/// Lookups on the evaluation cache of transform ops, by result (`hit` or `miss`).
+pub static EVALUATION_CACHE_LOOKUPS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(
+        IntCounterVec::new(
+            Opts::new(
+                "cocoindex_evaluation_cache_lookups_total",
+                "Number of lookups on the evaluation cache of transform ops.",
+            ),
+            &["flow", "op", "result"],
+        )
+        .unwrap(),
+    )
+});
+
+/// Counters of evaluation cache lookups for a specific transform op.
+pub struct CacheLookupCounters {
+    pub hit: IntCounter,
+    pub miss: IntCounter,
+}
+
+impl CacheLookupCounters {
+    pub fn new(flow_name: &str, op_name: &str) -> Self {
+        Self {
+            hit: EVALUATION_CACHE_LOOKUPS.with_label_values(&[flow_name, op_name, "hit"]),
+            miss: EVALUATION_CACHE_LOOKUPS.with_label_values(&[flow_name, op_name, "miss"]),
+        }
+    }
+}
+
+/// Requests sent to LLM APIs.
+pub static LLM_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(
+        IntCounterVec::new(
+            Opts::new(
+                "cocoindex_llm_requests_total",
+                "Number of requests sent to LLM APIs.",
+            ),
+            &["api_type", "request_type", "status"],
+        )
+        .unwrap(),
+    )
+});
+
+/// Tokens consumed by LLM requests, as reported by the providers.
+pub static LLM_TOKENS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(
+        IntCounterVec::new(
+            Opts::new(
+                "cocoindex_llm_tokens_total",
+                "Number of tokens consumed by LLM requests.",
+            ),
+            &["api_type", "request_type", "model", "token_type"],
+        )
+        .unwrap(),
+    )
+});
+
+pub fn record_source_row_stats(flow_name: &str, source_name: &str, stats: &UpdateStats) {
+    for (outcome, counter) in [
+        ("insertion", &stats.num_insertions),
+        ("deletion", &stats.num_deletions),
+        ("update", &stats.num_updates),
+        ("reprocess", &stats.num_reprocesses),
+        ("no_change", &stats.num_no_change),
+        ("error", &stats.num_errors),
+    ] {
+        let value = counter.get();
+        if value > 0 {
+            SOURCE_ROWS
+                .with_label_values(&[flow_name, source_name, outcome])
+                .inc_by(value as u64);
+        }
+    }
+}
+
+/// Encode all metrics in the Prometheus text exposition format.
+pub fn encode_text() -> Result<String> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_source_row_stats() {
+        let stats = UpdateStats::default();
+        stats.num_insertions.inc(2);
+        stats.num_errors.inc(1);
+        record_source_row_stats("test_flow", "test_source", &stats);
+
+        let text = encode_text().unwrap();
+        assert!(text.contains(
+            r#"cocoindex_source_rows_total{flow="test_flow",outcome="insertion",source="test_source"} 2"#
+        ));
+        assert!(text.contains(
+            r#"cocoindex_source_rows_total{flow="test_flow",outcome="error",source="test_source"} 1"#
+        ));
+        assert!(!text.contains(r#"outcome="deletion",source="test_source""#));
+    }
+}
@@ -11,6 +11,7 @@ use crate::base::schema::*;
 use crate::base::spec::*;
 use crate::builder::plan::AnalyzedValueMapping;
 use crate::setup;
+use crate::utils::batching;
 // SourceFactoryBase
 pub struct ResolvedOpArg {
     pub name: String,
@@ -178,6 +179,16 @@ pub trait SourceFactoryBase: SourceFactory + Send + Sync + 'static {
         context: &FlowInstanceContext,
     ) -> Result<EnrichedValueType>;
 
+    /// Resources needed by the source (e.g. triggers to capture changes), as declarations of export target kinds.
+    /// They're set up and dropped together with targets of the flow.
+    async fn get_setup_declarations(
+        &self,
+        _spec: &Self::Spec,
+        _context: &FlowInstanceContext,
+    ) -> Result<Vec<OpSpec>> {
+        Ok(vec![])
+    }
+
     async fn build_executor(
         self: Arc<Self>,
         spec: Self::Spec,
@@ -203,12 +214,14 @@ impl<T: SourceFactoryBase> SourceFactory for T {
         context: Arc<FlowInstanceContext>,
     ) -> Result<(
         EnrichedValueType,
+        Vec<OpSpec>,
         BoxFuture<'static, Result<Box<dyn SourceExecutor>>>,
     )> {
         let spec: T::Spec = serde_json::from_value(spec)?;
         let output_schema = self.get_output_schema(&spec, &context).await?;
+        let setup_declarations = self.get_setup_declarations(&spec, &context).await?;
         let executor = self.build_executor(spec, context);
-        Ok((output_schema, executor))
+        Ok((output_schema, setup_declarations, executor))
     }
 }
 
@@ -268,6 +281,80 @@ impl<T: SimpleFunctionFactoryBase> SimpleFunctionFactory for T {
     }
 }
 
+/// Implemented by function executors that evaluate multiple invocations at once more efficiently.
+/// Concurrent invocations (e.g. from different rows or ForEach iterations) are coalesced into batches.
+#[async_trait]
+pub trait BatchedFunctionExecutor: Send + Sync + Sized + 'static {
+    /// Returns one output for each invocation's args, in the same order.
+    async fn evaluate_batch(&self, args: Vec<Vec<value::Value>>) -> Result<Vec<value::Value>>;
+
+    fn enable_cache(&self) -> bool {
+        false
+    }
+
+    fn behavior_version(&self) -> Option<u32> {
+        None
+    }
+
+    /// Max number of invocations in a single batch. `None` means no limit.
+    fn max_batch_size(&self) -> Option<usize> {
+        None
+    }
+
+    fn into_fn_executor(self) -> Box<dyn SimpleFunctionExecutor> {
+        Box::new(BatchedFunctionExecutorWrapper::new(self))
+    }
+}
+
+struct BatchedFunctionExecutorRunner<E: BatchedFunctionExecutor>(E);
+
+#[async_trait]
+impl<E: BatchedFunctionExecutor> batching::Runner for BatchedFunctionExecutorRunner<E> {
+    type Input = Vec<value::Value>;
+    type Output = value::Value;
+
+    async fn run(&self, inputs: Vec<Vec<value::Value>>) -> Result<Vec<value::Value>> {
+        self.0.evaluate_batch(inputs).await
+    }
+}
+
+struct BatchedFunctionExecutorWrapper<E: BatchedFunctionExecutor> {
+    batcher: batching::Batcher<BatchedFunctionExecutorRunner<E>>,
+    enable_cache: bool,
+    behavior_version: Option<u32>,
+}
+
+impl<E: BatchedFunctionExecutor> BatchedFunctionExecutorWrapper<E> {
+    fn new(executor: E) -> Self {
+        let enable_cache = executor.enable_cache();
+        let behavior_version = executor.behavior_version();
+        let max_batch_size = executor.max_batch_size();
+        Self {
+            batcher: batching::Batcher::new(
+                BatchedFunctionExecutorRunner(executor),
+                max_batch_size,
+            ),
+            enable_cache,
+            behavior_version,
+        }
+    }
+}
+
+#[async_trait]
+impl<E: BatchedFunctionExecutor> SimpleFunctionExecutor for BatchedFunctionExecutorWrapper<E> {
+    async fn evaluate(&self, args: Vec<value::Value>) -> Result<value::Value> {
+        self.batcher.run(args).await
+    }
+
+    fn enable_cache(&self) -> bool {
+        self.enable_cache
+    }
+
+    fn behavior_version(&self) -> Option<u32> {
+        self.behavior_version
+    }
+}
+
 pub struct TypedExportDataCollectionBuildOutput<F: StorageFactoryBase + ?Sized> {
     pub export_context: BoxFuture<'static, Result<Arc<F::ExportContext>>>,
     pub setup_key: F::Key,
@@ -360,6 +447,13 @@ pub trait StorageFactoryBase: ExportTargetFactory + Send + Sync + 'static {
         setup_status: Vec<TypedResourceSetupChangeItem<'async_trait, Self>>,
         auth_registry: &Arc<AuthRegistry>,
     ) -> Result<()>;
+
+    fn build_query_target(
+        &self,
+        _export_context: Arc<Self::ExportContext>,
+    ) -> Result<Option<Arc<dyn QueryTarget>>> {
+        Ok(None)
+    }
 }
 
 #[async_trait]
@@ -518,6 +612,18 @@ impl<T: StorageFactoryBase> ExportTargetFactory for T {
         )
         .await
     }
+
+    fn build_query_target(
+        &self,
+        export_context: Arc<dyn Any + Send + Sync>,
+    ) -> Result<Option<Arc<dyn QueryTarget>>> {
+        StorageFactoryBase::build_query_target(
+            self,
+            export_context
+                .downcast::<T::ExportContext>()
+                .map_err(|_| invariance_violation())?,
+        )
+    }
 }
 fn from_json_combined_state<T: Debug + Clone + Serialize + DeserializeOwned>(
     existing_states: setup::CombinedState<serde_json::Value>,
@@ -23,7 +23,7 @@ struct Executor {
 }
 
 #[async_trait]
-impl SimpleFunctionExecutor for Executor {
+impl BatchedFunctionExecutor for Executor {
     fn behavior_version(&self) -> Option<u32> {
         Some(1)
     }
@@ -32,11 +32,14 @@ impl SimpleFunctionExecutor for Executor {
         true
     }
 
-    async fn evaluate(&self, input: Vec<Value>) -> Result<Value> {
-        let text = self.args.text.value(&input)?.as_str()?;
+    async fn evaluate_batch(&self, args: Vec<Vec<Value>>) -> Result<Vec<Value>> {
+        let texts = args
+            .iter()
+            .map(|input| Ok(Cow::Borrowed(&**self.args.text.value(input)?.as_str()?)))
+            .collect::<Result<Vec<_>>>()?;
         let req = LlmEmbeddingRequest {
             model: &self.spec.model,
-            text: Cow::Borrowed(text),
+            texts,
             output_dimension: self.spec.output_dimension,
             task_type: self
                 .spec
@@ -44,8 +47,8 @@ impl SimpleFunctionExecutor for Executor {
                 .as_ref()
                 .map(|s| Cow::Borrowed(s.as_str())),
         };
-        let embedding = self.args.client.embed_text(req).await?;
-        Ok(embedding.embedding.into())
+        let embeddings = self.args.client.embed_text(req).await?.embeddings;
+        Ok(embeddings.into_iter().map(|e| e.into()).collect())
     }
 }
 
@@ -67,7 +70,7 @@ impl SimpleFunctionFactoryBase for Factory {
         _context: &FlowInstanceContext,
     ) -> Result<(Self::ResolvedArgs, EnrichedValueType)> {
         let text = args_resolver.next_arg("text")?;
-        let client = new_llm_embedding_client(spec.api_type, spec.address.clone())?;
+        let client = new_llm_embedding_client(spec.api_type, spec.address.clone()).await?;
         let output_dimension = match spec.output_dimension {
             Some(output_dimension) => output_dimension,
             None => {
@@ -88,7 +91,7 @@ impl SimpleFunctionFactoryBase for Factory {
         args: Args,
         _context: Arc<FlowInstanceContext>,
     ) -> Result<Box<dyn SimpleFunctionExecutor>> {
-        Ok(Box::new(Executor { spec, args }))
+        Ok(Executor { spec, args }.into_fn_executor())
     }
 }
 
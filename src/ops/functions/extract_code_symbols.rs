@@ -0,0 +1,878 @@
+use crate::ops::sdk::*;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+use unicase::UniCase;
+
+use super::split_recursively::{TREE_SITTER_LANGUAGE_BY_LANG, TreesitterLanguageConfig};
+
+pub struct Args {
+    text: ResolvedOpArg,
+    language: Option<ResolvedOpArg>,
+}
+
+enum SymbolKind {
+    Fixed(&'static str),
+    /// Taken from text of the child in the field, e.g. `struct` or `class` for Swift.
+    FromField(&'static str),
+    /// The node isn't a symbol by itself, but scopes symbols inside, e.g. `impl` blocks in Rust.
+    ScopeOnly,
+}
+
+struct DefinitionConfig {
+    symbol_kind: SymbolKind,
+    name_field: &'static str,
+    /// Only treat the node as a definition if it has a body, e.g. `struct` in C.
+    body_required: bool,
+}
+
+struct SymbolLanguageConfig {
+    definitions: HashMap<&'static str, DefinitionConfig>,
+    separator: &'static str,
+    /// Docstrings are string literals at the beginning of the body, e.g. Python.
+    docstring_in_body: bool,
+}
+
+fn symbol(node_kind: &'static str, kind: &'static str) -> (&'static str, DefinitionConfig) {
+    (
+        node_kind,
+        DefinitionConfig {
+            symbol_kind: SymbolKind::Fixed(kind),
+            name_field: "name",
+            body_required: false,
+        },
+    )
+}
+
+fn symbol_with_body(
+    node_kind: &'static str,
+    kind: &'static str,
+) -> (&'static str, DefinitionConfig) {
+    (
+        node_kind,
+        DefinitionConfig {
+            symbol_kind: SymbolKind::Fixed(kind),
+            name_field: "name",
+            body_required: true,
+        },
+    )
+}
+
+fn add_language(
+    output: &mut HashMap<&'static str, Arc<SymbolLanguageConfig>>,
+    names: impl IntoIterator<Item = &'static str>,
+    separator: &'static str,
+    definitions: impl IntoIterator<Item = (&'static str, DefinitionConfig)>,
+) {
+    let config = Arc::new(SymbolLanguageConfig {
+        definitions: definitions.into_iter().collect(),
+        separator,
+        docstring_in_body: false,
+    });
+    for name in names {
+        if output.insert(name, config.clone()).is_some() {
+            panic!("Language `{name}` already exists");
+        }
+    }
+}
+
+const C_DEFINITIONS: [(&str, &str); 3] = [
+    ("struct_specifier", "struct"),
+    ("union_specifier", "union"),
+    ("enum_specifier", "enum"),
+];
+
+const JAVASCRIPT_DEFINITIONS: [(&str, &str); 4] = [
+    ("function_declaration", "function"),
+    ("generator_function_declaration", "function"),
+    ("class_declaration", "class"),
+    ("method_definition", "method"),
+];
+
+/// Keyed by names of languages in `TREE_SITTER_LANGUAGE_BY_LANG`.
+static SYMBOL_CONFIG_BY_LANG: LazyLock<HashMap<&'static str, Arc<SymbolLanguageConfig>>> =
+    LazyLock::new(|| {
+        let mut map = HashMap::new();
+        add_language(
+            &mut map,
+            ["C"],
+            ".",
+            C_DEFINITIONS
+                .into_iter()
+                .map(|(node_kind, kind)| symbol_with_body(node_kind, kind))
+                .chain([
+                    symbol("function_definition", "function"),
+                    symbol("type_definition", "type"),
+                ]),
+        );
+        add_language(
+            &mut map,
+            ["C++"],
+            "::",
+            C_DEFINITIONS
+                .into_iter()
+                .chain([("class_specifier", "class")])
+                .map(|(node_kind, kind)| symbol_with_body(node_kind, kind))
+                .chain([
+                    symbol("function_definition", "function"),
+                    symbol("type_definition", "type"),
+                    symbol("namespace_definition", "namespace"),
+                ]),
+        );
+        add_language(
+            &mut map,
+            ["C#"],
+            ".",
+            [
+                symbol("namespace_declaration", "namespace"),
+                symbol("class_declaration", "class"),
+                symbol("interface_declaration", "interface"),
+                symbol("struct_declaration", "struct"),
+                symbol("enum_declaration", "enum"),
+                symbol("record_declaration", "record"),
+                symbol("constructor_declaration", "constructor"),
+                symbol("method_declaration", "method"),
+                symbol("property_declaration", "property"),
+            ],
+        );
+        add_language(
+            &mut map,
+            ["Go"],
+            ".",
+            [
+                symbol("function_declaration", "function"),
+                symbol("method_declaration", "method"),
+                symbol("type_spec", "type"),
+            ],
+        );
+        add_language(
+            &mut map,
+            ["Java"],
+            ".",
+            [
+                symbol("class_declaration", "class"),
+                symbol("interface_declaration", "interface"),
+                symbol("enum_declaration", "enum"),
+                symbol("record_declaration", "record"),
+                symbol("annotation_type_declaration", "annotation"),
+                symbol("constructor_declaration", "constructor"),
+                symbol("method_declaration", "method"),
+            ],
+        );
+        add_language(
+            &mut map,
+            ["JavaScript"],
+            ".",
+            JAVASCRIPT_DEFINITIONS
+                .into_iter()
+                .map(|(node_kind, kind)| symbol(node_kind, kind)),
+        );
+        add_language(
+            &mut map,
+            ["PHP"],
+            "\\",
+            [
+                symbol("namespace_definition", "namespace"),
+                symbol("function_definition", "function"),
+                symbol("class_declaration", "class"),
+                symbol("interface_declaration", "interface"),
+                symbol("trait_declaration", "trait"),
+                symbol("enum_declaration", "enum"),
+                symbol("method_declaration", "method"),
+            ],
+        );
+        map.insert(
+            "Python",
+            Arc::new(SymbolLanguageConfig {
+                definitions: [
+                    symbol("class_definition", "class"),
+                    symbol("function_definition", "function"),
+                ]
+                .into_iter()
+                .collect(),
+                separator: ".",
+                docstring_in_body: true,
+            }),
+        );
+        add_language(
+            &mut map,
+            ["Ruby"],
+            "::",
+            [
+                symbol("module", "module"),
+                symbol("class", "class"),
+                symbol("method", "method"),
+                symbol("singleton_method", "method"),
+            ],
+        );
+        add_language(
+            &mut map,
+            ["Rust"],
+            "::",
+            [
+                symbol("mod_item", "module"),
+                symbol("struct_item", "struct"),
+                symbol("enum_item", "enum"),
+                symbol("union_item", "union"),
+                symbol("trait_item", "trait"),
+                symbol("type_item", "type"),
+                symbol("function_item", "function"),
+                symbol("function_signature_item", "function"),
+                symbol("const_item", "constant"),
+                symbol("static_item", "static"),
+                symbol("macro_definition", "macro"),
+                (
+                    "impl_item",
+                    DefinitionConfig {
+                        symbol_kind: SymbolKind::ScopeOnly,
+                        name_field: "type",
+                        body_required: false,
+                    },
+                ),
+            ],
+        );
+        add_language(
+            &mut map,
+            ["Scala"],
+            ".",
+            [
+                symbol("object_definition", "object"),
+                symbol("class_definition", "class"),
+                symbol("trait_definition", "trait"),
+                symbol("function_definition", "function"),
+                symbol("function_declaration", "function"),
+            ],
+        );
+        add_language(
+            &mut map,
+            ["Swift"],
+            ".",
+            [
+                (
+                    "class_declaration",
+                    DefinitionConfig {
+                        symbol_kind: SymbolKind::FromField("declaration_kind"),
+                        name_field: "name",
+                        body_required: false,
+                    },
+                ),
+                symbol("protocol_declaration", "protocol"),
+                symbol("function_declaration", "function"),
+                symbol("protocol_function_declaration", "function"),
+            ],
+        );
+        add_language(
+            &mut map,
+            ["TypeScript", "TSX"],
+            ".",
+            JAVASCRIPT_DEFINITIONS
+                .into_iter()
+                .chain([
+                    ("abstract_class_declaration", "class"),
+                    ("interface_declaration", "interface"),
+                    ("type_alias_declaration", "type"),
+                    ("enum_declaration", "enum"),
+                    ("internal_module", "namespace"),
+                    ("function_signature", "function"),
+                    ("method_signature", "method"),
+                    ("abstract_method_signature", "method"),
+                ])
+                .map(|(node_kind, kind)| symbol(node_kind, kind)),
+        );
+        map
+    });
+
+/// Nodes wrapping definitions, which are considered part of the definitions, e.g. decorators.
+const DEFINITION_WRAPPER_KINDS: [&str; 4] = [
+    "decorated_definition",
+    "export_statement",
+    "ambient_declaration",
+    "template_declaration",
+];
+
+struct CodeSymbol {
+    kind: String,
+    name: String,
+    qualified_name: String,
+    parent: Option<String>,
+    signature: String,
+    docstring: Option<String>,
+    range: std::ops::Range<usize>,
+    start_line: usize,
+    end_line: usize,
+}
+
+struct SymbolCollector<'a> {
+    text: &'a str,
+    config: &'a SymbolLanguageConfig,
+    /// Comments occupying whole lines, as (start row, text), keyed by the row they end at.
+    comments_by_end_row: HashMap<usize, (usize, &'a str)>,
+    symbols: Vec<CodeSymbol>,
+}
+
+impl<'a> SymbolCollector<'a> {
+    /// Collects symbols and comments under the root in document order, with an explicit stack
+    /// as syntax trees may be nested arbitrarily deep.
+    fn collect(&mut self, root: tree_sitter::Node<'a>) {
+        let mut cursor = root.walk();
+        let mut nodes: Vec<(tree_sitter::Node<'a>, Option<Arc<str>>)> = vec![(root, None)];
+        while let Some((node, scope)) = nodes.pop() {
+            if node.kind().contains("comment") {
+                self.add_comment(node);
+                continue;
+            }
+
+            let mut child_scope = scope;
+            if let Some(definition) = self.config.definitions.get(node.kind()) {
+                if let Some(symbol) = self.to_symbol(node, definition, child_scope.as_deref()) {
+                    child_scope = Some(Arc::from(symbol.qualified_name.as_str()));
+                    if !matches!(definition.symbol_kind, SymbolKind::ScopeOnly) {
+                        self.symbols.push(symbol);
+                    }
+                }
+            }
+
+            // Reversed, so nodes are visited in document order.
+            let children = node.named_children(&mut cursor).collect::<Vec<_>>();
+            nodes.extend(
+                children
+                    .into_iter()
+                    .rev()
+                    .map(|child| (child, child_scope.clone())),
+            );
+        }
+    }
+
+    fn add_comment(&mut self, node: tree_sitter::Node<'a>) {
+        let start = node.start_position();
+        let line_start = node.start_byte() - start.column;
+        if !self.text[line_start..node.start_byte()].trim().is_empty() {
+            return;
+        }
+        // Line comments may include the trailing line break.
+        let end = node.end_position();
+        let end_row = if end.column == 0 && end.row > start.row {
+            end.row - 1
+        } else {
+            end.row
+        };
+        self.comments_by_end_row
+            .insert(end_row, (start.row, &self.text[node.byte_range()]));
+    }
+
+    fn to_symbol(
+        &self,
+        node: tree_sitter::Node<'a>,
+        definition: &DefinitionConfig,
+        scope: Option<&str>,
+    ) -> Option<CodeSymbol> {
+        let body = node.child_by_field_name("body");
+        if definition.body_required && body.is_none() {
+            return None;
+        }
+        let name = self.symbol_name(node, definition.name_field)?;
+        let kind = match definition.symbol_kind {
+            SymbolKind::Fixed(kind) => kind,
+            SymbolKind::FromField(field) => node.child_by_field_name(field)?.kind(),
+            SymbolKind::ScopeOnly => "",
+        };
+        let qualified_name = match scope {
+            Some(scope) => format!("{scope}{}{name}", self.config.separator),
+            None => name.to_string(),
+        };
+
+        let signature = match body {
+            Some(body) => self.text[node.start_byte()..body.start_byte()]
+                .trim_end()
+                .trim_end_matches([':', '='])
+                .trim_end(),
+            None => {
+                let text = &self.text[node.byte_range()];
+                text.lines().next().unwrap_or(text).trim_end()
+            }
+        };
+
+        let mut outer_node = node;
+        while let Some(parent) = outer_node.parent() {
+            if !DEFINITION_WRAPPER_KINDS.contains(&parent.kind()) {
+                break;
+            }
+            outer_node = parent;
+        }
+        let docstring = body
+            .filter(|_| self.config.docstring_in_body)
+            .and_then(|body| self.body_docstring(body))
+            .or_else(|| self.preceding_comments(outer_node.start_position().row));
+
+        Some(CodeSymbol {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            qualified_name,
+            parent: scope.map(|s| s.to_string()),
+            signature: signature.to_string(),
+            docstring,
+            range: outer_node.byte_range(),
+            start_line: outer_node.start_position().row + 1,
+            end_line: outer_node.end_position().row + 1,
+        })
+    }
+
+    fn symbol_name(&self, node: tree_sitter::Node<'a>, name_field: &str) -> Option<&'a str> {
+        let name_node = match node.child_by_field_name(name_field) {
+            Some(name_node) => name_node,
+            None => {
+                // C-family declarators are nested, e.g. `*f(int a)`, with the name at the innermost.
+                let mut declarator = node.child_by_field_name("declarator")?;
+                while let Some(inner) = declarator.child_by_field_name("declarator") {
+                    declarator = inner;
+                }
+                declarator
+            }
+        };
+        // Strip generic parameters, e.g. `Foo<T>` in Rust `impl` blocks.
+        let name = self.text[name_node.byte_range()]
+            .split('<')
+            .next()
+            .unwrap_or_default()
+            .trim();
+        (!name.is_empty()).then_some(name)
+    }
+
+    fn body_docstring(&self, body: tree_sitter::Node<'a>) -> Option<String> {
+        let statement = body.named_child(0)?;
+        if statement.kind() != "expression_statement" {
+            return None;
+        }
+        let string = statement.named_child(0)?;
+        if string.kind() != "string" {
+            return None;
+        }
+        let mut cursor = string.walk();
+        let content = string
+            .named_children(&mut cursor)
+            .filter(|child| child.kind() == "string_content")
+            .map(|child| &self.text[child.byte_range()])
+            .collect::<String>();
+        Some(dedent_docstring(&content))
+    }
+
+    fn preceding_comments(&self, start_row: usize) -> Option<String> {
+        let mut comments = Vec::new();
+        let mut row = start_row;
+        while row > 0 {
+            let Some((comment_start_row, comment)) = self.comments_by_end_row.get(&(row - 1))
+            else {
+                break;
+            };
+            comments.push(*comment);
+            row = *comment_start_row;
+        }
+        if comments.is_empty() {
+            return None;
+        }
+        comments.reverse();
+        let docstring = comments
+            .iter()
+            .flat_map(|comment| comment.lines())
+            .map(strip_comment_markers)
+            .collect::<Vec<_>>()
+            .join("\n");
+        Some(docstring.trim().to_string())
+    }
+}
+
+fn strip_comment_markers(line: &str) -> &str {
+    let line = line.trim();
+    let line = line.strip_suffix("*/").unwrap_or(line);
+    let line = ["///", "//!", "//", "/**", "/*", "#", "*"]
+        .into_iter()
+        .find_map(|prefix| line.strip_prefix(prefix))
+        .unwrap_or(line);
+    line.trim()
+}
+
+/// Removes indentation of lines after the first one, like Python's `inspect.cleandoc()`.
+fn dedent_docstring(docstring: &str) -> String {
+    let mut lines = docstring.lines();
+    let first_line = lines.next().unwrap_or_default().trim();
+    let rest_lines = lines.collect::<Vec<_>>();
+    let indent = rest_lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    std::iter::once(first_line)
+        .chain(
+            rest_lines
+                .iter()
+                .map(|line| line.get(indent..).unwrap_or_default().trim_end()),
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Grammar to parse the language with. C++ is parsed with the C grammar for chunking, which doesn't know C++ definitions.
+fn tree_sitter_lang(lang_config: &TreesitterLanguageConfig) -> tree_sitter::Language {
+    match lang_config.name.as_str() {
+        "C++" => tree_sitter_cpp::LANGUAGE.into(),
+        _ => lang_config.tree_sitter_lang.clone(),
+    }
+}
+
+fn extract_code_symbols(text: &str, language: &str) -> Result<Vec<CodeSymbol>> {
+    let Some(lang_config) = TREE_SITTER_LANGUAGE_BY_LANG.get(&UniCase::new(language.to_string()))
+    else {
+        return Ok(vec![]);
+    };
+    let Some(config) = SYMBOL_CONFIG_BY_LANG.get(lang_config.name.as_str()) else {
+        return Ok(vec![]);
+    };
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&tree_sitter_lang(lang_config))?;
+    let tree = parser
+        .parse(text, None)
+        .ok_or_else(|| anyhow!("failed in parsing text in language: {}", lang_config.name))?;
+
+    let mut collector = SymbolCollector {
+        text,
+        config,
+        comments_by_end_row: HashMap::new(),
+        symbols: Vec::new(),
+    };
+    collector.collect(tree.root_node());
+    Ok(collector.symbols)
+}
+
+struct Executor {
+    args: Args,
+}
+
+#[async_trait]
+impl SimpleFunctionExecutor for Executor {
+    async fn evaluate(&self, input: Vec<value::Value>) -> Result<value::Value> {
+        let text = self.args.text.value(&input)?.as_str()?;
+        let language = match self.args.language.value(&input)?.optional() {
+            Some(language) => language.as_str()?.as_ref(),
+            None => "",
+        };
+        let rows = extract_code_symbols(text, language)?
+            .into_iter()
+            .map(|symbol| {
+                ScopeValue(FieldValues {
+                    fields: vec![
+                        symbol.kind.into(),
+                        symbol.name.into(),
+                        symbol.qualified_name.into(),
+                        symbol.parent.into(),
+                        symbol.signature.into(),
+                        symbol.docstring.into(),
+                        value::Value::Basic(value::BasicValue::Range(RangeValue::new(
+                            symbol.range.start,
+                            symbol.range.end,
+                        ))),
+                        (symbol.start_line as i64).into(),
+                        (symbol.end_line as i64).into(),
+                    ],
+                })
+            })
+            .collect();
+        Ok(value::Value::LTable(rows))
+    }
+}
+
+pub struct Factory;
+
+#[async_trait]
+impl SimpleFunctionFactoryBase for Factory {
+    type Spec = EmptySpec;
+    type ResolvedArgs = Args;
+
+    fn name(&self) -> &str {
+        "ExtractCodeSymbols"
+    }
+
+    async fn resolve_schema<'a>(
+        &'a self,
+        _spec: &'a EmptySpec,
+        args_resolver: &mut OpArgsResolver<'a>,
+        _context: &FlowInstanceContext,
+    ) -> Result<(Args, EnrichedValueType)> {
+        let args = Args {
+            text: args_resolver
+                .next_arg("text")?
+                .expect_type(&ValueType::Basic(BasicValueType::Str))?,
+            language: args_resolver
+                .next_optional_arg("language")?
+                .expect_type(&ValueType::Basic(BasicValueType::Str))?,
+        };
+
+        let mut struct_schema = StructSchema::default();
+        let mut schema_builder = StructSchemaBuilder::new(&mut struct_schema);
+        schema_builder.add_field(FieldSchema::new(
+            "kind",
+            make_output_type(BasicValueType::Str),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "name",
+            make_output_type(BasicValueType::Str),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "qualified_name",
+            make_output_type(BasicValueType::Str),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "parent",
+            EnrichedValueType {
+                nullable: true,
+                ..make_output_type(BasicValueType::Str)
+            },
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "signature",
+            make_output_type(BasicValueType::Str),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "docstring",
+            EnrichedValueType {
+                nullable: true,
+                ..make_output_type(BasicValueType::Str)
+            },
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "location",
+            make_output_type(BasicValueType::Range),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "start_line",
+            make_output_type(BasicValueType::Int64),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "end_line",
+            make_output_type(BasicValueType::Int64),
+        ));
+        let output_schema = make_output_type(TableSchema::new(TableKind::LTable, struct_schema));
+        Ok((args, output_schema))
+    }
+
+    async fn build_executor(
+        self: Arc<Self>,
+        _spec: EmptySpec,
+        args: Args,
+        _context: Arc<FlowInstanceContext>,
+    ) -> Result<Box<dyn SimpleFunctionExecutor>> {
+        Ok(Box::new(Executor { args }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summarize(text: &str, language: &str) -> Vec<(String, String, Option<String>)> {
+        extract_code_symbols(text, language)
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.kind, s.qualified_name, s.parent))
+            .collect()
+    }
+
+    fn owned(symbols: &[(&str, &str, Option<&str>)]) -> Vec<(String, String, Option<String>)> {
+        symbols
+            .iter()
+            .map(|(kind, name, parent)| {
+                (
+                    kind.to_string(),
+                    name.to_string(),
+                    parent.map(|p| p.to_string()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_node_kinds_exist_in_grammars() {
+        for (lang, config) in SYMBOL_CONFIG_BY_LANG.iter() {
+            let lang_config = TREE_SITTER_LANGUAGE_BY_LANG
+                .get(&UniCase::new(lang.to_string()))
+                .unwrap();
+            for node_kind in config.definitions.keys() {
+                assert_ne!(
+                    tree_sitter_lang(lang_config).id_for_node_kind(node_kind, true),
+                    0,
+                    "`{node_kind}` not found for {lang}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_python_symbols() {
+        let text = r#"
+import os
+
+# Helper class.
+class Greeter(Base):
+    """Greets people.
+
+    Politely.
+    """
+
+    @staticmethod
+    def greet(name: str) -> str:
+        return f"Hello, {name}"
+
+def main():
+    pass
+"#;
+        assert_eq!(
+            summarize(text, ".py"),
+            owned(&[
+                ("class", "Greeter", None),
+                ("function", "Greeter.greet", Some("Greeter")),
+                ("function", "main", None),
+            ])
+        );
+
+        let symbols = extract_code_symbols(text, ".py").unwrap();
+        let class = &symbols[0];
+        assert_eq!(class.signature, "class Greeter(Base)");
+        assert_eq!(
+            class.docstring.as_deref(),
+            Some("Greets people.\n\nPolitely.")
+        );
+        assert_eq!((class.start_line, class.end_line), (5, 13));
+
+        let method = &symbols[1];
+        assert_eq!(method.name, "greet");
+        assert_eq!(method.signature, "def greet(name: str) -> str");
+        assert_eq!(method.docstring, None);
+        assert!(text[method.range.clone()].starts_with("@staticmethod"));
+    }
+
+    #[test]
+    fn test_rust_symbols() {
+        let text = r#"
+/// A point.
+///
+/// In 2D.
+pub struct Point<T> {
+    x: T,
+}
+
+impl<T> Point<T> {
+    // Creates a point.
+    pub fn new(x: T) -> Self {
+        Self { x }
+    }
+}
+
+mod inner {
+    const LIMIT: usize = 10;
+}
+"#;
+        assert_eq!(
+            summarize(text, "rust"),
+            owned(&[
+                ("struct", "Point", None),
+                ("function", "Point::new", Some("Point")),
+                ("module", "inner", None),
+                ("constant", "inner::LIMIT", Some("inner")),
+            ])
+        );
+
+        let symbols = extract_code_symbols(text, "rust").unwrap();
+        assert_eq!(symbols[0].signature, "pub struct Point<T>");
+        assert_eq!(symbols[0].docstring.as_deref(), Some("A point.\n\nIn 2D."));
+        assert_eq!(symbols[1].signature, "pub fn new(x: T) -> Self");
+        assert_eq!(symbols[1].docstring.as_deref(), Some("Creates a point."));
+        assert_eq!(symbols[3].signature, "const LIMIT: usize = 10;");
+    }
+
+    #[test]
+    fn test_deeply_nested() {
+        let depth = 20_000;
+        let text = format!(
+            "mod outer {{\n    fn f() {{ {}1{} }}\n}}\n\nfn g() {{}}\n",
+            "(".repeat(depth),
+            ")".repeat(depth)
+        );
+        assert_eq!(
+            summarize(&text, "rust"),
+            owned(&[
+                ("module", "outer", None),
+                ("function", "outer::f", Some("outer")),
+                ("function", "g", None),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_typescript_symbols() {
+        let text = r#"
+/**
+ * Shapes.
+ */
+export interface Shape {
+  area(): number;
+}
+
+export class Circle implements Shape {
+  area(): number { return 0; }
+}
+"#;
+        assert_eq!(
+            summarize(text, ".ts"),
+            owned(&[
+                ("interface", "Shape", None),
+                ("method", "Shape.area", Some("Shape")),
+                ("class", "Circle", None),
+                ("method", "Circle.area", Some("Circle")),
+            ])
+        );
+        let symbols = extract_code_symbols(text, ".ts").unwrap();
+        assert_eq!(symbols[0].docstring.as_deref(), Some("Shapes."));
+        assert_eq!(symbols[2].docstring, None);
+    }
+
+    #[test]
+    fn test_c_family_symbols() {
+        let text = r#"
+struct Node;
+
+struct Node {
+  int value;
+};
+
+static int *find(struct Node *root) { return 0; }
+"#;
+        assert_eq!(
+            summarize(text, "c"),
+            owned(&[("struct", "Node", None), ("function", "find", None)])
+        );
+
+        let text = r#"
+namespace geo {
+class Shape {
+ public:
+  void draw() {}
+};
+}
+"#;
+        assert_eq!(
+            summarize(text, "cpp"),
+            owned(&[
+                ("namespace", "geo", None),
+                ("class", "geo::Shape", Some("geo")),
+                ("function", "geo::Shape::draw", Some("geo::Shape")),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_unsupported_language() {
+        assert!(summarize("{\"a\": 1}", "json").is_empty());
+        assert!(summarize("def f(): pass", "unknown").is_empty());
+    }
+}
@@ -0,0 +1,363 @@
+use crate::ops::sdk::*;
+use crate::utils::fingerprint::Fingerprinter;
+use chrono::TimeZone;
+use pdf_extract::{Dictionary, Document};
+use std::sync::Arc;
+
+/// Bump when the output of the function changes, other than from upgrades of `pdf-extract`.
+const OUTPUT_VERSION: u32 = 1;
+/// Version of `pdf-extract` pinned in `Cargo.toml`, as its upgrades may change extracted text.
+/// Tests check it against both the pin and the version resolved in `Cargo.lock`.
+const PDF_EXTRACT_VERSION: &str = "0.12.1";
+
+pub struct Args {
+    content: ResolvedOpArg,
+}
+
+struct PdfText {
+    title: Option<String>,
+    author: Option<String>,
+    creation_date: Option<chrono::DateTime<chrono::FixedOffset>>,
+    /// (page number, text)
+    pages: Vec<(u32, String)>,
+}
+
+fn info_string(doc: &Document, info: &Dictionary, key: &[u8]) -> Option<String> {
+    let (_, obj) = doc.dereference(info.get(key).ok()?).ok()?;
+    let text = pdf_extract::decode_text_string(obj).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Parses dates in the PDF format, e.g. `D:20240102030405+08'00'`. All parts after the year are optional.
+fn parse_pdf_date(date: &str) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    let date = date.trim();
+    let date = date.strip_prefix("D:").unwrap_or(date);
+    let (digits, tz) = date.split_at(
+        date.find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(date.len()),
+    );
+    let part = |start: usize, default: u32| -> Option<u32> {
+        digits
+            .get(start..start + 2)
+            .map_or(Some(default), |v| v.parse().ok())
+    };
+    let year = digits.get(0..4)?.parse().ok()?;
+    let offset_secs = match tz.chars().next() {
+        None | Some('Z') => 0,
+        Some(sign @ ('+' | '-')) => {
+            let tz_digits = tz[1..].replace('\'', "");
+            let hours: i32 = tz_digits.get(0..2)?.parse().ok()?;
+            let minutes: i32 = tz_digits.get(2..4).map_or(Some(0), |v| v.parse().ok())?;
+            let secs = hours * 3600 + minutes * 60;
+            if sign == '-' { -secs } else { secs }
+        }
+        _ => return None,
+    };
+    chrono::FixedOffset::east_opt(offset_secs)?
+        .with_ymd_and_hms(
+            year,
+            part(4, 1)?,
+            part(6, 1)?,
+            part(8, 0)?,
+            part(10, 0)?,
+            part(12, 0)?,
+        )
+        .single()
+}
+
+fn extract_pdf_text(content: &[u8]) -> Result<PdfText> {
+    let mut doc = Document::load_mem(content)?;
+    if doc.is_encrypted() {
+        // Documents only protected by an owner password can be opened with an empty user password.
+        doc.decrypt("")
+            .map_err(|e| api_error!("failed in decrypting the PDF: {}", e))?;
+    }
+
+    let info = doc
+        .trailer
+        .get(b"Info")
+        .and_then(|info| doc.dereference(info))
+        .and_then(|(_, info)| info.as_dict())
+        .ok();
+    let (title, author, creation_date) = match info {
+        Some(info) => (
+            info_string(&doc, info, b"Title"),
+            info_string(&doc, info, b"Author"),
+            info_string(&doc, info, b"CreationDate").and_then(|date| parse_pdf_date(&date)),
+        ),
+        None => (None, None, None),
+    };
+
+    let pages = doc
+        .get_pages()
+        .into_keys()
+        .map(|page_number| {
+            let mut text = String::new();
+            pdf_extract::output_doc_page(
+                &doc,
+                &mut pdf_extract::PlainTextOutput::new(&mut text),
+                page_number,
+            )
+            .with_context(|| format!("failed in extracting text of page {page_number}"))?;
+            Ok((page_number, text.trim().to_string()))
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(PdfText {
+        title,
+        author,
+        creation_date,
+        pages,
+    })
+}
+
+struct Executor {
+    args: Args,
+    behavior_version: u32,
+}
+
+impl Executor {
+    fn new(args: Args) -> Result<Self> {
+        let fingerprint = Fingerprinter::default()
+            .with(&(OUTPUT_VERSION, PDF_EXTRACT_VERSION))?
+            .into_fingerprint();
+        let behavior_version = u32::from_le_bytes(fingerprint.0[..4].try_into()?);
+        Ok(Self {
+            args,
+            behavior_version,
+        })
+    }
+}
+
+#[async_trait]
+impl SimpleFunctionExecutor for Executor {
+    fn behavior_version(&self) -> Option<u32> {
+        Some(self.behavior_version)
+    }
+
+    fn enable_cache(&self) -> bool {
+        true
+    }
+
+    async fn evaluate(&self, input: Vec<value::Value>) -> Result<value::Value> {
+        let content = self.args.content.value(&input)?.as_bytes()?.clone();
+        // Extraction is CPU intensive, and may panic on malformed documents.
+        let pdf_text = tokio::task::spawn_blocking(move || extract_pdf_text(&content)).await??;
+        let pages = pdf_text
+            .pages
+            .into_iter()
+            .map(|(page_number, text)| {
+                value::ScopeValue(value::FieldValues {
+                    fields: vec![(page_number as i64).into(), text.into()],
+                })
+            })
+            .collect();
+        Ok(value::Value::Struct(value::FieldValues {
+            fields: vec![
+                pdf_text.title.into(),
+                pdf_text.author.into(),
+                pdf_text.creation_date.into(),
+                value::Value::LTable(pages),
+            ],
+        }))
+    }
+}
+
+pub struct Factory;
+
+#[async_trait]
+impl SimpleFunctionFactoryBase for Factory {
+    type Spec = EmptySpec;
+    type ResolvedArgs = Args;
+
+    fn name(&self) -> &str {
+        "ExtractPdfText"
+    }
+
+    async fn resolve_schema<'a>(
+        &'a self,
+        _spec: &'a EmptySpec,
+        args_resolver: &mut OpArgsResolver<'a>,
+        _context: &FlowInstanceContext,
+    ) -> Result<(Args, EnrichedValueType)> {
+        let args = Args {
+            content: args_resolver
+                .next_arg("content")?
+                .expect_type(&ValueType::Basic(BasicValueType::Bytes))?,
+        };
+
+        let nullable_type = |typ: BasicValueType| EnrichedValueType {
+            nullable: true,
+            ..make_output_type(typ)
+        };
+        let mut pages_schema = StructSchema::default();
+        let mut pages_schema_builder = StructSchemaBuilder::new(&mut pages_schema);
+        pages_schema_builder.add_field(FieldSchema::new(
+            "page_number",
+            make_output_type(BasicValueType::Int64),
+        ));
+        pages_schema_builder.add_field(FieldSchema::new(
+            "text",
+            make_output_type(BasicValueType::Str),
+        ));
+
+        let mut struct_schema = StructSchema::default();
+        let mut schema_builder = StructSchemaBuilder::new(&mut struct_schema);
+        schema_builder.add_field(FieldSchema::new(
+            "title",
+            nullable_type(BasicValueType::Str),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "author",
+            nullable_type(BasicValueType::Str),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "creation_date",
+            nullable_type(BasicValueType::OffsetDateTime),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "pages",
+            make_output_type(TableSchema::new(TableKind::LTable, pages_schema)),
+        ));
+        Ok((args, make_output_type(struct_schema)))
+    }
+
+    async fn build_executor(
+        self: Arc<Self>,
+        _spec: EmptySpec,
+        args: Args,
+        _context: Arc<FlowInstanceContext>,
+    ) -> Result<Box<dyn SimpleFunctionExecutor>> {
+        Ok(Box::new(Executor::new(args)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pdf_extract::{Object, Stream, StringFormat, dictionary};
+
+    fn build_pdf(page_texts: &[&str], info: Dictionary) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+        let page_ids = page_texts
+            .iter()
+            .map(|text| {
+                let content = format!("BT /F1 12 Tf 72 720 Td ({text}) Tj ET");
+                let content_id = doc.add_object(Stream::new(dictionary! {}, content.into_bytes()));
+                doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "Contents" => content_id,
+                    "Resources" => resources_id,
+                    "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                })
+                .into()
+            })
+            .collect::<Vec<Object>>();
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => page_ids.len() as i64,
+                "Kids" => page_ids,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        let info_id = doc.add_object(info);
+        doc.trailer.set("Info", info_id);
+
+        let mut buffer = Vec::new();
+        doc.save_to(&mut buffer).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn test_extract_pdf_text() {
+        let content = build_pdf(
+            &["Hello from page one", "Goodbye from page two"],
+            dictionary! {
+                "Title" => Object::String(b"Annual Report".to_vec(), StringFormat::Literal),
+                "Author" => Object::String(
+                    // UTF-16BE with BOM, for "Zoë".
+                    vec![0xFE, 0xFF, 0x00, b'Z', 0x00, b'o', 0x00, 0xEB],
+                    StringFormat::Hexadecimal,
+                ),
+                "CreationDate" => Object::string_literal("D:20240102030405+08'00'"),
+            },
+        );
+        let pdf_text = extract_pdf_text(&content).unwrap();
+        assert_eq!(pdf_text.title.as_deref(), Some("Annual Report"));
+        assert_eq!(pdf_text.author.as_deref(), Some("Zoë"));
+        assert_eq!(
+            pdf_text.creation_date.unwrap().to_rfc3339(),
+            "2024-01-02T03:04:05+08:00"
+        );
+        assert_eq!(
+            pdf_text.pages,
+            vec![
+                (1, "Hello from page one".to_string()),
+                (2, "Goodbye from page two".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_pdf_date() {
+        let parse = |date| parse_pdf_date(date).map(|d| d.to_rfc3339());
+        assert_eq!(parse("D:2024"), Some("2024-01-01T00:00:00+00:00".into()));
+        assert_eq!(
+            parse("D:20240102030405Z"),
+            Some("2024-01-02T03:04:05+00:00".into())
+        );
+        assert_eq!(
+            parse("20240102030405-05'30"),
+            Some("2024-01-02T03:04:05-05:30".into())
+        );
+        assert_eq!(parse("D:20241340"), None);
+        assert_eq!(parse("yesterday"), None);
+    }
+
+    #[test]
+    fn test_pdf_extract_version_pinned() {
+        let manifest: toml::Table = toml::from_str(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/Cargo.toml"
+        )))
+        .unwrap();
+        assert_eq!(
+            manifest["dependencies"]["pdf-extract"].as_str(),
+            Some(format!("={PDF_EXTRACT_VERSION}").as_str())
+        );
+    }
+
+    #[test]
+    fn test_pdf_extract_version_resolved() {
+        // `Cargo.lock` isn't checked in, but cargo always generates it before building tests.
+        let lock: toml::Table = toml::from_str(
+            &std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.lock")).unwrap(),
+        )
+        .unwrap();
+        let versions = lock["package"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|package| package["name"].as_str() == Some("pdf-extract"))
+            .map(|package| package["version"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(versions, vec![PDF_EXTRACT_VERSION]);
+    }
+}
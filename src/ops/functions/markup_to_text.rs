@@ -0,0 +1,1029 @@
+use crate::ops::registry::ExecutorFactoryRegistry;
+use crate::ops::sdk::*;
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize)]
+pub struct Spec {
+    /// If true, outputs the paragraphs of the text along with the headings they're under.
+    #[serde(default)]
+    include_paragraphs: bool,
+}
+
+pub struct Args {
+    text: ResolvedOpArg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MarkupFormat {
+    Html,
+    Markdown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Paragraph,
+    Heading(usize),
+    TableRow,
+    Code,
+}
+
+/// Consecutive blocks in the same group are separated by a single line break, instead of a blank line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockGroup {
+    List,
+    Table,
+}
+
+struct ListState {
+    /// `None` for unordered lists.
+    next_number: Option<u64>,
+    /// Width of the marker of the current item, to indent its continuation lines.
+    marker_width: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct Paragraph {
+    range: RangeValue,
+    headings: Vec<String>,
+}
+
+/// Builds plain text from blocks of markup, keeping headings, lists and tables as plain structure.
+#[derive(Default)]
+struct TextBuilder {
+    text: String,
+    paragraphs: Vec<Paragraph>,
+    /// (level, text) of headings that the current position is under.
+    headings: Vec<(usize, String)>,
+    /// Content of the block being built.
+    block: String,
+    lists: Vec<ListState>,
+    /// Marker of the current list item, if its first block isn't emitted yet.
+    pending_marker: Option<String>,
+    last_group: Option<BlockGroup>,
+}
+
+impl TextBuilder {
+    /// Pushes inline text, with whitespaces collapsed.
+    fn push_inline(&mut self, text: &str) {
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !self.block.is_empty() && !self.block.ends_with(char::is_whitespace) {
+                    self.block.push(' ');
+                }
+            } else {
+                self.block.push(c);
+            }
+        }
+    }
+
+    /// Pushes preformatted text as is.
+    fn push_raw(&mut self, text: &str) {
+        self.block.push_str(text);
+    }
+
+    fn push_line_break(&mut self) {
+        self.block.truncate(self.block.trim_end_matches(' ').len());
+        if !self.block.is_empty() {
+            self.block.push('\n');
+        }
+    }
+
+    fn push_cell_separator(&mut self) {
+        self.block.truncate(self.block.trim_end().len());
+        self.block.push_str(" | ");
+    }
+
+    /// Ends the inline content before a nested block as a paragraph.
+    fn flush(&mut self) {
+        self.end_block(BlockKind::Paragraph);
+    }
+
+    fn end_block(&mut self, kind: BlockKind) {
+        let block = std::mem::take(&mut self.block);
+        let content = match kind {
+            BlockKind::Code => block.trim_start_matches('\n').trim_end(),
+            _ => block.trim(),
+        };
+        if content.trim_start().is_empty() {
+            return;
+        }
+        if let BlockKind::Heading(level) = kind {
+            while self.headings.last().is_some_and(|(l, _)| *l >= level) {
+                self.headings.pop();
+            }
+            self.headings.push((level, content.to_string()));
+        }
+
+        let group = if !self.lists.is_empty() {
+            Some(BlockGroup::List)
+        } else if kind == BlockKind::TableRow {
+            Some(BlockGroup::Table)
+        } else {
+            None
+        };
+        if !self.text.is_empty() {
+            let tight = group.is_some() && group == self.last_group;
+            self.text.push_str(if tight { "\n" } else { "\n\n" });
+        }
+        self.last_group = group;
+
+        let start = self.text.len();
+        let (first_prefix, prefix) = match self.lists.last() {
+            Some(list) => {
+                let indent = "  ".repeat(self.lists.len() - 1);
+                let prefix = format!("{indent}{}", " ".repeat(list.marker_width));
+                let first_prefix = match self.pending_marker.take() {
+                    Some(marker) => format!("{indent}{marker}"),
+                    None => prefix.clone(),
+                };
+                (first_prefix, prefix)
+            }
+            None => (String::new(), String::new()),
+        };
+        for (i, line) in content.split('\n').enumerate() {
+            if i > 0 {
+                self.text.push('\n');
+                if !line.is_empty() {
+                    self.text.push_str(&prefix);
+                }
+            } else {
+                self.text.push_str(&first_prefix);
+            }
+            self.text.push_str(line);
+        }
+
+        if !matches!(kind, BlockKind::Heading(_)) {
+            self.paragraphs.push(Paragraph {
+                range: RangeValue::new(start, self.text.len()),
+                headings: self.headings.iter().map(|(_, h)| h.clone()).collect(),
+            });
+        }
+    }
+
+    /// `start` is the number of the first item for ordered lists.
+    fn begin_list(&mut self, start: Option<u64>) {
+        self.flush();
+        self.lists.push(ListState {
+            next_number: start,
+            marker_width: 0,
+        });
+    }
+
+    fn end_list(&mut self) {
+        self.flush();
+        self.lists.pop();
+    }
+
+    fn begin_list_item(&mut self) {
+        self.flush();
+        let Some(list) = self.lists.last_mut() else {
+            return;
+        };
+        let marker = match &mut list.next_number {
+            Some(number) => {
+                *number += 1;
+                format!("{}. ", *number - 1)
+            }
+            None => "- ".to_string(),
+        };
+        list.marker_width = marker.len();
+        self.pending_marker = Some(marker);
+    }
+
+    fn end_list_item(&mut self) {
+        self.flush();
+        self.pending_marker = None;
+    }
+
+    fn finish(mut self) -> (String, Vec<Paragraph>) {
+        self.flush();
+        (self.text, self.paragraphs)
+    }
+}
+
+/// Decodes a character reference, e.g. `&amp;` or `&#x2014;`.
+fn decode_entity(entity: &str) -> Option<char> {
+    let name = entity.strip_prefix('&')?.strip_suffix(';')?;
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    let c = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "mdash" => '—',
+        "ndash" => '–',
+        "hellip" => '…',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "middot" => '·',
+        "bull" => '•',
+        _ => return None,
+    };
+    Some(c)
+}
+
+fn push_entity(builder: &mut TextBuilder, entity: &str, preformatted: bool) {
+    let mut buf = [0; 4];
+    let text = match decode_entity(entity) {
+        Some(c) => &*c.encode_utf8(&mut buf),
+        None => entity,
+    };
+    if preformatted {
+        builder.push_raw(text);
+    } else {
+        builder.push_inline(text);
+    }
+}
+
+/// Elements that are boilerplate or not rendered as text.
+const HTML_SKIPPED_TAGS: &[&str] = &[
+    "aside", "button", "canvas", "dialog", "footer", "form", "head", "iframe", "nav", "noscript",
+    "object", "select", "svg", "template", "title",
+];
+
+const HTML_BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "blockquote",
+    "body",
+    "caption",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "main",
+    "p",
+    "section",
+    "summary",
+    "table",
+    "tbody",
+    "tfoot",
+    "thead",
+];
+
+/// Elements (or blocks) nested deeper than this are converted into plain text without structure,
+/// instead of recursing further.
+const MAX_NESTING_DEPTH: usize = 128;
+
+struct HtmlConverter<'a> {
+    html: &'a str,
+    builder: TextBuilder,
+    /// Whether the next table cell is the first one of its row.
+    first_cell: bool,
+    /// Number of elements being converted.
+    depth: usize,
+}
+
+impl<'a> HtmlConverter<'a> {
+    fn node_text(&self, node: tree_sitter::Node) -> &'a str {
+        &self.html[node.byte_range()]
+    }
+
+    fn start_tag<'t>(&self, element: tree_sitter::Node<'t>) -> Option<tree_sitter::Node<'t>> {
+        element
+            .named_child(0)
+            .filter(|n| n.kind() == "start_tag" || n.kind() == "self_closing_tag")
+    }
+
+    fn tag_name(&self, element: tree_sitter::Node) -> String {
+        self.start_tag(element)
+            .and_then(|tag| tag.named_child(0))
+            .map(|name| self.node_text(name).to_ascii_lowercase())
+            .unwrap_or_default()
+    }
+
+    /// Returns the value of the attribute, or an empty string for attributes without value.
+    fn attribute(&self, element: tree_sitter::Node, name: &str) -> Option<&'a str> {
+        let start_tag = self.start_tag(element)?;
+        let mut cursor = start_tag.walk();
+        let attr = start_tag.named_children(&mut cursor).find(|attr| {
+            attr.kind() == "attribute"
+                && attr
+                    .named_child(0)
+                    .is_some_and(|n| self.node_text(n).eq_ignore_ascii_case(name))
+        })?;
+        let value = attr.named_child(1).map_or("", |v| {
+            self.node_text(v)
+                .trim_matches(|c| c == '"' || c == '\'')
+                .trim()
+        });
+        Some(value)
+    }
+
+    fn is_hidden(&self, element: tree_sitter::Node) -> bool {
+        self.attribute(element, "hidden").is_some()
+            || self
+                .attribute(element, "aria-hidden")
+                .is_some_and(|v| v.eq_ignore_ascii_case("true"))
+            || self.attribute(element, "style").is_some_and(|v| {
+                v.replace(char::is_whitespace, "")
+                    .to_ascii_lowercase()
+                    .contains("display:none")
+            })
+    }
+
+    /// Finds the first `<main>` element, which contains the content without boilerplate around.
+    fn find_main<'t>(&self, root: tree_sitter::Node<'t>) -> Option<tree_sitter::Node<'t>> {
+        let mut cursor = root.walk();
+        let mut nodes = vec![root];
+        while let Some(node) = nodes.pop() {
+            if node.kind() == "element" {
+                let tag_name = self.tag_name(node);
+                if tag_name == "main" && !self.is_hidden(node) {
+                    return Some(node);
+                }
+                if HTML_SKIPPED_TAGS.contains(&tag_name.as_str()) {
+                    continue;
+                }
+            }
+            // Reversed, so nodes are visited in document order.
+            let children = node.named_children(&mut cursor).collect::<Vec<_>>();
+            nodes.extend(children.into_iter().rev());
+        }
+        None
+    }
+
+    fn convert_children(&mut self, node: tree_sitter::Node, preformatted: bool) {
+        let mut cursor = node.walk();
+        let mut prev_end = None;
+        for child in node.named_children(&mut cursor) {
+            // Gaps between children are whitespaces.
+            if let Some(prev_end) = prev_end {
+                let gap = &self.html[prev_end..child.start_byte()];
+                if preformatted {
+                    self.builder.push_raw(gap);
+                } else {
+                    self.builder.push_inline(gap);
+                }
+            }
+            prev_end = Some(child.end_byte());
+            match child.kind() {
+                "text" => {
+                    let text = self.node_text(child);
+                    if preformatted {
+                        self.builder.push_raw(text);
+                    } else {
+                        self.builder.push_inline(text);
+                    }
+                }
+                "entity" => {
+                    let entity = self.node_text(child);
+                    push_entity(&mut self.builder, entity, preformatted);
+                }
+                "element" if self.depth < MAX_NESTING_DEPTH => {
+                    self.depth += 1;
+                    self.convert_element(child, preformatted);
+                    self.depth -= 1;
+                }
+                "element" => self.convert_flattened(child, preformatted),
+                // Scripts, styles, comments, tags, etc.
+                _ => {}
+            }
+        }
+    }
+
+    /// Converts the text within the element without its structure.
+    fn convert_flattened(&mut self, element: tree_sitter::Node, preformatted: bool) {
+        let mut cursor = element.walk();
+        let mut nodes = vec![element];
+        while let Some(node) = nodes.pop() {
+            match node.kind() {
+                "text" => {
+                    let text = self.node_text(node);
+                    if preformatted {
+                        self.builder.push_raw(text);
+                    } else {
+                        self.builder.push_inline(" ");
+                        self.builder.push_inline(text);
+                    }
+                }
+                "entity" => {
+                    let entity = self.node_text(node);
+                    push_entity(&mut self.builder, entity, preformatted);
+                }
+                "element" => {
+                    let tag_name = self.tag_name(node);
+                    if HTML_SKIPPED_TAGS.contains(&tag_name.as_str()) || self.is_hidden(node) {
+                        continue;
+                    }
+                    // Reversed, so nodes are visited in document order.
+                    let children = node.named_children(&mut cursor).collect::<Vec<_>>();
+                    nodes.extend(children.into_iter().rev());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn convert_element(&mut self, element: tree_sitter::Node, preformatted: bool) {
+        let tag_name = self.tag_name(element);
+        if HTML_SKIPPED_TAGS.contains(&tag_name.as_str()) || self.is_hidden(element) {
+            return;
+        }
+        // Void elements, e.g. `<br>`, may wrap the content following them, which is still converted.
+        match tag_name.as_str() {
+            "br" => {
+                if preformatted {
+                    self.builder.push_raw("\n");
+                } else {
+                    self.builder.push_line_break();
+                }
+                self.convert_children(element, preformatted);
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = (tag_name.as_bytes()[1] - b'0') as usize;
+                self.builder.flush();
+                self.convert_children(element, preformatted);
+                self.builder.end_block(BlockKind::Heading(level));
+            }
+            "ul" | "ol" | "menu" => {
+                let start = (tag_name == "ol").then(|| {
+                    self.attribute(element, "start")
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(1)
+                });
+                self.builder.begin_list(start);
+                self.convert_children(element, preformatted);
+                self.builder.end_list();
+            }
+            "li" => {
+                self.builder.begin_list_item();
+                self.convert_children(element, preformatted);
+                self.builder.end_list_item();
+            }
+            "pre" => {
+                self.builder.flush();
+                self.convert_children(element, true);
+                self.builder.end_block(BlockKind::Code);
+            }
+            "tr" => {
+                self.builder.flush();
+                let first_cell = std::mem::replace(&mut self.first_cell, true);
+                self.convert_children(element, preformatted);
+                self.first_cell = first_cell;
+                self.builder.end_block(BlockKind::TableRow);
+            }
+            "td" | "th" => {
+                if !std::mem::replace(&mut self.first_cell, false) {
+                    self.builder.push_cell_separator();
+                }
+                self.convert_children(element, preformatted);
+            }
+            tag_name if HTML_BLOCK_TAGS.contains(&tag_name) => {
+                self.builder.flush();
+                self.convert_children(element, preformatted);
+                self.builder.flush();
+            }
+            _ => self.convert_children(element, preformatted),
+        }
+    }
+}
+
+fn html_to_text(html: &str) -> Result<TextBuilder> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&tree_sitter_html::LANGUAGE.into())?;
+    let tree = parser
+        .parse(html, None)
+        .ok_or_else(|| anyhow!("failed in parsing the HTML"))?;
+    let mut converter = HtmlConverter {
+        html,
+        builder: TextBuilder::default(),
+        first_cell: true,
+        depth: 0,
+    };
+    match converter.find_main(tree.root_node()) {
+        Some(main) => converter.convert_element(main, false),
+        None => converter.convert_children(tree.root_node(), false),
+    }
+    Ok(converter.builder)
+}
+
+struct MarkdownConverter<'a> {
+    markdown: &'a str,
+    inline_parser: tree_sitter::Parser,
+    builder: TextBuilder,
+    /// Number of blocks being converted.
+    depth: usize,
+}
+
+impl<'a> MarkdownConverter<'a> {
+    /// Text of the node, excluding the markers of container blocks (e.g. `>`) on continuation lines.
+    fn text_without_continuations(&self, node: tree_sitter::Node) -> String {
+        let mut text = String::new();
+        let mut pos = node.start_byte();
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            if child.kind() == "block_continuation" {
+                text.push_str(&self.markdown[pos..child.start_byte()]);
+                pos = child.end_byte();
+            }
+        }
+        text.push_str(&self.markdown[pos..node.end_byte()]);
+        text
+    }
+
+    fn convert_block(&mut self, node: tree_sitter::Node) {
+        if self.depth >= MAX_NESTING_DEPTH {
+            let text = self.text_without_continuations(node);
+            self.builder.flush();
+            self.builder.push_inline(&text);
+            self.builder.flush();
+            return;
+        }
+        self.depth += 1;
+        self.convert_block_content(node);
+        self.depth -= 1;
+    }
+
+    fn convert_block_content(&mut self, node: tree_sitter::Node) {
+        let mut cursor = node.walk();
+        match node.kind() {
+            "atx_heading" | "setext_heading" => {
+                let level = node
+                    .named_children(&mut cursor)
+                    .find_map(|child| match child.kind() {
+                        "atx_h1_marker" | "setext_h1_underline" => Some(1),
+                        "atx_h2_marker" | "setext_h2_underline" => Some(2),
+                        "atx_h3_marker" => Some(3),
+                        "atx_h4_marker" => Some(4),
+                        "atx_h5_marker" => Some(5),
+                        "atx_h6_marker" => Some(6),
+                        _ => None,
+                    })
+                    .unwrap_or(1);
+                self.builder.flush();
+                let mut nodes = vec![node];
+                while let Some(node) = nodes.pop() {
+                    if node.kind() == "inline" {
+                        self.convert_inline(node);
+                    } else {
+                        let mut cursor = node.walk();
+                        let children = node.named_children(&mut cursor).collect::<Vec<_>>();
+                        nodes.extend(children.into_iter().rev());
+                    }
+                }
+                self.builder.end_block(BlockKind::Heading(level));
+            }
+            "paragraph" => {
+                self.builder.flush();
+                for child in node.named_children(&mut cursor) {
+                    if child.kind() == "inline" {
+                        self.convert_inline(child);
+                    }
+                }
+                self.builder.flush();
+            }
+            "list" => {
+                let first_marker = node
+                    .named_child(0)
+                    .and_then(|item| item.named_child(0))
+                    .filter(|marker| {
+                        marker.kind() == "list_marker_dot"
+                            || marker.kind() == "list_marker_parenthesis"
+                    });
+                let start = first_marker.map(|marker| {
+                    self.markdown[marker.byte_range()]
+                        .trim()
+                        .trim_end_matches(['.', ')'])
+                        .parse()
+                        .unwrap_or(1)
+                });
+                self.builder.begin_list(start);
+                for child in node.named_children(&mut cursor) {
+                    self.convert_block(child);
+                }
+                self.builder.end_list();
+            }
+            "list_item" => {
+                self.builder.begin_list_item();
+                for child in node.named_children(&mut cursor) {
+                    self.convert_block(child);
+                }
+                self.builder.end_list_item();
+            }
+            "fenced_code_block" => {
+                self.builder.flush();
+                for child in node.named_children(&mut cursor) {
+                    if child.kind() == "code_fence_content" {
+                        let code = self.text_without_continuations(child);
+                        self.builder.push_raw(&code);
+                    }
+                }
+                self.builder.end_block(BlockKind::Code);
+            }
+            "indented_code_block" => {
+                self.builder.flush();
+                let code = self.text_without_continuations(node);
+                // Indentation of the first line isn't a continuation.
+                let code = code
+                    .strip_prefix("    ")
+                    .or_else(|| code.strip_prefix('\t'))
+                    .unwrap_or(&code);
+                self.builder.push_raw(code);
+                self.builder.end_block(BlockKind::Code);
+            }
+            "pipe_table" => {
+                for row in node.named_children(&mut cursor) {
+                    if row.kind() != "pipe_table_header" && row.kind() != "pipe_table_row" {
+                        continue;
+                    }
+                    self.builder.flush();
+                    let mut row_cursor = row.walk();
+                    for (i, cell) in row
+                        .named_children(&mut row_cursor)
+                        .filter(|cell| cell.kind() == "pipe_table_cell")
+                        .enumerate()
+                    {
+                        if i > 0 {
+                            self.builder.push_cell_separator();
+                        }
+                        self.convert_inline(cell);
+                    }
+                    self.builder.end_block(BlockKind::TableRow);
+                }
+            }
+            "html_block"
+            | "link_reference_definition"
+            | "thematic_break"
+            | "minus_metadata"
+            | "plus_metadata" => {}
+            // Containers, e.g. documents, sections and block quotes.
+            _ => {
+                for child in node.named_children(&mut cursor) {
+                    self.convert_block(child);
+                }
+            }
+        }
+    }
+
+    fn convert_inline(&mut self, node: tree_sitter::Node) {
+        let source = self.text_without_continuations(node);
+        match self.inline_parser.parse(&source, None) {
+            Some(tree) => self.convert_inline_children(tree.root_node(), &source, 0),
+            None => self.builder.push_inline(&source),
+        }
+    }
+
+    fn convert_inline_children(&mut self, node: tree_sitter::Node, source: &str, depth: usize) {
+        if depth >= MAX_NESTING_DEPTH {
+            self.builder.push_inline(&source[node.byte_range()]);
+            return;
+        }
+        let mut cursor = node.walk();
+        let mut pos = node.start_byte();
+        for child in node.named_children(&mut cursor) {
+            self.builder.push_inline(&source[pos..child.start_byte()]);
+            pos = child.end_byte();
+            let text = &source[child.byte_range()];
+            match child.kind() {
+                "emphasis_delimiter"
+                | "code_span_delimiter"
+                | "latex_span_delimiter"
+                | "html_tag"
+                | "link_destination"
+                | "link_title"
+                | "link_label" => {}
+                "inline_link"
+                | "full_reference_link"
+                | "collapsed_reference_link"
+                | "shortcut_link"
+                | "image" => {
+                    let mut link_cursor = child.walk();
+                    for part in child.named_children(&mut link_cursor) {
+                        if part.kind() == "link_text" || part.kind() == "image_description" {
+                            self.convert_inline_children(part, source, depth + 1);
+                        }
+                    }
+                }
+                "uri_autolink" | "email_autolink" => {
+                    self.builder.push_inline(text.trim_matches(['<', '>']));
+                }
+                "backslash_escape" => self.builder.push_inline(&text[1..]),
+                "entity_reference" | "numeric_character_reference" => {
+                    push_entity(&mut self.builder, text, false);
+                }
+                "hard_line_break" => self.builder.push_line_break(),
+                _ => self.convert_inline_children(child, source, depth + 1),
+            }
+        }
+        self.builder.push_inline(&source[pos..node.end_byte()]);
+    }
+}
+
+fn markdown_to_text(markdown: &str) -> Result<TextBuilder> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&tree_sitter_md::LANGUAGE.into())?;
+    let tree = parser
+        .parse(markdown, None)
+        .ok_or_else(|| anyhow!("failed in parsing the Markdown"))?;
+    let mut inline_parser = tree_sitter::Parser::new();
+    inline_parser.set_language(&tree_sitter_md::INLINE_LANGUAGE.into())?;
+    let mut converter = MarkdownConverter {
+        markdown,
+        inline_parser,
+        builder: TextBuilder::default(),
+        depth: 0,
+    };
+    converter.convert_block(tree.root_node());
+    Ok(converter.builder)
+}
+
+struct Executor {
+    args: Args,
+    format: MarkupFormat,
+    include_paragraphs: bool,
+}
+
+#[async_trait]
+impl SimpleFunctionExecutor for Executor {
+    async fn evaluate(&self, input: Vec<value::Value>) -> Result<value::Value> {
+        let markup = self.args.text.value(&input)?.as_str()?;
+        let builder = match self.format {
+            MarkupFormat::Html => html_to_text(markup)?,
+            MarkupFormat::Markdown => markdown_to_text(markup)?,
+        };
+        let (text, paragraphs) = builder.finish();
+        if !self.include_paragraphs {
+            return Ok(text.into());
+        }
+        let paragraphs = paragraphs
+            .into_iter()
+            .map(|paragraph| {
+                let headings = paragraph
+                    .headings
+                    .into_iter()
+                    .map(|h| value::BasicValue::Str(Arc::from(h)))
+                    .collect::<Vec<_>>();
+                value::ScopeValue(value::FieldValues {
+                    fields: vec![
+                        value::Value::Basic(value::BasicValue::Range(paragraph.range)),
+                        value::Value::Basic(value::BasicValue::Vector(Arc::from(headings))),
+                        text[paragraph.range.start..paragraph.range.end]
+                            .to_string()
+                            .into(),
+                    ],
+                })
+            })
+            .collect();
+        Ok(value::Value::Struct(value::FieldValues {
+            fields: vec![text.into(), value::Value::LTable(paragraphs)],
+        }))
+    }
+}
+
+pub struct Factory {
+    format: MarkupFormat,
+}
+
+#[async_trait]
+impl SimpleFunctionFactoryBase for Factory {
+    type Spec = Spec;
+    type ResolvedArgs = Args;
+
+    fn name(&self) -> &str {
+        match self.format {
+            MarkupFormat::Html => "HtmlToText",
+            MarkupFormat::Markdown => "MarkdownToText",
+        }
+    }
+
+    async fn resolve_schema<'a>(
+        &'a self,
+        spec: &'a Spec,
+        args_resolver: &mut OpArgsResolver<'a>,
+        _context: &FlowInstanceContext,
+    ) -> Result<(Args, EnrichedValueType)> {
+        let args = Args {
+            text: args_resolver
+                .next_arg("text")?
+                .expect_type(&ValueType::Basic(BasicValueType::Str))?,
+        };
+        if !spec.include_paragraphs {
+            return Ok((args, make_output_type(BasicValueType::Str)));
+        }
+
+        let mut paragraph_schema = StructSchema::default();
+        let mut paragraph_schema_builder = StructSchemaBuilder::new(&mut paragraph_schema);
+        paragraph_schema_builder.add_field(FieldSchema::new(
+            "location",
+            make_output_type(BasicValueType::Range),
+        ));
+        paragraph_schema_builder.add_field(FieldSchema::new(
+            "headings",
+            make_output_type(BasicValueType::Vector(VectorTypeSchema {
+                element_type: Box::new(BasicValueType::Str),
+                dimension: None,
+            })),
+        ));
+        paragraph_schema_builder.add_field(FieldSchema::new(
+            "text",
+            make_output_type(BasicValueType::Str),
+        ));
+
+        let mut struct_schema = StructSchema::default();
+        let mut schema_builder = StructSchemaBuilder::new(&mut struct_schema);
+        schema_builder.add_field(FieldSchema::new(
+            "text",
+            make_output_type(BasicValueType::Str),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "paragraphs",
+            make_output_type(TableSchema::new(TableKind::LTable, paragraph_schema)),
+        ));
+        Ok((args, make_output_type(struct_schema)))
+    }
+
+    async fn build_executor(
+        self: Arc<Self>,
+        spec: Spec,
+        args: Args,
+        _context: Arc<FlowInstanceContext>,
+    ) -> Result<Box<dyn SimpleFunctionExecutor>> {
+        Ok(Box::new(Executor {
+            args,
+            format: self.format,
+            include_paragraphs: spec.include_paragraphs,
+        }))
+    }
+}
+
+pub fn register(registry: &mut ExecutorFactoryRegistry) -> Result<()> {
+    Factory {
+        format: MarkupFormat::Html,
+    }
+    .register(registry)?;
+    Factory {
+        format: MarkupFormat::Markdown,
+    }
+    .register(registry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_paragraphs(builder: TextBuilder) -> (String, Vec<(String, Vec<String>)>) {
+        let (text, paragraphs) = builder.finish();
+        let paragraphs = paragraphs
+            .into_iter()
+            .map(|p| (text[p.range.start..p.range.end].to_string(), p.headings))
+            .collect();
+        (text, paragraphs)
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let html = r#"<!DOCTYPE html>
+<html>
+<head><title>Page</title><style>p { color: red; }</style></head>
+<body>
+  <nav><a href="/">Home</a> <a href="/about">About</a></nav>
+  <main>
+    <h1>Title &amp; more</h1>
+    <p>Hello <b>bold</b>
+       world<br>next &#8212; line</p>
+    <div hidden>Hidden</div>
+    <script>var x = 1;</script>
+    <ol start="3">
+      <li>three<ul><li>nested</li></ul></li>
+      <li>four</li>
+    </ol>
+    <table><tr><th>a</th><th>b</th></tr><tr><td>1</td><td>2</td></tr></table>
+    <pre>  fn main() {
+      x &lt; 1
+  }</pre>
+  </main>
+  <footer>Copyright</footer>
+</body>
+</html>"#;
+        let (text, _) = html_to_text(html).unwrap().finish();
+        assert_eq!(
+            text,
+            "Title & more\n\n\
+             Hello bold world\nnext — line\n\n\
+             3. three\n  - nested\n4. four\n\n\
+             a | b\n1 | 2\n\n  \
+             fn main() {\n      x < 1\n  }"
+        );
+    }
+
+    #[test]
+    fn test_html_to_text_without_main() {
+        let html = "<body><nav>Menu</nav><div><p>First</p>Second <span aria-hidden=\"true\">x</span></div></body>";
+        let (text, _) = html_to_text(html).unwrap().finish();
+        assert_eq!(text, "First\n\nSecond");
+    }
+
+    #[test]
+    fn test_markdown_to_text() {
+        let markdown = r#"---
+title: Doc
+---
+
+# Title
+
+Some *emph* and **strong** `code`, a [link](http://example.com "t") and ![image](x.png).
+Escaped \* &amp; continued<span>!</span>
+
+- item one
+- item two
+  - nested
+
+> quoted
+> text
+
+```rust
+fn x() {}
+```
+
+| a | b |
+|---|---|
+| 1 | 2 |
+"#;
+        let (text, _) = markdown_to_text(markdown).unwrap().finish();
+        assert_eq!(
+            text,
+            "Title\n\n\
+             Some emph and strong code, a link and image. Escaped * & continued!\n\n\
+             - item one\n- item two\n  - nested\n\n\
+             quoted text\n\n\
+             fn x() {}\n\n\
+             a | b\n1 | 2"
+        );
+    }
+
+    #[test]
+    fn test_deeply_nested() {
+        let depth = 5_000;
+        let html = format!(
+            "{}<p>deep <b>text</b></p>{}",
+            "<div>".repeat(depth),
+            "</div>".repeat(depth)
+        );
+        let (text, _) = html_to_text(&html).unwrap().finish();
+        assert_eq!(text, "deep text");
+
+        // The block parser can't take much deeper nesting itself.
+        let markdown = format!("{} deep *text*\n", ">".repeat(MAX_NESTING_DEPTH + 50));
+        let (text, _) = markdown_to_text(&markdown).unwrap().finish();
+        assert!(text.ends_with("deep *text*"), "{text}");
+
+        let markdown = format!(
+            "{}deep{}\n",
+            "[".repeat(MAX_NESTING_DEPTH + 50),
+            "](x)".repeat(MAX_NESTING_DEPTH + 50)
+        );
+        let (text, _) = markdown_to_text(&markdown).unwrap().finish();
+        assert!(text.contains("deep"));
+    }
+
+    #[test]
+    fn test_paragraph_headings() {
+        let markdown = "# A\n\nP1\n\n## B\n\nP2\n\n### C\n\nP3\n\n## D\n\nP4\n";
+        let (text, paragraphs) = collect_paragraphs(markdown_to_text(markdown).unwrap());
+        assert_eq!(text, "A\n\nP1\n\nB\n\nP2\n\nC\n\nP3\n\nD\n\nP4");
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            paragraphs,
+            vec![
+                ("P1".to_string(), strings(&["A"])),
+                ("P2".to_string(), strings(&["A", "B"])),
+                ("P3".to_string(), strings(&["A", "B", "C"])),
+                ("P4".to_string(), strings(&["A", "D"])),
+            ]
+        );
+
+        let html = "<h2>X</h2><p>one</p><h1>Y</h1><ul><li>two</li></ul>";
+        let (_, paragraphs) = collect_paragraphs(html_to_text(html).unwrap());
+        assert_eq!(
+            paragraphs,
+            vec![
+                ("one".to_string(), strings(&["X"])),
+                ("- two".to_string(), strings(&["Y"])),
+            ]
+        );
+    }
+}
@@ -1,4 +1,7 @@
 pub mod embed_text;
 pub mod extract_by_llm;
+pub mod extract_code_symbols;
+pub mod extract_pdf_text;
+pub mod markup_to_text;
 pub mod parse_json;
 pub mod split_recursively;
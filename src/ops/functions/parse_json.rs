@@ -1,15 +1,24 @@
 use crate::ops::sdk::*;
+use crate::ops::shared::csv_cell;
 use anyhow::Result;
 use std::collections::HashMap;
 use std::sync::{Arc, LazyLock};
 use unicase::UniCase;
+use yaml_rust2::yaml::Yaml;
+
+#[derive(Debug, Deserialize)]
+pub struct Spec {
+    /// If provided, the parsed value is converted to this type, instead of untyped JSON.
+    output_type: Option<EnrichedValueType>,
+}
 
 pub struct Args {
     text: ResolvedOpArg,
     language: Option<ResolvedOpArg>,
 }
 
-type ParseFn = fn(&str) -> Result<serde_json::Value>;
+/// The output type is passed for formats without types on their own, e.g. CSV.
+type ParseFn = fn(&str, Option<&ValueType>) -> Result<serde_json::Value>;
 struct LanguageConfig {
     parse_fn: ParseFn,
 }
@@ -28,19 +37,132 @@ fn add_language<'a>(
     }
 }
 
-fn parse_json(text: &str) -> Result<serde_json::Value> {
+fn parse_json(text: &str, _output_type: Option<&ValueType>) -> Result<serde_json::Value> {
     Ok(serde_json::from_str(text)?)
 }
 
+fn parse_json5(text: &str, _output_type: Option<&ValueType>) -> Result<serde_json::Value> {
+    Ok(json5::from_str(text)?)
+}
+
+fn yaml_to_json(yaml: Yaml) -> Result<serde_json::Value> {
+    let value = match yaml {
+        Yaml::Null => serde_json::Value::Null,
+        Yaml::Boolean(v) => serde_json::Value::Bool(v),
+        Yaml::Integer(v) => serde_json::Value::from(v),
+        // Infinity and NaN are not representable in JSON, and become null.
+        Yaml::Real(_) => serde_json::Value::from(yaml.as_f64()),
+        Yaml::String(v) => serde_json::Value::String(v),
+        Yaml::Array(v) => {
+            serde_json::Value::Array(v.into_iter().map(yaml_to_json).collect::<Result<_>>()?)
+        }
+        Yaml::Hash(v) => serde_json::Value::Object(
+            v.into_iter()
+                .map(|(k, v)| {
+                    let key = match k {
+                        Yaml::String(k) | Yaml::Real(k) => k,
+                        Yaml::Integer(k) => k.to_string(),
+                        Yaml::Boolean(k) => k.to_string(),
+                        Yaml::Null => "null".to_string(),
+                        k => api_bail!("unsupported YAML mapping key: {:?}", k),
+                    };
+                    Ok((key, yaml_to_json(v)?))
+                })
+                .collect::<Result<_>>()?,
+        ),
+        Yaml::Alias(_) | Yaml::BadValue => api_bail!("invalid YAML value: {:?}", yaml),
+    };
+    Ok(value)
+}
+
+/// The text is expected to have at most one document. Empty text is parsed into null.
+fn parse_yaml(text: &str, _output_type: Option<&ValueType>) -> Result<serde_json::Value> {
+    let mut docs = yaml_rust2::YamlLoader::load_from_str(text)?;
+    if docs.len() > 1 {
+        api_bail!(
+            "YAML text has {} documents. Use language `yaml_multi` to parse multiple documents",
+            docs.len()
+        );
+    }
+    docs.pop().map_or(Ok(serde_json::Value::Null), yaml_to_json)
+}
+
+/// All documents are parsed into an array, even if there's only one (or none).
+fn parse_yaml_multi(text: &str, _output_type: Option<&ValueType>) -> Result<serde_json::Value> {
+    let docs = yaml_rust2::YamlLoader::load_from_str(text)?;
+    Ok(serde_json::Value::Array(
+        docs.into_iter().map(yaml_to_json).collect::<Result<_>>()?,
+    ))
+}
+
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(v) => serde_json::Value::String(v),
+        toml::Value::Integer(v) => serde_json::Value::from(v),
+        toml::Value::Float(v) => serde_json::Value::from(v),
+        toml::Value::Boolean(v) => serde_json::Value::Bool(v),
+        toml::Value::Datetime(v) => serde_json::Value::String(v.to_string()),
+        toml::Value::Array(v) => {
+            serde_json::Value::Array(v.into_iter().map(toml_to_json).collect())
+        }
+        toml::Value::Table(v) => {
+            serde_json::Value::Object(v.into_iter().map(|(k, v)| (k, toml_to_json(v))).collect())
+        }
+    }
+}
+
+fn parse_toml(text: &str, _output_type: Option<&ValueType>) -> Result<serde_json::Value> {
+    let table: toml::Table = toml::from_str(text)?;
+    Ok(toml_to_json(toml::Value::Table(table)))
+}
+
+/// Parsed into an array of objects, keyed by the header row.
+fn parse_csv(text: &str, output_type: Option<&ValueType>) -> Result<serde_json::Value> {
+    let column_types: HashMap<&str, &ValueType> = match output_type {
+        Some(ValueType::Table(table_schema)) => table_schema
+            .row
+            .fields
+            .iter()
+            .map(|field| (field.name.as_str(), &field.value_type.typ))
+            .collect(),
+        _ => HashMap::new(),
+    };
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    let headers = reader.headers()?.clone();
+    let rows = reader
+        .records()
+        .map(|row| {
+            let row = row?;
+            Ok(serde_json::Value::Object(
+                headers
+                    .iter()
+                    .zip(row.iter())
+                    .map(|(column, cell)| {
+                        let typ = column_types.get(column).copied();
+                        (column.to_string(), csv_cell::to_json(cell, typ))
+                    })
+                    .collect(),
+            ))
+        })
+        .collect::<Result<_>>()?;
+    Ok(serde_json::Value::Array(rows))
+}
+
 static PARSE_FN_BY_LANG: LazyLock<HashMap<UniCase<&'static str>, Arc<LanguageConfig>>> =
     LazyLock::new(|| {
         let mut map = HashMap::new();
         add_language(&mut map, "json", [".json"], parse_json);
+        add_language(&mut map, "json5", [".json5"], parse_json5);
+        add_language(&mut map, "yaml", [".yaml", ".yml", "yml"], parse_yaml);
+        add_language(&mut map, "yaml_multi", [], parse_yaml_multi);
+        add_language(&mut map, "toml", [".toml"], parse_toml);
+        add_language(&mut map, "csv", [".csv"], parse_csv);
         map
     });
 
 struct Executor {
     args: Args,
+    output_type: Option<EnrichedValueType>,
 }
 
 #[async_trait]
@@ -56,10 +178,18 @@ impl SimpleFunctionExecutor for Executor {
                 .and_then(|lang| PARSE_FN_BY_LANG.get(&UniCase::new(lang)))
         };
         let parse_fn = lang_config.map(|c| c.parse_fn).unwrap_or(parse_json);
-        let parsed_value = parse_fn(text)?;
-        Ok(value::Value::Basic(value::BasicValue::Json(Arc::new(
-            parsed_value,
-        ))))
+        let parsed_value = parse_fn(text, self.output_type.as_ref().map(|t| &t.typ))?;
+        match &self.output_type {
+            Some(output_type) => {
+                if parsed_value.is_null() && !output_type.nullable {
+                    api_bail!("parsed value is null, but the output type is not nullable");
+                }
+                value::Value::from_json(parsed_value, &output_type.typ)
+            }
+            None => Ok(value::Value::Basic(value::BasicValue::Json(Arc::new(
+                parsed_value,
+            )))),
+        }
     }
 }
 
@@ -67,7 +197,7 @@ pub struct Factory;
 
 #[async_trait]
 impl SimpleFunctionFactoryBase for Factory {
-    type Spec = EmptySpec;
+    type Spec = Spec;
     type ResolvedArgs = Args;
 
     fn name(&self) -> &str {
@@ -76,7 +206,7 @@ impl SimpleFunctionFactoryBase for Factory {
 
     async fn resolve_schema<'a>(
         &'a self,
-        _spec: &'a EmptySpec,
+        spec: &'a Spec,
         args_resolver: &mut OpArgsResolver<'a>,
         _context: &FlowInstanceContext,
     ) -> Result<(Args, EnrichedValueType)> {
@@ -89,16 +219,94 @@ impl SimpleFunctionFactoryBase for Factory {
                 .expect_type(&ValueType::Basic(BasicValueType::Str))?,
         };
 
-        let output_schema = make_output_type(BasicValueType::Json);
+        let output_schema = spec
+            .output_type
+            .clone()
+            .unwrap_or_else(|| make_output_type(BasicValueType::Json));
         Ok((args, output_schema))
     }
 
     async fn build_executor(
         self: Arc<Self>,
-        _spec: EmptySpec,
+        spec: Spec,
         args: Args,
         _context: Arc<FlowInstanceContext>,
     ) -> Result<Box<dyn SimpleFunctionExecutor>> {
-        Ok(Box::new(Executor { args }))
+        Ok(Box::new(Executor {
+            args,
+            output_type: spec.output_type,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_yaml() {
+        let text = "a: 1\nb: [x, 2.5, true, null]\n---\nc: {1: d}\n";
+        assert!(parse_yaml(text, None).is_err());
+        assert_eq!(
+            parse_yaml_multi(text, None).unwrap(),
+            json!([{"a": 1, "b": ["x", 2.5, true, null]}, {"c": {"1": "d"}}])
+        );
+        assert_eq!(parse_yaml("a: 1", None).unwrap(), json!({"a": 1}));
+        assert_eq!(parse_yaml_multi("a: 1", None).unwrap(), json!([{"a": 1}]));
+        assert_eq!(parse_yaml("", None).unwrap(), json!(null));
+        assert_eq!(parse_yaml_multi("", None).unwrap(), json!([]));
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        let text = "title = \"t\"\ndate = 2024-01-02\n\n[server]\nports = [80, 443]\n";
+        assert_eq!(
+            parse_toml(text, None).unwrap(),
+            json!({"title": "t", "date": "2024-01-02", "server": {"ports": [80, 443]}})
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_with_output_type() {
+        let output_type = ValueType::Table(TableSchema::new(
+            TableKind::LTable,
+            StructSchema {
+                fields: Arc::new(vec![
+                    FieldSchema::new("name", make_output_type(BasicValueType::Str)),
+                    FieldSchema::new(
+                        "age",
+                        EnrichedValueType {
+                            nullable: true,
+                            ..make_output_type(BasicValueType::Int64)
+                        },
+                    ),
+                ]),
+                description: None,
+            },
+        ));
+        let text = "name,age\nalice,30\nbob,\n";
+        assert_eq!(
+            parse_csv(text, None).unwrap(),
+            json!([{"name": "alice", "age": "30"}, {"name": "bob", "age": ""}])
+        );
+
+        let parsed = parse_csv(text, Some(&output_type)).unwrap();
+        assert_eq!(
+            parsed,
+            json!([{"name": "alice", "age": 30}, {"name": "bob", "age": null}])
+        );
+        let value = value::Value::<value::ScopeValue>::from_json(parsed, &output_type).unwrap();
+        assert_eq!(
+            value,
+            value::Value::LTable(vec![
+                value::ScopeValue(value::FieldValues {
+                    fields: vec!["alice".to_string().into(), 30i64.into()],
+                }),
+                value::ScopeValue(value::FieldValues {
+                    fields: vec!["bob".to_string().into(), value::Value::Null],
+                }),
+            ])
+        );
     }
 }
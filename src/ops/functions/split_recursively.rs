@@ -19,10 +19,20 @@ struct CustomLanguageSpec {
     separators_regex: Vec<String>,
 }
 
+#[derive(Deserialize)]
+#[serde(tag = "kind")]
+enum TokenizerSpec {
+    /// A tiktoken encoding (e.g. `cl100k_base`), or an OpenAI model name to look up the encoding.
+    Tiktoken { encoding: String },
+    /// A HuggingFace tokenizer, loaded from a `tokenizer.json` file.
+    HuggingFace { path: String },
+}
+
 #[derive(Deserialize)]
 struct Spec {
     #[serde(default)]
     custom_languages: Vec<CustomLanguageSpec>,
+    tokenizer: Option<TokenizerSpec>,
 }
 
 const SYNTAX_LEVEL_GAP_COST: usize = 512;
@@ -54,9 +64,9 @@ static DEFAULT_LANGUAGE_CONFIG: LazyLock<SimpleLanguageConfig> =
             .collect(),
     });
 
-struct TreesitterLanguageConfig {
-    name: String,
-    tree_sitter_lang: tree_sitter::Language,
+pub(crate) struct TreesitterLanguageConfig {
+    pub(crate) name: String,
+    pub(crate) tree_sitter_lang: tree_sitter::Language,
     terminal_node_kind_ids: HashSet<u16>,
 }
 
@@ -94,7 +104,7 @@ fn add_treesitter_language<'a>(
     }
 }
 
-static TREE_SITTER_LANGUAGE_BY_LANG: LazyLock<
+pub(crate) static TREE_SITTER_LANGUAGE_BY_LANG: LazyLock<
     HashMap<UniCase<String>, Arc<TreesitterLanguageConfig>>,
 > = LazyLock::new(|| {
     let mut map = HashMap::new();
@@ -500,14 +510,115 @@ struct ChunkOutput<'s> {
     text: &'s str,
 }
 
+enum Tokenizer {
+    Tiktoken(&'static tiktoken_rs::CoreBPE),
+    HuggingFace(Box<tokenizers::Tokenizer>),
+}
+
+impl Tokenizer {
+    fn new(spec: TokenizerSpec) -> Result<Self> {
+        let tokenizer = match spec {
+            TokenizerSpec::Tiktoken { encoding } => {
+                use tiktoken_rs::tokenizer::Tokenizer as Encoding;
+                let encoding = match encoding.as_str() {
+                    "o200k_base" => Encoding::O200kBase,
+                    "o200k_harmony" => Encoding::O200kHarmony,
+                    "cl100k_base" => Encoding::Cl100kBase,
+                    "p50k_base" => Encoding::P50kBase,
+                    "p50k_edit" => Encoding::P50kEdit,
+                    "r50k_base" | "gpt2" => Encoding::R50kBase,
+                    model => tiktoken_rs::tokenizer::get_tokenizer(model).ok_or_else(|| {
+                        api_error!("unknown tiktoken encoding or model: `{}`", model)
+                    })?,
+                };
+                Tokenizer::Tiktoken(tiktoken_rs::bpe_for_tokenizer(encoding)?)
+            }
+            TokenizerSpec::HuggingFace { path } => {
+                let mut tokenizer = tokenizers::Tokenizer::from_file(&path)
+                    .map_err(|e| anyhow!("failed in loading tokenizer from `{}`: {}", path, e))?;
+                // Chunks are measured as they are, without truncating or padding.
+                tokenizer
+                    .with_truncation(None)
+                    .map_err(|e| anyhow!("{}", e))?
+                    .with_padding(None);
+                Tokenizer::HuggingFace(Box::new(tokenizer))
+            }
+        };
+        Ok(tokenizer)
+    }
+
+    fn count_tokens(&self, text: &str) -> Result<usize> {
+        let count = match self {
+            Tokenizer::Tiktoken(bpe) => bpe.encode_ordinary(text).len(),
+            Tokenizer::HuggingFace(tokenizer) => tokenizer
+                .encode_fast(text, false)
+                .map_err(|e| anyhow!("{}", e))?
+                .len(),
+        };
+        Ok(count)
+    }
+
+    fn token_offsets(&self, text: &str) -> Result<TokenOffsets> {
+        let mut starts = Vec::new();
+        let mut ends = Vec::new();
+        match self {
+            Tokenizer::Tiktoken(bpe) => {
+                // Tokens are contiguous byte sequences covering the whole text.
+                let mut offset = 0;
+                for token in bpe.encode_ordinary(text) {
+                    starts.push(offset);
+                    offset += bpe.decode_bytes(&[token])?.len();
+                    ends.push(offset);
+                }
+            }
+            Tokenizer::HuggingFace(tokenizer) => {
+                let encoding = tokenizer
+                    .encode(text, false)
+                    .map_err(|e| anyhow!("{}", e))?;
+                for &(start, end) in encoding.get_offsets() {
+                    starts.push(start);
+                    ends.push(end);
+                }
+                starts.sort_unstable();
+                ends.sort_unstable();
+            }
+        }
+        Ok(TokenOffsets { starts, ends })
+    }
+}
+
+/// Byte offsets of tokens in the full text, sorted.
+struct TokenOffsets {
+    starts: Vec<usize>,
+    ends: Vec<usize>,
+}
+
+impl TokenOffsets {
+    /// Number of tokens overlapping with the range.
+    fn count(&self, start: usize, end: usize) -> usize {
+        let started = self.starts.partition_point(|&s| s < end);
+        let ended = self.ends.partition_point(|&e| e <= start);
+        started.saturating_sub(ended)
+    }
+}
+
 struct RecursiveChunker<'s> {
     full_text: &'s str,
     chunk_size: usize,
     chunk_overlap: usize,
     min_chunk_size: usize,
+    /// If provided, sizes are measured in tokens instead of bytes.
+    tokenizer: Option<(&'s Tokenizer, TokenOffsets)>,
 }
 
 impl<'t, 's: 't> RecursiveChunker<'s> {
+    fn size(&self, start: usize, end: usize) -> usize {
+        match &self.tokenizer {
+            Some((_, token_offsets)) => token_offsets.count(start, end),
+            None => end - start,
+        }
+    }
+
     fn collect_atom_chunks_from_iter(
         &self,
         sub_chunks_iter: impl Iterator<Item = Chunk<'t, 's>>,
@@ -516,7 +627,7 @@ impl<'t, 's: 't> RecursiveChunker<'s> {
         atom_collector.curr_level += 1;
         for sub_chunk in sub_chunks_iter {
             let range = sub_chunk.range;
-            if range.len() <= self.min_chunk_size {
+            if self.size(range.start, range.end) <= self.min_chunk_size {
                 atom_collector.collect(range);
             } else {
                 self.collect_atom_chunks(sub_chunk, atom_collector)?;
@@ -583,11 +694,15 @@ impl<'t, 's: 't> RecursiveChunker<'s> {
         if self.chunk_overlap == 0 {
             0
         } else {
-            (self.full_text.len() - offset) * MISSING_OVERLAP_COST / self.chunk_overlap
+            self.size(offset, self.full_text.len()) * MISSING_OVERLAP_COST / self.chunk_overlap
         }
     }
 
-    fn merge_atom_chunks(&self, atom_chunks: Vec<AtomChunk>) -> Vec<ChunkOutput<'s>> {
+    fn merge_atom_chunks(
+        &self,
+        atom_chunks: &[AtomChunk],
+        max_chunk_size: usize,
+    ) -> Vec<ChunkOutput<'s>> {
         struct AtomRoutingPlan {
             start_idx: usize,     // index of `atom_chunks` for the start chunk
             prev_plan_idx: usize, // index of `plans` for the previous plan
@@ -640,7 +755,7 @@ impl<'t, 's: 't> RecursiveChunker<'s> {
             }
             loop {
                 let start_chunk = &atom_chunks[start_idx];
-                let chunk_size = chunk.range.end - start_chunk.range.start;
+                let chunk_size = self.size(start_chunk.range.start, chunk.range.end);
 
                 let mut cost = 0;
                 cost +=
@@ -653,7 +768,7 @@ impl<'t, 's: 't> RecursiveChunker<'s> {
                     cost += TOO_SMALL_CHUNK_COST;
                 }
 
-                if chunk_size > self.chunk_size {
+                if chunk_size > max_chunk_size {
                     if min_cost == usize::MAX {
                         min_cost = cost + plans[start_idx].cost;
                         arg_min_start_idx = start_idx;
@@ -664,8 +779,10 @@ impl<'t, 's: 't> RecursiveChunker<'s> {
 
                 let prev_plan_idx = if self.chunk_overlap > 0 {
                     while let Some(top_prev_plan) = prev_plan_candidates.peek() {
-                        let overlap_size =
-                            atom_chunks[top_prev_plan.1].range.end - start_chunk.range.start;
+                        let overlap_size = self.size(
+                            start_chunk.range.start,
+                            atom_chunks[top_prev_plan.1].range.end,
+                        );
                         if overlap_size <= self.chunk_overlap {
                             break;
                         }
@@ -750,14 +867,40 @@ impl<'t, 's: 't> RecursiveChunker<'s> {
             &mut atom_collector,
         )?;
         let atom_chunks = atom_collector.into_atom_chunks();
-        let output = self.merge_atom_chunks(atom_chunks);
-        Ok(output)
+        let Some((tokenizer, _)) = &self.tokenizer else {
+            return Ok(self.merge_atom_chunks(&atom_chunks, self.chunk_size));
+        };
+
+        // Token counts of ranges are estimated from tokens of the full text, which may differ
+        // from tokens of the chunk text at boundaries. Verify with exact counts, and tighten the
+        // limit until all chunks fit, except the ones that cannot be split further.
+        let mut max_chunk_size = self.chunk_size;
+        loop {
+            let output = self.merge_atom_chunks(&atom_chunks, max_chunk_size);
+            let mut max_excess = 0;
+            for chunk in output.iter() {
+                let estimated_size =
+                    self.size(chunk.start_pos.byte_offset, chunk.end_pos.byte_offset);
+                if estimated_size > max_chunk_size {
+                    continue;
+                }
+                let excess = tokenizer
+                    .count_tokens(chunk.text)?
+                    .saturating_sub(self.chunk_size);
+                max_excess = max_excess.max(excess);
+            }
+            if max_excess == 0 || max_chunk_size == 0 {
+                return Ok(output);
+            }
+            max_chunk_size = max_chunk_size.saturating_sub(max_excess);
+        }
     }
 }
 
 struct Executor {
     args: Args,
     custom_languages: HashMap<UniCase<String>, Arc<SimpleLanguageConfig>>,
+    tokenizer: Option<Tokenizer>,
 }
 
 impl Executor {
@@ -801,9 +944,11 @@ impl Executor {
                 }
             }
         }
+        let tokenizer = spec.tokenizer.map(Tokenizer::new).transpose()?;
         Ok(Self {
             args,
             custom_languages,
+            tokenizer,
         })
     }
 }
@@ -862,6 +1007,10 @@ impl SimpleFunctionExecutor for Executor {
     async fn evaluate(&self, input: Vec<Value>) -> Result<Value> {
         let full_text = self.args.text.value(&input)?.as_str()?;
         let chunk_size = self.args.chunk_size.value(&input)?.as_int64()?;
+        let tokenizer = match &self.tokenizer {
+            Some(tokenizer) => Some((tokenizer, tokenizer.token_offsets(full_text)?)),
+            None => None,
+        };
         let recursive_chunker = RecursiveChunker {
             full_text,
             chunk_size: chunk_size as usize,
@@ -875,6 +1024,7 @@ impl SimpleFunctionExecutor for Executor {
                 .map(|v| v.as_int64())
                 .transpose()?
                 .unwrap_or(chunk_size / 2) as usize,
+            tokenizer,
         };
 
         let language = UniCase::new(
@@ -1071,6 +1221,7 @@ mod tests {
             chunk_size,
             chunk_overlap,
             min_chunk_size,
+            tokenizer: None,
         }
     }
 
@@ -1229,4 +1380,32 @@ mod tests {
         );
         assert_chunk_text_consistency(text, &chunks[2], "at the end.", "Whitespace Test, Chunk 2");
     }
+
+    #[test]
+    fn test_split_by_tokens() {
+        let tokenizer = Tokenizer::new(TokenizerSpec::Tiktoken {
+            encoding: "cl100k_base".to_string(),
+        })
+        .unwrap();
+        let text = "Tokenization splits words like antidisestablishmentarianism into pieces. \
+                    Short words are usually one token each.\n\n\
+                    Numbers such as 1234567890 and symbols like <=> take several tokens.";
+        let mut chunker = create_test_chunker(text, 12, 6, 0);
+        chunker.tokenizer = Some((&tokenizer, tokenizer.token_offsets(text).unwrap()));
+
+        let chunks = chunker
+            .split_root_chunk(ChunkKind::RegexpSepChunk {
+                lang_config: &DEFAULT_LANGUAGE_CONFIG,
+                next_regexp_sep_id: 0,
+            })
+            .unwrap();
+
+        // Much fewer chunks than splitting by 12 bytes would produce.
+        assert!(chunks.len() > 1 && chunks.len() < text.len() / 24);
+        for (i, chunk) in chunks.iter().enumerate() {
+            let num_tokens = tokenizer.count_tokens(chunk.text).unwrap();
+            assert!(num_tokens <= 12, "chunk {i} has {num_tokens} tokens");
+            assert_chunk_text_consistency(text, chunk, chunk.text, &format!("Chunk {i}"));
+        }
+    }
 }
@@ -1,6 +1,10 @@
 use std::time::SystemTime;
 
-use crate::base::{schema::*, spec::IndexOptions, value::*};
+use crate::base::{
+    schema::*,
+    spec::{IndexOptions, OpSpec, VectorSimilarityMetric},
+    value::*,
+};
 use crate::prelude::*;
 use crate::setup;
 use chrono::TimeZone;
@@ -151,12 +155,14 @@ pub trait SourceExecutor: Send + Sync {
 
 #[async_trait]
 pub trait SourceFactory {
+    /// Returns the output type, declarations of setup-managed resources needed by the source, and the executor.
     async fn build(
         self: Arc<Self>,
         spec: serde_json::Value,
         context: Arc<FlowInstanceContext>,
     ) -> Result<(
         EnrichedValueType,
+        Vec<OpSpec>,
         BoxFuture<'static, Result<Box<dyn SourceExecutor>>>,
     )>;
 }
@@ -253,6 +259,35 @@ pub struct ExportDataCollectionSpec {
     pub index_options: IndexOptions,
 }
 
+pub struct VectorMatchQuery {
+    pub vector_field_name: String,
+    pub vector: Vec<f32>,
+    pub similarity_metric: VectorSimilarityMetric,
+    pub limit: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryResult {
+    /// Values of fields in `QueryResults::fields`, in the same order.
+    pub data: Vec<serde_json::Value>,
+    /// Cosine similarity or inner product (higher is closer), or L2 distance (lower is closer).
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryResults {
+    pub fields: Vec<FieldSchema>,
+    pub results: Vec<QueryResult>,
+}
+
+#[async_trait]
+pub trait QueryTarget: Send + Sync {
+    /// Name of the vector field to search on when not specified by the query.
+    fn default_vector_field_name(&self) -> Result<String>;
+
+    async fn search(&self, query: VectorMatchQuery) -> Result<QueryResults>;
+}
+
 #[async_trait]
 pub trait ExportTargetFactory: Send + Sync {
     async fn build(
@@ -304,6 +339,12 @@ pub trait ExportTargetFactory: Send + Sync {
         setup_status: Vec<ResourceSetupChangeItem<'async_trait>>,
         auth_registry: &Arc<AuthRegistry>,
     ) -> Result<()>;
+
+    /// Returns `None` if the target doesn't support querying.
+    fn build_query_target(
+        &self,
+        export_context: Arc<dyn Any + Send + Sync>,
+    ) -> Result<Option<Arc<dyn QueryTarget>>>;
 }
 
 #[derive(Clone)]
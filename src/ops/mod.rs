@@ -4,6 +4,7 @@ pub mod registry;
 // All operations
 mod factory_bases;
 mod functions;
+mod shared;
 mod sources;
 mod targets;
 
@@ -11,11 +11,25 @@ fn register_executor_factories(registry: &mut ExecutorFactoryRegistry) -> Result
     sources::local_file::Factory.register(registry)?;
     sources::google_drive::Factory.register(registry)?;
     sources::amazon_s3::Factory.register(registry)?;
+    sources::postgres::Factory.register(registry)?;
+    sources::git_repository::Factory.register(registry)?;
+    sources::local_archive::Factory.register(registry)?;
+    sources::structured_file::Factory.register(registry)?;
+    sources::sqlite::Factory.register(registry)?;
+    sources::web_crawler::Factory.register(registry)?;
+    sources::kafka::Factory.register(registry)?;
+    sources::mailbox::Factory.register(registry)?;
+    sources::azure_blob::Factory.register(registry)?;
+    sources::postgres::NotificationTriggerFactory.register(registry)?;
+    sources::google_drive::PageTokenTableFactory.register(registry)?;
 
     functions::parse_json::Factory.register(registry)?;
     functions::split_recursively::register(registry)?;
     functions::extract_by_llm::Factory.register(registry)?;
     functions::embed_text::register(registry)?;
+    functions::extract_code_symbols::Factory.register(registry)?;
+    functions::extract_pdf_text::Factory.register(registry)?;
+    functions::markup_to_text::register(registry)?;
 
     targets::postgres::Factory::default().register(registry)?;
     targets::qdrant::register(registry)?;
@@ -0,0 +1,22 @@
+use crate::base::schema::{BasicValueType, ValueType};
+
+/// Convert a CSV cell to JSON, based on the type of its field if known.
+/// Cells of non-string types are parsed as JSON literals, falling back to strings. Empty cells are null for them.
+pub fn to_json(cell: &str, typ: Option<&ValueType>) -> serde_json::Value {
+    match typ {
+        None | Some(ValueType::Basic(BasicValueType::Str)) => {
+            serde_json::Value::String(cell.to_string())
+        }
+        Some(_) if cell.is_empty() => serde_json::Value::Null,
+        Some(ValueType::Basic(
+            BasicValueType::Bool
+            | BasicValueType::Int64
+            | BasicValueType::Float32
+            | BasicValueType::Float64
+            | BasicValueType::Json,
+        ))
+        | Some(ValueType::Struct(_) | ValueType::Table(_)) => serde_json::from_str(cell)
+            .unwrap_or_else(|_| serde_json::Value::String(cell.to_string())),
+        Some(_) => serde_json::Value::String(cell.to_string()),
+    }
+}
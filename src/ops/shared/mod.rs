@@ -0,0 +1,5 @@
+pub mod csv_cell;
+pub mod pattern_matcher;
+pub mod postgres;
+#[cfg(test)]
+pub mod test_dir;
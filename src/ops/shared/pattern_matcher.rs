@@ -0,0 +1,46 @@
+use crate::prelude::*;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+fn build_glob_set(patterns: Vec<String>) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern.as_str())?);
+    }
+    Ok(builder.build()?)
+}
+
+/// Matches paths (or object keys) of a source against its included and excluded glob patterns.
+pub struct PatternMatcher {
+    /// All files are included if not set.
+    included_glob_set: Option<GlobSet>,
+    excluded_glob_set: Option<GlobSet>,
+}
+
+impl PatternMatcher {
+    pub fn new(
+        included_patterns: Option<Vec<String>>,
+        excluded_patterns: Option<Vec<String>>,
+    ) -> Result<Self> {
+        Ok(Self {
+            included_glob_set: included_patterns.map(build_glob_set).transpose()?,
+            excluded_glob_set: excluded_patterns.map(build_glob_set).transpose()?,
+        })
+    }
+
+    /// Whether the path matches the excluded patterns. Also used for directories, to skip everything under them.
+    pub fn is_excluded(&self, path: impl AsRef<Path>) -> bool {
+        self.excluded_glob_set
+            .as_ref()
+            .is_some_and(|glob_set| glob_set.is_match(path))
+    }
+
+    pub fn is_file_included(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        self.included_glob_set
+            .as_ref()
+            .is_none_or(|glob_set| glob_set.is_match(path))
+            && !self.is_excluded(path)
+    }
+}
@@ -0,0 +1,18 @@
+use std::path::PathBuf;
+
+/// A temporary directory for tests, removed with everything in it when dropped.
+pub struct TestDir(pub PathBuf);
+
+impl TestDir {
+    pub fn new(prefix: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("cocoindex-{prefix}-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
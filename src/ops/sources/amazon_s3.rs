@@ -2,11 +2,19 @@ use crate::fields_value;
 use async_stream::try_stream;
 use aws_config::BehaviorVersion;
 use aws_sdk_s3::Client;
-use globset::{Glob, GlobSet, GlobSetBuilder};
+use aws_sdk_s3::config::{Credentials, Region};
 use std::sync::Arc;
 
 use crate::base::field_attrs;
 use crate::ops::sdk::*;
+use crate::ops::shared::pattern_matcher::PatternMatcher;
+
+#[derive(Debug, Deserialize)]
+pub struct CredentialSpec {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
 
 #[derive(Debug, Deserialize)]
 pub struct Spec {
@@ -16,6 +24,15 @@ pub struct Spec {
     included_patterns: Option<Vec<String>>,
     excluded_patterns: Option<Vec<String>>,
     sqs_queue_url: Option<String>,
+
+    /// Endpoint of an S3-compatible store (e.g. MinIO), instead of Amazon S3.
+    endpoint_url: Option<String>,
+    region: Option<String>,
+    /// Address buckets by path (`{endpoint}/{bucket}`) instead of subdomain (`{bucket}.{endpoint}`).
+    #[serde(default)]
+    force_path_style: bool,
+    /// If not provided, credentials are resolved from the default AWS config.
+    credential: Option<spec::AuthEntryReference<CredentialSpec>>,
 }
 
 struct SqsContext {
@@ -40,26 +57,10 @@ struct Executor {
     bucket_name: String,
     prefix: Option<String>,
     binary: bool,
-    included_glob_set: Option<GlobSet>,
-    excluded_glob_set: Option<GlobSet>,
+    pattern_matcher: PatternMatcher,
     sqs_context: Option<Arc<SqsContext>>,
 }
 
-impl Executor {
-    fn is_excluded(&self, key: &str) -> bool {
-        self.excluded_glob_set
-            .as_ref()
-            .is_some_and(|glob_set| glob_set.is_match(key))
-    }
-
-    fn is_file_included(&self, key: &str) -> bool {
-        self.included_glob_set
-            .as_ref()
-            .is_none_or(|glob_set| glob_set.is_match(key))
-            && !self.is_excluded(key)
-    }
-}
-
 fn datetime_to_ordinal(dt: &aws_sdk_s3::primitives::DateTime) -> Ordinal {
     Ordinal(Some((dt.as_nanos() / 1000) as i64))
 }
@@ -89,15 +90,7 @@ impl SourceExecutor for Executor {
                         if let Some(key) = obj.key() {
                             // Only include files (not folders)
                             if key.ends_with('/') { continue; }
-                            let include = self.included_glob_set
-                                .as_ref()
-                                .map(|gs| gs.is_match(key))
-                                .unwrap_or(true);
-                            let exclude = self.excluded_glob_set
-                                .as_ref()
-                                .map(|gs| gs.is_match(key))
-                                .unwrap_or(false);
-                            if include && !exclude {
+                            if self.pattern_matcher.is_file_included(key) {
                                 batch.push(PartialSourceRowMetadata {
                                     key: KeyValue::Str(key.to_string().into()),
                                     ordinal: obj.last_modified().map(datetime_to_ordinal),
@@ -124,7 +117,7 @@ impl SourceExecutor for Executor {
         options: &SourceExecutorGetOptions,
     ) -> Result<PartialSourceRowData> {
         let key_str = key.str_value()?;
-        if !self.is_file_included(key_str) {
+        if !self.pattern_matcher.is_file_included(&**key_str) {
             return Ok(PartialSourceRowData {
                 value: Some(SourceValue::NonExistence),
                 ordinal: Some(Ordinal::unavailable()),
@@ -326,16 +319,36 @@ impl SourceFactoryBase for Factory {
     async fn build_executor(
         self: Arc<Self>,
         spec: Spec,
-        _context: Arc<FlowInstanceContext>,
+        context: Arc<FlowInstanceContext>,
     ) -> Result<Box<dyn SourceExecutor>> {
-        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        let mut config_loader = aws_config::defaults(BehaviorVersion::latest());
+        if let Some(region) = spec.region {
+            config_loader = config_loader.region(Region::new(region));
+        }
+        if let Some(credential) = &spec.credential {
+            let credential = context.auth_registry.get(credential)?;
+            config_loader = config_loader.credentials_provider(Credentials::new(
+                credential.access_key_id,
+                credential.secret_access_key,
+                credential.session_token,
+                None,
+                "cocoindex",
+            ));
+        }
+        let config = config_loader.load().await;
+        let mut s3_config = aws_sdk_s3::config::Builder::from(&config);
+        if let Some(endpoint_url) = spec.endpoint_url {
+            s3_config = s3_config.endpoint_url(endpoint_url);
+        }
+        if spec.force_path_style {
+            s3_config = s3_config.force_path_style(true);
+        }
         Ok(Box::new(Executor {
-            client: Client::new(&config),
+            client: Client::from_conf(s3_config.build()),
             bucket_name: spec.bucket_name,
             prefix: spec.prefix,
             binary: spec.binary,
-            included_glob_set: spec.included_patterns.map(build_glob_set).transpose()?,
-            excluded_glob_set: spec.excluded_patterns.map(build_glob_set).transpose()?,
+            pattern_matcher: PatternMatcher::new(spec.included_patterns, spec.excluded_patterns)?,
             sqs_context: spec.sqs_queue_url.map(|url| {
                 Arc::new(SqsContext {
                     client: aws_sdk_sqs::Client::new(&config),
@@ -345,11 +358,3 @@ impl SourceFactoryBase for Factory {
         }))
     }
 }
-
-fn build_glob_set(patterns: Vec<String>) -> Result<GlobSet> {
-    let mut builder = GlobSetBuilder::new();
-    for pattern in patterns {
-        builder.add(Glob::new(pattern.as_str())?);
-    }
-    Ok(builder.build()?)
-}
@@ -0,0 +1,523 @@
+use crate::fields_value;
+use async_stream::try_stream;
+use azure_core::StatusCode;
+use azure_core::error::ErrorKind;
+use azure_storage::{CloudLocation, StorageCredentials};
+use azure_storage_blobs::prelude::{ClientBuilder, ContainerClient};
+use azure_storage_queues::{QueueClient, QueueServiceClientBuilder};
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use std::sync::Arc;
+
+use crate::base::field_attrs;
+use crate::ops::sdk::*;
+use crate::ops::shared::pattern_matcher::PatternMatcher;
+
+/// Interval to poll the queue again when it has no messages, as Azure Queue Storage doesn't support long polling.
+const QUEUE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Debug, Deserialize)]
+pub struct CredentialSpec {
+    account_key: Option<String>,
+    sas_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Spec {
+    account_name: String,
+    container_name: String,
+    prefix: Option<String>,
+    binary: bool,
+    included_patterns: Option<Vec<String>>,
+    excluded_patterns: Option<Vec<String>>,
+
+    /// Endpoint of the Blob service, e.g. of the Azurite emulator. Derived from the account name if not specified.
+    endpoint_url: Option<String>,
+    /// If not provided, the container is accessed anonymously.
+    credential: Option<spec::AuthEntryReference<CredentialSpec>>,
+
+    /// Queue receiving Event Grid events of the container, in the same storage account.
+    queue_name: Option<String>,
+    queue_endpoint_url: Option<String>,
+}
+
+struct Executor {
+    container_client: ContainerClient,
+    prefix: Option<String>,
+    binary: bool,
+    pattern_matcher: PatternMatcher,
+    queue_client: Option<Arc<QueueClient>>,
+}
+
+fn datetime_to_ordinal(dt: &time::OffsetDateTime) -> Ordinal {
+    Ordinal(Some((dt.unix_timestamp_nanos() / 1000) as i64))
+}
+
+fn is_not_found(err: &azure_core::Error) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::HttpResponse {
+            status: StatusCode::NotFound,
+            ..
+        }
+    )
+}
+
+#[async_trait]
+impl SourceExecutor for Executor {
+    fn list<'a>(
+        &'a self,
+        options: &'a SourceExecutorListOptions,
+    ) -> BoxStream<'a, Result<Vec<PartialSourceRowMetadata>>> {
+        try_stream! {
+            let mut list_call = self.container_client.list_blobs();
+            if let Some(prefix) = &self.prefix {
+                list_call = list_call.prefix(prefix.clone());
+            }
+            let mut pages = list_call.into_stream();
+            while let Some(page) = pages.next().await {
+                let page = page?;
+                let batch = page
+                    .blobs
+                    .blobs()
+                    // Only include files (not folders)
+                    .filter(|blob| {
+                        !blob.name.ends_with('/')
+                            && self.pattern_matcher.is_file_included(&blob.name)
+                    })
+                    .map(|blob| PartialSourceRowMetadata {
+                        key: KeyValue::Str(blob.name.as_str().into()),
+                        ordinal: options
+                            .include_ordinal
+                            .then(|| datetime_to_ordinal(&blob.properties.last_modified)),
+                    })
+                    .collect::<Vec<_>>();
+                if !batch.is_empty() {
+                    yield batch;
+                }
+            }
+        }
+        .boxed()
+    }
+
+    async fn get_value(
+        &self,
+        key: &KeyValue,
+        options: &SourceExecutorGetOptions,
+    ) -> Result<PartialSourceRowData> {
+        let key_str = key.str_value()?;
+        let non_existence = PartialSourceRowData {
+            value: Some(SourceValue::NonExistence),
+            ordinal: Some(Ordinal::unavailable()),
+        };
+        if !self.pattern_matcher.is_file_included(&**key_str) {
+            return Ok(non_existence);
+        }
+        let blob_client = self.container_client.blob_client(key_str.as_ref());
+        if !options.include_value {
+            let resp = match blob_client.get_properties().await {
+                Err(e) if is_not_found(&e) => return Ok(non_existence),
+                r => r?,
+            };
+            return Ok(PartialSourceRowData {
+                value: None,
+                ordinal: options
+                    .include_ordinal
+                    .then(|| datetime_to_ordinal(&resp.blob.properties.last_modified)),
+            });
+        }
+        let mut chunks = blob_client.get().into_stream();
+        let mut ordinal = None;
+        let mut content = Vec::new();
+        while let Some(chunk) = chunks.next().await {
+            let chunk = match chunk {
+                Err(e) if is_not_found(&e) => return Ok(non_existence),
+                r => r?,
+            };
+            ordinal
+                .get_or_insert_with(|| datetime_to_ordinal(&chunk.blob.properties.last_modified));
+            content.extend(chunk.data.collect().await?);
+        }
+        Ok(PartialSourceRowData {
+            value: Some(SourceValue::Existence(if self.binary {
+                fields_value!(content)
+            } else {
+                fields_value!(String::from_utf8_lossy(&content).to_string())
+            })),
+            ordinal: ordinal.filter(|_| options.include_ordinal),
+        })
+    }
+
+    async fn change_stream(
+        &self,
+    ) -> Result<Option<BoxStream<'async_trait, Result<SourceChangeMessage>>>> {
+        let queue_client = if let Some(queue_client) = &self.queue_client {
+            queue_client
+        } else {
+            return Ok(None);
+        };
+        let stream = stream! {
+            loop {
+                match self.poll_queue(queue_client).await {
+                    Ok(messages) => {
+                        if messages.is_empty() {
+                            tokio::time::sleep(QUEUE_POLL_INTERVAL).await;
+                        }
+                        for message in messages {
+                            yield Ok(message);
+                        }
+                    }
+                    Err(e) => {
+                        yield Err(e);
+                        tokio::time::sleep(QUEUE_POLL_INTERVAL).await;
+                    }
+                };
+            }
+        };
+        Ok(Some(stream.boxed()))
+    }
+}
+
+/// An event of Event Grid, in either the Event Grid schema or the CloudEvents schema.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum BlobEvents {
+    Single(BlobEvent),
+    Batch(Vec<BlobEvent>),
+}
+
+#[derive(Debug, Deserialize)]
+struct BlobEvent {
+    #[serde(rename = "eventType", alias = "type")]
+    event_type: String,
+    /// In the form of `/blobServices/default/containers/{container}/blobs/{blob}`.
+    subject: String,
+}
+
+/// Parse Event Grid events in a queue message, and return names of changed blobs in the container.
+fn parse_changed_blobs(message_text: &str, container_name: &str) -> Result<Vec<String>> {
+    let events: BlobEvents = match serde_json::from_str(message_text) {
+        Ok(events) => events,
+        // Messages may be Base64-encoded, depending on the producer.
+        Err(e) => match BASE64_STANDARD.decode(message_text.trim()) {
+            Ok(decoded) => serde_json::from_slice(&decoded)?,
+            Err(_) => Err(e)?,
+        },
+    };
+    let events = match events {
+        BlobEvents::Single(event) => vec![event],
+        BlobEvents::Batch(events) => events,
+    };
+    let subject_prefix = format!("/blobServices/default/containers/{container_name}/blobs/");
+    let blob_names = events
+        .into_iter()
+        .filter(|event| {
+            event.event_type == "Microsoft.Storage.BlobCreated"
+                || event.event_type == "Microsoft.Storage.BlobDeleted"
+        })
+        .filter_map(|event| {
+            event
+                .subject
+                .strip_prefix(&subject_prefix)
+                .map(|blob_name| blob_name.to_string())
+        })
+        .collect();
+    Ok(blob_names)
+}
+
+impl Executor {
+    async fn poll_queue(
+        &self,
+        queue_client: &Arc<QueueClient>,
+    ) -> Result<Vec<SourceChangeMessage>> {
+        let resp = queue_client.get_messages().number_of_messages(32u8).await?;
+        let mut change_messages = vec![];
+        for message in resp.messages.into_iter() {
+            let pop_receipt_client = queue_client.pop_receipt_client(message.pop_receipt());
+            let blob_names = match parse_changed_blobs(
+                &message.message_text,
+                self.container_client.container_name(),
+            ) {
+                Ok(blob_names) => blob_names,
+                Err(e) => {
+                    // It'll never be parsed, so it's deleted below instead of being received again.
+                    warn!(
+                        "Skipped ill-formed message {} in queue `{}`: {e:?}",
+                        message.message_id,
+                        queue_client.queue_name()
+                    );
+                    vec![]
+                }
+            };
+            let changes = blob_names
+                .into_iter()
+                .filter(|blob_name| {
+                    self.prefix
+                        .as_ref()
+                        .is_none_or(|prefix| blob_name.starts_with(prefix))
+                })
+                .map(|blob_name| SourceChange {
+                    key: KeyValue::Str(blob_name.into()),
+                    data: None,
+                })
+                .collect::<Vec<_>>();
+            if !changes.is_empty() {
+                change_messages.push(SourceChangeMessage {
+                    changes,
+                    ack_fn: Some(Box::new(move || {
+                        async move {
+                            pop_receipt_client.delete().await?;
+                            Ok(())
+                        }
+                        .boxed()
+                    })),
+                });
+            } else if let Err(e) = pop_receipt_client.delete().await {
+                // The message will be received again, so no need to fail other messages.
+                warn!(
+                    "Failed to delete message {} in queue `{}`: {e:?}",
+                    message.message_id,
+                    queue_client.queue_name()
+                );
+            }
+        }
+        Ok(change_messages)
+    }
+}
+
+pub struct Factory;
+
+impl Factory {
+    fn build_credentials(spec: &Spec, context: &FlowInstanceContext) -> Result<StorageCredentials> {
+        let Some(credential) = &spec.credential else {
+            return Ok(StorageCredentials::anonymous());
+        };
+        let credential = context.auth_registry.get(credential)?;
+        let credentials = match (credential.account_key, credential.sas_token) {
+            (Some(account_key), None) => {
+                StorageCredentials::access_key(spec.account_name.clone(), account_key)
+            }
+            (None, Some(sas_token)) => StorageCredentials::sas_token(sas_token)?,
+            _ => api_bail!("Exactly one of `account_key` and `sas_token` should be provided"),
+        };
+        Ok(credentials)
+    }
+
+    fn cloud_location(account_name: &str, endpoint_url: Option<String>) -> CloudLocation {
+        match endpoint_url {
+            Some(uri) => CloudLocation::Custom {
+                account: account_name.to_string(),
+                uri: uri.trim_end_matches('/').to_string(),
+            },
+            None => CloudLocation::Public {
+                account: account_name.to_string(),
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl SourceFactoryBase for Factory {
+    type Spec = Spec;
+
+    fn name(&self) -> &str {
+        "AzureBlob"
+    }
+
+    async fn get_output_schema(
+        &self,
+        spec: &Spec,
+        _context: &FlowInstanceContext,
+    ) -> Result<EnrichedValueType> {
+        let mut struct_schema = StructSchema::default();
+        let mut schema_builder = StructSchemaBuilder::new(&mut struct_schema);
+        let filename_field = schema_builder.add_field(FieldSchema::new(
+            "filename",
+            make_output_type(BasicValueType::Str),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "content",
+            make_output_type(if spec.binary {
+                BasicValueType::Bytes
+            } else {
+                BasicValueType::Str
+            })
+            .with_attr(
+                field_attrs::CONTENT_FILENAME,
+                serde_json::to_value(filename_field.to_field_ref())?,
+            ),
+        ));
+        Ok(make_output_type(TableSchema::new(
+            TableKind::KTable,
+            struct_schema,
+        )))
+    }
+
+    async fn build_executor(
+        self: Arc<Self>,
+        spec: Spec,
+        context: Arc<FlowInstanceContext>,
+    ) -> Result<Box<dyn SourceExecutor>> {
+        let credentials = Self::build_credentials(&spec, &context)?;
+        let container_client = ClientBuilder::with_location(
+            Self::cloud_location(&spec.account_name, spec.endpoint_url),
+            credentials.clone(),
+        )
+        .container_client(spec.container_name);
+        let queue_client = spec.queue_name.map(|queue_name| {
+            Arc::new(
+                QueueServiceClientBuilder::with_location(
+                    Self::cloud_location(&spec.account_name, spec.queue_endpoint_url),
+                    credentials,
+                )
+                .build()
+                .queue_client(queue_name),
+            )
+        });
+        Ok(Box::new(Executor {
+            container_client,
+            prefix: spec.prefix,
+            binary: spec.binary,
+            pattern_matcher: PatternMatcher::new(spec.included_patterns, spec.excluded_patterns)?,
+            queue_client,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_changed_blobs() {
+        let event_grid_event = r#"{
+            "topic": "/subscriptions/s/resourceGroups/g/providers/Microsoft.Storage/storageAccounts/a",
+            "subject": "/blobServices/default/containers/docs/blobs/dir/a.md",
+            "eventType": "Microsoft.Storage.BlobCreated",
+            "id": "1",
+            "data": {"api": "PutBlob"}
+        }"#;
+        assert_eq!(
+            parse_changed_blobs(event_grid_event, "docs").unwrap(),
+            vec!["dir/a.md"]
+        );
+        assert!(
+            parse_changed_blobs(event_grid_event, "other")
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            parse_changed_blobs(&BASE64_STANDARD.encode(event_grid_event), "docs").unwrap(),
+            vec!["dir/a.md"]
+        );
+
+        let cloud_events = r#"[
+            {"specversion": "1.0", "type": "Microsoft.Storage.BlobDeleted", "source": "s", "id": "2",
+             "subject": "/blobServices/default/containers/docs/blobs/b.md"},
+            {"specversion": "1.0", "type": "Microsoft.Storage.BlobTierChanged", "source": "s", "id": "3",
+             "subject": "/blobServices/default/containers/docs/blobs/c.md"}
+        ]"#;
+        assert_eq!(
+            parse_changed_blobs(cloud_events, "docs").unwrap(),
+            vec!["b.md"]
+        );
+
+        assert!(parse_changed_blobs("not an event", "docs").is_err());
+    }
+
+    /// Runs against the Azurite emulator on the host given by `COCOINDEX_TEST_AZURITE_HOST`, e.g. `127.0.0.1` with
+    /// Azurite started by `docker run -d -p 10000:10000 -p 10001:10001 mcr.microsoft.com/azure-storage/azurite`.
+    /// Skipped if it's not set.
+    #[tokio::test]
+    async fn test_azurite() {
+        let Ok(host) = std::env::var("COCOINDEX_TEST_AZURITE_HOST") else {
+            return;
+        };
+        let container_name = format!("cocoindex-test-{}", uuid::Uuid::new_v4());
+        let blob_location = CloudLocation::Emulator {
+            address: host.clone(),
+            port: 10000,
+        };
+        let queue_location = CloudLocation::Emulator {
+            address: host,
+            port: 10001,
+        };
+        let container_client =
+            ClientBuilder::with_location(blob_location.clone(), StorageCredentials::emulator())
+                .container_client(&container_name);
+        container_client.create().await.unwrap();
+        for (name, content) in [("docs/a.md", "a"), ("docs/b.txt", "b"), ("other/c.md", "c")] {
+            container_client
+                .blob_client(name)
+                .put_block_blob(content)
+                .await
+                .unwrap();
+        }
+        let queue_client = QueueServiceClientBuilder::with_location(
+            queue_location,
+            StorageCredentials::emulator(),
+        )
+        .build()
+        .queue_client(&container_name);
+        queue_client.create().await.unwrap();
+
+        let executor = Executor {
+            container_client: container_client.clone(),
+            prefix: Some("docs/".to_string()),
+            binary: false,
+            pattern_matcher: PatternMatcher::new(None, Some(vec!["**/*.txt".to_string()])).unwrap(),
+            queue_client: Some(Arc::new(queue_client.clone())),
+        };
+        let rows = executor
+            .list(&SourceExecutorListOptions {
+                include_ordinal: true,
+            })
+            .try_concat()
+            .await
+            .unwrap();
+        assert_eq!(
+            rows.iter().map(|row| row.key.clone()).collect::<Vec<_>>(),
+            vec![KeyValue::Str("docs/a.md".into())]
+        );
+        assert!(rows[0].ordinal.is_some_and(|o| o.0.is_some()));
+
+        let options = SourceExecutorGetOptions {
+            include_ordinal: true,
+            include_value: true,
+        };
+        let data = executor
+            .get_value(&KeyValue::Str("docs/a.md".into()), &options)
+            .await
+            .unwrap();
+        assert_eq!(data.ordinal, rows[0].ordinal);
+        assert!(matches!(
+            data.value,
+            Some(SourceValue::Existence(values)) if values.fields == vec!["a".to_string().into()]
+        ));
+        let data = executor
+            .get_value(&KeyValue::Str("docs/missing.md".into()), &options)
+            .await
+            .unwrap();
+        assert!(matches!(data.value, Some(SourceValue::NonExistence)));
+
+        let event = serde_json::json!({
+            "subject": format!("/blobServices/default/containers/{container_name}/blobs/docs/a.md"),
+            "eventType": "Microsoft.Storage.BlobCreated",
+        });
+        queue_client.put_message(event.to_string()).await.unwrap();
+        let mut change_stream = executor.change_stream().await.unwrap().unwrap();
+        let message = change_stream.next().await.unwrap().unwrap();
+        assert_eq!(
+            message
+                .changes
+                .iter()
+                .map(|change| change.key.clone())
+                .collect::<Vec<_>>(),
+            vec![KeyValue::Str("docs/a.md".into())]
+        );
+        (message.ack_fn.unwrap())().await.unwrap();
+
+        queue_client.delete().await.unwrap();
+        container_client.delete().await.unwrap();
+    }
+}
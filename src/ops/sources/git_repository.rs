@@ -0,0 +1,427 @@
+use async_stream::{stream, try_stream};
+use log::warn;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::base::field_attrs;
+use crate::ops::shared::pattern_matcher::PatternMatcher;
+use crate::{fields_value, ops::sdk::*};
+
+const DEFAULT_REV: &str = "HEAD";
+
+#[derive(Debug, Deserialize)]
+pub struct Spec {
+    path: String,
+    rev: Option<String>,
+    binary: bool,
+    included_patterns: Option<Vec<String>>,
+    excluded_patterns: Option<Vec<String>>,
+    rev_poll_interval: Option<std::time::Duration>,
+}
+
+/// Included files at a specific commit, with their blob IDs.
+///
+/// Files have no ordinals. Commit times aren't monotonic (commits within the same second, rewound or
+/// force-pushed refs), and nothing derived from the repository alone can stay monotonic across restarts.
+/// So content fingerprints decide whether a file needs reprocessing.
+struct TreeState {
+    commit_id: git2::Oid,
+    files: HashMap<String, git2::Oid>,
+}
+
+impl TreeState {
+    /// Paths of files that are added, removed or changed in `self` compared to `prev`.
+    fn changed_paths<'a>(&'a self, prev: &'a TreeState) -> impl Iterator<Item = &'a String> {
+        let changed = self
+            .files
+            .iter()
+            .filter_map(|(path, blob_id)| (prev.files.get(path) != Some(blob_id)).then_some(path));
+        let removed = prev
+            .files
+            .keys()
+            .filter(|path| !self.files.contains_key(*path));
+        changed.chain(removed)
+    }
+}
+
+struct Repo {
+    path: PathBuf,
+    rev: String,
+    pattern_matcher: PatternMatcher,
+}
+
+impl Repo {
+    fn open(&self) -> Result<git2::Repository> {
+        Ok(git2::Repository::open(&self.path)?)
+    }
+
+    fn list_files(&self, tree: &git2::Tree<'_>) -> Result<HashMap<String, git2::Oid>> {
+        let mut files = HashMap::new();
+        tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+            let Some(name) = entry.name() else {
+                warn!("Skipped ill-formed file path under `{dir}` in git repository");
+                return git2::TreeWalkResult::Skip;
+            };
+            let path = format!("{dir}{name}");
+            match entry.kind() {
+                Some(git2::ObjectType::Tree) if self.pattern_matcher.is_excluded(&path) => {
+                    git2::TreeWalkResult::Skip
+                }
+                Some(git2::ObjectType::Blob) => {
+                    if self.pattern_matcher.is_file_included(&path) {
+                        files.insert(path, entry.id());
+                    }
+                    git2::TreeWalkResult::Ok
+                }
+                _ => git2::TreeWalkResult::Ok,
+            }
+        })?;
+        Ok(files)
+    }
+
+    /// Resolve the state at the current tip of `rev`. Returns `None` if the tip is the same as `prev`.
+    fn resolve_state(&self, prev: Option<&TreeState>) -> Result<Option<TreeState>> {
+        let repo = self.open()?;
+        let tip = repo.revparse_single(&self.rev)?.peel_to_commit()?;
+        if prev.is_some_and(|prev| prev.commit_id == tip.id()) {
+            return Ok(None);
+        }
+        Ok(Some(TreeState {
+            commit_id: tip.id(),
+            files: self.list_files(&tip.tree()?)?,
+        }))
+    }
+
+    fn read_blob(&self, blob_id: git2::Oid) -> Result<Vec<u8>> {
+        let repo = self.open()?;
+        let blob = repo.find_blob(blob_id)?;
+        Ok(blob.content().to_vec())
+    }
+}
+
+struct Executor {
+    repo: Arc<Repo>,
+    binary: bool,
+    rev_poll_interval: Option<std::time::Duration>,
+    state: tokio::sync::Mutex<Option<Arc<TreeState>>>,
+}
+
+impl Executor {
+    /// Get the state at the current tip of the configured rev, updating the cached one if the tip moves.
+    async fn current_state(&self) -> Result<Arc<TreeState>> {
+        let mut state = self.state.lock().await;
+        let prev_state = state.clone();
+        let repo = self.repo.clone();
+        let new_state =
+            tokio::task::spawn_blocking(move || repo.resolve_state(prev_state.as_deref()))
+                .await??;
+        if let Some(new_state) = new_state {
+            *state = Some(Arc::new(new_state));
+        }
+        state.clone().ok_or_else(invariance_violation)
+    }
+}
+
+#[async_trait]
+impl SourceExecutor for Executor {
+    fn list<'a>(
+        &'a self,
+        options: &'a SourceExecutorListOptions,
+    ) -> BoxStream<'a, Result<Vec<PartialSourceRowMetadata>>> {
+        try_stream! {
+            let state = self.current_state().await?;
+            yield state
+                .files
+                .keys()
+                .map(|path| PartialSourceRowMetadata {
+                    key: KeyValue::Str(path.as_str().into()),
+                    ordinal: options.include_ordinal.then_some(Ordinal::unavailable()),
+                })
+                .collect();
+        }
+        .boxed()
+    }
+
+    async fn get_value(
+        &self,
+        key: &KeyValue,
+        options: &SourceExecutorGetOptions,
+    ) -> Result<PartialSourceRowData> {
+        let path = key.str_value()?;
+        let blob_id = if self.repo.pattern_matcher.is_file_included(&**path) {
+            self.current_state()
+                .await?
+                .files
+                .get(path.as_ref())
+                .cloned()
+        } else {
+            None
+        };
+        let Some(blob_id) = blob_id else {
+            return Ok(PartialSourceRowData {
+                value: Some(SourceValue::NonExistence),
+                ordinal: Some(Ordinal::unavailable()),
+            });
+        };
+        let value = if options.include_value {
+            let repo = self.repo.clone();
+            let content = tokio::task::spawn_blocking(move || repo.read_blob(blob_id)).await??;
+            let content = if self.binary {
+                fields_value!(content)
+            } else {
+                fields_value!(String::from_utf8_lossy(&content).to_string())
+            };
+            Some(SourceValue::Existence(content))
+        } else {
+            None
+        };
+        Ok(PartialSourceRowData {
+            value,
+            ordinal: options.include_ordinal.then_some(Ordinal::unavailable()),
+        })
+    }
+
+    async fn change_stream(
+        &self,
+    ) -> Result<Option<BoxStream<'async_trait, Result<SourceChangeMessage>>>> {
+        let Some(poll_interval) = self.rev_poll_interval else {
+            return Ok(None);
+        };
+        let mut prev_state = self.current_state().await?;
+        let mut interval = tokio::time::interval(poll_interval);
+        interval.tick().await;
+        // Errors (e.g. a locked index during a fetch) are yielded without ending the stream.
+        let stream = stream! {
+            loop {
+                interval.tick().await;
+                let state = match self.current_state().await {
+                    Ok(state) => state,
+                    Err(e) => {
+                        yield Err(e);
+                        continue;
+                    }
+                };
+                if Arc::ptr_eq(&state, &prev_state) {
+                    continue;
+                }
+                let changes: Vec<_> = state
+                    .changed_paths(&prev_state)
+                    .map(|path| SourceChange {
+                        key: KeyValue::Str(path.as_str().into()),
+                        data: None,
+                    })
+                    .collect();
+                prev_state = state;
+                if !changes.is_empty() {
+                    yield Ok(SourceChangeMessage {
+                        changes,
+                        ack_fn: None,
+                    });
+                }
+            }
+        };
+        Ok(Some(stream.boxed()))
+    }
+}
+
+pub struct Factory;
+
+#[async_trait]
+impl SourceFactoryBase for Factory {
+    type Spec = Spec;
+
+    fn name(&self) -> &str {
+        "GitRepository"
+    }
+
+    async fn get_output_schema(
+        &self,
+        spec: &Spec,
+        _context: &FlowInstanceContext,
+    ) -> Result<EnrichedValueType> {
+        let mut struct_schema = StructSchema::default();
+        let mut schema_builder = StructSchemaBuilder::new(&mut struct_schema);
+        let filename_field = schema_builder.add_field(FieldSchema::new(
+            "filename",
+            make_output_type(BasicValueType::Str),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "content",
+            make_output_type(if spec.binary {
+                BasicValueType::Bytes
+            } else {
+                BasicValueType::Str
+            })
+            .with_attr(
+                field_attrs::CONTENT_FILENAME,
+                serde_json::to_value(filename_field.to_field_ref())?,
+            ),
+        ));
+
+        Ok(make_output_type(TableSchema::new(
+            TableKind::KTable,
+            struct_schema,
+        )))
+    }
+
+    async fn build_executor(
+        self: Arc<Self>,
+        spec: Spec,
+        _context: Arc<FlowInstanceContext>,
+    ) -> Result<Box<dyn SourceExecutor>> {
+        let repo = Repo {
+            path: PathBuf::from(spec.path),
+            rev: spec.rev.unwrap_or_else(|| DEFAULT_REV.to_string()),
+            pattern_matcher: PatternMatcher::new(spec.included_patterns, spec.excluded_patterns)?,
+        };
+        let repo = Arc::new(repo);
+        // Resolving the initial state also validates the repository and the rev.
+        let state = {
+            let repo = repo.clone();
+            tokio::task::spawn_blocking(move || repo.resolve_state(None)).await?
+        }
+        .with_context(|| {
+            format!(
+                "Failed to resolve rev `{}` in git repository `{}`",
+                repo.rev,
+                repo.path.display()
+            )
+        })?;
+        Ok(Box::new(Executor {
+            repo,
+            binary: spec.binary,
+            rev_poll_interval: spec.rev_poll_interval,
+            state: tokio::sync::Mutex::new(state.map(Arc::new)),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::shared::test_dir::TestDir;
+
+    struct TestRepo {
+        dir: TestDir,
+        repo: git2::Repository,
+    }
+
+    impl TestRepo {
+        fn new() -> Self {
+            let dir = TestDir::new("git");
+            let repo = git2::Repository::init_bare(&dir.0).unwrap();
+            Self { dir, repo }
+        }
+
+        fn commit(&self, files: &[(&str, &str)], time_secs: i64) -> git2::Oid {
+            let mut index = git2::Index::new().unwrap();
+            for (path, content) in files {
+                let blob_id = self.repo.blob(content.as_bytes()).unwrap();
+                index
+                    .add(&git2::IndexEntry {
+                        ctime: git2::IndexTime::new(0, 0),
+                        mtime: git2::IndexTime::new(0, 0),
+                        dev: 0,
+                        ino: 0,
+                        mode: 0o100644,
+                        uid: 0,
+                        gid: 0,
+                        file_size: 0,
+                        id: blob_id,
+                        flags: 0,
+                        flags_extended: 0,
+                        path: path.as_bytes().to_vec(),
+                    })
+                    .unwrap();
+            }
+            let tree = self
+                .repo
+                .find_tree(index.write_tree_to(&self.repo).unwrap())
+                .unwrap();
+            let signature =
+                git2::Signature::new("test", "test@example.com", &git2::Time::new(time_secs, 0))
+                    .unwrap();
+            let parent = self
+                .repo
+                .head()
+                .ok()
+                .and_then(|head| head.peel_to_commit().ok());
+            self.repo
+                .commit(
+                    Some("HEAD"),
+                    &signature,
+                    &signature,
+                    "test",
+                    &tree,
+                    parent.iter().collect::<Vec<_>>().as_slice(),
+                )
+                .unwrap()
+        }
+
+        fn executor(&self, excluded_patterns: Option<Vec<String>>) -> Executor {
+            Executor {
+                repo: Arc::new(Repo {
+                    path: self.dir.0.clone(),
+                    rev: DEFAULT_REV.to_string(),
+                    pattern_matcher: PatternMatcher::new(None, excluded_patterns).unwrap(),
+                }),
+                binary: false,
+                rev_poll_interval: None,
+                state: tokio::sync::Mutex::new(None),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_changed_paths_between_commits() {
+        let test_repo = TestRepo::new();
+        test_repo.commit(&[("a.txt", "a1"), ("src/b.rs", "b1")], 1000);
+        let executor = test_repo.executor(Some(vec!["target".to_string()]));
+        let state = executor.current_state().await.unwrap();
+
+        // Commit times aren't used, so changes are detected even if they go backwards.
+        test_repo.commit(
+            &[("a.txt", "a2"), ("src/b.rs", "b1"), ("target/out", "x")],
+            500,
+        );
+        let new_state = executor.current_state().await.unwrap();
+        assert!(!new_state.files.contains_key("target/out"));
+        assert_eq!(
+            new_state.changed_paths(&state).collect::<Vec<_>>(),
+            vec!["a.txt"]
+        );
+
+        let data = executor
+            .get_value(
+                &KeyValue::Str("a.txt".into()),
+                &SourceExecutorGetOptions {
+                    include_value: true,
+                    include_ordinal: true,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(data.ordinal, Some(Ordinal::unavailable()));
+        match data.value {
+            Some(SourceValue::Existence(value)) => {
+                assert_eq!(value, fields_value!("a2".to_string()))
+            }
+            _ => panic!("expected the file to exist"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_removed_file_is_changed() {
+        let test_repo = TestRepo::new();
+        test_repo.commit(&[("a.txt", "a1"), ("b.txt", "b1")], 1000);
+        let executor = test_repo.executor(None);
+        let state = executor.current_state().await.unwrap();
+
+        test_repo.commit(&[("a.txt", "a1")], 2000);
+        let new_state = executor.current_state().await.unwrap();
+        assert_eq!(
+            new_state.changed_paths(&state).collect::<Vec<_>>(),
+            vec!["b.txt"]
+        );
+    }
+}
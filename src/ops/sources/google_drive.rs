@@ -1,13 +1,13 @@
-use chrono::Duration;
 use google_drive3::{
     DriveHub,
-    api::{File, Scope},
+    api::{Change, File, Scope},
     yup_oauth2::{ServiceAccountAuthenticator, read_service_account_key},
 };
 use http_body_util::BodyExt;
 use hyper_rustls::HttpsConnector;
 use hyper_util::client::legacy::connect::HttpConnector;
 use phf::phf_map;
+use sqlx::PgPool;
 
 use crate::base::field_attrs;
 use crate::ops::sdk::*;
@@ -58,20 +58,177 @@ pub struct Spec {
     service_account_credential_path: String,
     binary: bool,
     root_folder_ids: Vec<String>,
+    /// ID of the Shared Drive containing the root folders. Files are in My Drive if not specified.
+    drive_id: Option<String>,
     recent_changes_poll_interval: Option<std::time::Duration>,
 }
 
+fn page_token_table_name(flow_instance_name: &str) -> String {
+    format!(
+        "{}__cocoindex_google_drive_page_tokens",
+        utils::db::sanitize_identifier(flow_instance_name)
+    )
+}
+
+/// Key of the page token of a source in the page token table of the flow.
+fn page_token_state_key(spec: &Spec) -> String {
+    format!(
+        "{}/{}",
+        spec.drive_id.as_deref().unwrap_or_default(),
+        spec.root_folder_ids.iter().sorted().join(",")
+    )
+}
+
+/// Persists the page token of the Changes API in the internal storage,
+/// so changes happened while the flow isn't running are picked up after restart.
+/// The table is created by flow setup, through the `GoogleDrivePageTokenTable` declaration.
+struct PageTokenStore {
+    db_pool: PgPool,
+    table_name: String,
+    state_key: String,
+}
+
+impl PageTokenStore {
+    async fn load(&self) -> Result<Option<String>> {
+        let page_token = sqlx::query_scalar(&format!(
+            "SELECT page_token FROM {} WHERE state_key = $1",
+            self.table_name
+        ))
+        .bind(&self.state_key)
+        .fetch_optional(&self.db_pool)
+        .await?;
+        Ok(page_token)
+    }
+
+    async fn save(&self, page_token: &str) -> Result<()> {
+        sqlx::query(&format!(
+            "INSERT INTO {} (state_key, page_token) VALUES ($1, $2) \
+             ON CONFLICT (state_key) DO UPDATE SET page_token = EXCLUDED.page_token",
+            self.table_name
+        ))
+        .bind(&self.state_key)
+        .bind(page_token)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct PendingPageTokens {
+    next_seq: u64,
+    /// Page tokens by sequence number of polls, with whether the poll is acknowledged.
+    page_tokens: std::collections::BTreeMap<u64, (String, bool)>,
+}
+
+/// Tracks page tokens of polls whose changes are still being processed.
+/// A page token is only committable after changes of all polls up to it are processed.
+#[derive(Default)]
+struct PageTokenTracker {
+    pending: Mutex<PendingPageTokens>,
+}
+
+impl PageTokenTracker {
+    fn add(&self, page_token: String) -> u64 {
+        let mut pending = self.pending.lock().unwrap();
+        let seq = pending.next_seq;
+        pending.next_seq += 1;
+        pending.page_tokens.insert(seq, (page_token, false));
+        seq
+    }
+
+    /// Returns the page token to commit, if it advances.
+    fn ack(&self, seq: u64) -> Option<String> {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some((_, acked)) = pending.page_tokens.get_mut(&seq) {
+            *acked = true;
+        }
+        let mut committable = None;
+        while let Some(entry) = pending.page_tokens.first_entry() {
+            if !entry.get().1 {
+                break;
+            }
+            committable = Some(entry.remove().0);
+        }
+        committable
+    }
+}
+
+/// Files and folders listed under the root folders or reported as changed, with the folders containing them.
+#[derive(Default)]
+struct KnownFiles {
+    /// Parent folder of each known file or folder.
+    parents: HashMap<Arc<str>, Arc<str>>,
+    /// Known files and folders directly under each folder, including root folders.
+    children: HashMap<Arc<str>, HashSet<Arc<str>>>,
+    folder_ids: HashSet<Arc<str>>,
+}
+
+impl KnownFiles {
+    fn insert(&mut self, id: Arc<str>, parent_id: Arc<str>, is_folder: bool) {
+        if let Some(prev_parent_id) = self.parents.insert(id.clone(), parent_id.clone()) {
+            if let Some(siblings) = self.children.get_mut(&prev_parent_id) {
+                siblings.remove(&id);
+            }
+        }
+        self.children
+            .entry(parent_id)
+            .or_default()
+            .insert(id.clone());
+        if is_folder {
+            self.folder_ids.insert(id);
+        }
+    }
+
+    fn contains(&self, id: &str) -> bool {
+        self.parents.contains_key(id)
+    }
+
+    fn is_folder(&self, id: &str) -> bool {
+        self.folder_ids.contains(id)
+    }
+
+    fn parent(&self, id: &str) -> Option<&Arc<str>> {
+        self.parents.get(id)
+    }
+
+    /// Remove a file, or a folder together with everything known under it.
+    /// Returns IDs of the removed files, i.e. the file itself or known files under the folder.
+    fn remove(&mut self, id: &Arc<str>) -> Vec<Arc<str>> {
+        if let Some(parent_id) = self.parents.get(id) {
+            if let Some(siblings) = self.children.get_mut(parent_id) {
+                siblings.remove(id);
+            }
+        }
+        let mut removed_file_ids = Vec::new();
+        let mut pending_ids = vec![id.clone()];
+        while let Some(id) = pending_ids.pop() {
+            self.parents.remove(&id);
+            if self.folder_ids.remove(&id) {
+                pending_ids.extend(self.children.remove(&id).into_iter().flatten());
+            } else {
+                removed_file_ids.push(id);
+            }
+        }
+        removed_file_ids
+    }
+}
+
 struct Executor {
     drive_hub: DriveHub<HttpsConnector<HttpConnector>>,
     binary: bool,
     root_folder_ids: IndexSet<Arc<str>>,
+    drive_id: Option<String>,
     recent_updates_poll_interval: Option<std::time::Duration>,
+    page_token_store: Option<Arc<PageTokenStore>>,
+    /// Files and folders moved out of the root folders are only reported as deleted if they're here.
+    known_files: Mutex<KnownFiles>,
 }
 
 impl Executor {
-    async fn new(spec: Spec) -> Result<Self> {
+    async fn new(spec: Spec, context: &FlowInstanceContext) -> Result<Self> {
         let service_account_key =
-            read_service_account_key(spec.service_account_credential_path).await?;
+            read_service_account_key(&spec.service_account_credential_path).await?;
         let auth = ServiceAccountAuthenticator::builder(service_account_key)
             .build()
             .await?;
@@ -87,11 +244,24 @@ impl Executor {
                         .build(),
                 );
         let drive_hub = DriveHub::new(client, auth);
+        let page_token_store = match &get_lib_context()?.persistence_ctx {
+            Some(persistence_ctx) if spec.recent_changes_poll_interval.is_some() => {
+                Some(Arc::new(PageTokenStore {
+                    db_pool: persistence_ctx.builtin_db_pool.clone(),
+                    table_name: page_token_table_name(&context.flow_instance_name),
+                    state_key: page_token_state_key(&spec),
+                }))
+            }
+            _ => None,
+        };
         Ok(Self {
             drive_hub,
             binary: spec.binary,
             root_folder_ids: spec.root_folder_ids.into_iter().map(Arc::from).collect(),
+            drive_id: spec.drive_id,
             recent_updates_poll_interval: spec.recent_changes_poll_interval,
+            page_token_store,
+            known_files: Mutex::new(KnownFiles::default()),
         })
     }
 }
@@ -108,7 +278,8 @@ fn escape_string(s: &str) -> String {
     escaped
 }
 
-const CUTOFF_TIME_BUFFER: Duration = Duration::seconds(1);
+const CHANGES_FIELDS: &str = "nextPageToken,newStartPageToken,changes(changeType,fileId,removed,file(mimeType,trashed,parents))";
+
 impl Executor {
     fn visit_file(
         &self,
@@ -156,7 +327,12 @@ impl Executor {
             .list()
             .add_scope(Scope::Readonly)
             .q(&query)
+            .supports_all_drives(true)
+            .include_items_from_all_drives(true)
             .param("fields", fields);
+        if let Some(drive_id) = &self.drive_id {
+            list_call = list_call.corpora("drive").drive_id(drive_id);
+        }
         if let Some(next_page_token) = &next_page_token {
             list_call = list_call.page_token(next_page_token);
         }
@@ -166,66 +342,188 @@ impl Executor {
         Ok(file_iter)
     }
 
-    fn make_cutoff_time(
-        most_recent_modified_time: Option<DateTime<Utc>>,
-        list_start_time: DateTime<Utc>,
-    ) -> DateTime<Utc> {
-        let safe_upperbound = list_start_time - CUTOFF_TIME_BUFFER;
-        most_recent_modified_time
-            .map(|t| t.min(safe_upperbound))
-            .unwrap_or(safe_upperbound)
+    fn add_known_files(
+        &self,
+        folder_id: &Arc<str>,
+        ids: impl Iterator<Item = Arc<str>>,
+        is_folder: bool,
+    ) {
+        let mut known_files = self.known_files.lock().unwrap();
+        for id in ids {
+            known_files.insert(id, folder_id.clone(), is_folder);
+        }
+    }
+
+    async fn get_start_page_token(&self) -> Result<String> {
+        let mut call = self
+            .drive_hub
+            .changes()
+            .get_start_page_token()
+            .add_scope(Scope::Readonly)
+            .supports_all_drives(true);
+        if let Some(drive_id) = &self.drive_id {
+            call = call.drive_id(drive_id);
+        }
+        let (_, start_page_token) = call.doit().await?;
+        start_page_token
+            .start_page_token
+            .ok_or_else(|| anyhow!("No start page token returned by Google Drive"))
+    }
+
+    /// Convert a change reported by the Changes API into changes of the source.
+    /// Returns no changes for changes not affecting rows of the source.
+    async fn resolve_change(&self, change: Change) -> Result<Vec<SourceChange>> {
+        if change.change_type.as_deref() != Some("file") {
+            return Ok(vec![]);
+        }
+        let Some(file_id) = change.file_id else {
+            return Ok(vec![]);
+        };
+        let file_id = Arc::<str>::from(file_id);
+        let mime_type = change.file.as_ref().and_then(|f| f.mime_type.as_deref());
+        let parent_id = change
+            .file
+            .as_ref()
+            .and_then(|f| f.parents.as_ref())
+            .and_then(|parents| parents.first())
+            .map(|parent_id| Arc::<str>::from(parent_id.as_str()));
+        let is_folder = match mime_type {
+            Some(mime_type) => mime_type == FOLDER_MIME_TYPE,
+            // Metadata isn't available for removed files.
+            None => self.known_files.lock().unwrap().is_folder(&file_id),
+        };
+        let is_removed = change.removed == Some(true)
+            || change
+                .file
+                .as_ref()
+                .is_some_and(|f| f.trashed == Some(true));
+        if is_folder {
+            // Files under folders moved into the root folders are not reported individually. They're left to refresh.
+            let known_parent_id = self.known_files.lock().unwrap().parent(&file_id).cloned();
+            let Some(known_parent_id) = known_parent_id else {
+                return Ok(vec![]);
+            };
+            if !is_removed {
+                if parent_id.as_ref() == Some(&known_parent_id) {
+                    return Ok(vec![]);
+                }
+                if self.is_file_covered(&file_id).await? {
+                    if let Some(parent_id) = parent_id {
+                        self.known_files
+                            .lock()
+                            .unwrap()
+                            .insert(file_id, parent_id, true);
+                    }
+                    return Ok(vec![]);
+                }
+            }
+        } else if !is_removed {
+            if mime_type.is_some_and(is_supported_file_type)
+                && self.is_file_covered(&file_id).await?
+            {
+                if let Some(parent_id) = parent_id {
+                    self.known_files
+                        .lock()
+                        .unwrap()
+                        .insert(file_id.clone(), parent_id, false);
+                }
+                return Ok(vec![SourceChange {
+                    key: KeyValue::Str(file_id),
+                    data: None,
+                }]);
+            }
+            // Changes of files never under the root folders (e.g. elsewhere in the drive) are irrelevant.
+            if !self.known_files.lock().unwrap().contains(&file_id) {
+                return Ok(vec![]);
+            }
+        }
+        // The file is deleted, trashed, or moved out of the root folders.
+        // For a folder, so are all known files under it.
+        let removed_file_ids = self.known_files.lock().unwrap().remove(&file_id);
+        Ok(removed_file_ids
+            .into_iter()
+            .map(|file_id| SourceChange {
+                key: KeyValue::Str(file_id),
+                data: Some(SourceData {
+                    value: SourceValue::NonExistence,
+                    ordinal: Ordinal::unavailable(),
+                }),
+            })
+            .collect())
     }
 
-    async fn get_recent_updates(
+    async fn get_changes(
         &self,
-        cutoff_time: &mut DateTime<Utc>,
+        page_token: &mut String,
+        page_token_tracker: &Arc<PageTokenTracker>,
     ) -> Result<SourceChangeMessage> {
-        let mut page_size: i32 = 10;
-        let mut next_page_token: Option<String> = None;
-        let mut changes = Vec::new();
-        let mut most_recent_modified_time = None;
-        let start_time = Utc::now();
-        'paginate: loop {
+        let mut changes = IndexMap::new();
+        let mut next_page_token = page_token.clone();
+        let new_start_page_token = loop {
             let mut list_call = self
                 .drive_hub
-                .files()
-                .list()
+                .changes()
+                .list(&next_page_token)
                 .add_scope(Scope::Readonly)
-                .param("fields", "files(id,modifiedTime,parents,trashed)")
-                .order_by("modifiedTime desc")
-                .page_size(page_size);
-            if let Some(token) = next_page_token {
-                list_call = list_call.page_token(token.as_str());
+                .supports_all_drives(true)
+                .include_items_from_all_drives(true)
+                .include_removed(true)
+                .page_size(1000)
+                .param("fields", CHANGES_FIELDS);
+            if let Some(drive_id) = &self.drive_id {
+                list_call = list_call.drive_id(drive_id);
             }
-            let (_, files) = list_call.doit().await?;
-            for file in files.files.into_iter().flat_map(|files| files.into_iter()) {
-                let modified_time = file.modified_time.unwrap_or_default();
-                if most_recent_modified_time.is_none() {
-                    most_recent_modified_time = Some(modified_time);
-                }
-                if modified_time <= *cutoff_time {
-                    break 'paginate;
-                }
-                let file_id = file.id.ok_or_else(|| anyhow!("File has no id"))?;
-                if self.is_file_covered(&file_id).await? {
-                    changes.push(SourceChange {
-                        key: KeyValue::Str(Arc::from(file_id)),
-                        data: None,
-                    });
+            let (_, change_list) = list_call.doit().await?;
+            for change in change_list.changes.into_iter().flatten() {
+                for change in self.resolve_change(change).await? {
+                    // Only keep the latest change of each file.
+                    changes.shift_remove(&change.key);
+                    changes.insert(change.key.clone(), change);
                 }
             }
-            if let Some(token) = files.next_page_token {
-                next_page_token = Some(token);
-            } else {
-                break;
+            match (
+                change_list.next_page_token,
+                change_list.new_start_page_token,
+            ) {
+                (Some(token), _) => next_page_token = token,
+                (None, Some(token)) => break token,
+                (None, None) => bail!(
+                    "Neither next page token nor new start page token returned by Google Drive"
+                ),
             }
-            // List more in a page since 2nd.
-            page_size = 100;
+        };
+        *page_token = new_start_page_token.clone();
+
+        let changes = changes.into_values().collect::<Vec<_>>();
+        let Some(page_token_store) = &self.page_token_store else {
+            return Ok(SourceChangeMessage {
+                changes,
+                ack_fn: None,
+            });
+        };
+        let seq = page_token_tracker.add(new_start_page_token);
+        if changes.is_empty() {
+            if let Some(page_token) = page_token_tracker.ack(seq) {
+                page_token_store.save(&page_token).await?;
+            }
+            return Ok(SourceChangeMessage {
+                changes,
+                ack_fn: None,
+            });
         }
-        *cutoff_time = Self::make_cutoff_time(most_recent_modified_time, start_time);
+        let page_token_store = page_token_store.clone();
+        let page_token_tracker = page_token_tracker.clone();
         Ok(SourceChangeMessage {
             changes,
-            ack_fn: None,
+            ack_fn: Some(Box::new(move || {
+                async move {
+                    if let Some(page_token) = page_token_tracker.ack(seq) {
+                        page_token_store.save(&page_token).await?;
+                    }
+                    Ok(())
+                }
+                .boxed()
+            })),
         })
     }
 
@@ -235,14 +533,19 @@ impl Executor {
             if self.root_folder_ids.contains(file_id.as_ref()) {
                 return Ok(true);
             }
-            let (_, file) = self
+            let Some((_, file)) = self
                 .drive_hub
                 .files()
                 .get(&file_id)
                 .add_scope(Scope::Readonly)
+                .supports_all_drives(true)
                 .param("fields", "parents")
                 .doit()
-                .await?;
+                .await
+                .or_not_found()?
+            else {
+                return Ok(false);
+            };
             next_file_id = file
                 .parents
                 .into_iter()
@@ -308,12 +611,21 @@ impl SourceExecutor for Executor {
                         curr_rows.extend(self.visit_file(file, &mut new_folder_ids, &mut seen_ids)?);
                     }
                     if !curr_rows.is_empty() {
+                        self.add_known_files(
+                            &folder_id,
+                            curr_rows.iter().filter_map(|row| match &row.key {
+                                KeyValue::Str(id) => Some(id.clone()),
+                                _ => None,
+                            }),
+                            false,
+                        );
                         yield curr_rows;
                     }
                     if next_page_token.is_none() {
                         break;
                     }
                 }
+                self.add_known_files(&folder_id, new_folder_ids.iter().cloned(), true);
                 folder_ids.extend(new_folder_ids.drain(..).rev());
             }
         }
@@ -335,6 +647,7 @@ impl SourceExecutor for Executor {
             .files()
             .get(file_id)
             .add_scope(Scope::Readonly)
+            .supports_all_drives(true)
             .param("fields", &fields)
             .doit()
             .await
@@ -376,6 +689,7 @@ impl SourceExecutor for Executor {
                 .files()
                 .get(file_id)
                 .add_scope(Scope::Readonly)
+                .supports_all_drives(true)
                 .param("alt", "media")
                 .doit()
                 .await
@@ -412,13 +726,21 @@ impl SourceExecutor for Executor {
         } else {
             return Ok(None);
         };
-        let mut cutoff_time = Utc::now() - CUTOFF_TIME_BUFFER;
+        let saved_page_token = match &self.page_token_store {
+            Some(page_token_store) => page_token_store.load().await?,
+            None => None,
+        };
+        let mut page_token = match saved_page_token {
+            Some(page_token) => page_token,
+            None => self.get_start_page_token().await?,
+        };
+        let page_token_tracker = Arc::new(PageTokenTracker::default());
         let mut interval = tokio::time::interval(poll_interval);
         interval.tick().await;
         let stream = stream! {
             loop {
                 interval.tick().await;
-                yield self.get_recent_updates(&mut cutoff_time).await;
+                yield self.get_changes(&mut page_token, &page_token_tracker).await;
             }
         };
         Ok(Some(stream.boxed()))
@@ -476,11 +798,276 @@ impl SourceFactoryBase for Factory {
         )))
     }
 
+    async fn get_setup_declarations(
+        &self,
+        spec: &Spec,
+        context: &FlowInstanceContext,
+    ) -> Result<Vec<OpSpec>> {
+        if spec.recent_changes_poll_interval.is_none() {
+            return Ok(vec![]);
+        }
+        let declaration = PageTokenTableDeclaration {
+            key: PageTokenTableKey {
+                table_name: page_token_table_name(&context.flow_instance_name),
+            },
+            state: PageTokenTableSetupState {},
+        };
+        let serde_json::Value::Object(spec) = serde_json::to_value(declaration)? else {
+            unreachable!()
+        };
+        Ok(vec![OpSpec {
+            kind: PAGE_TOKEN_TABLE_KIND.to_string(),
+            spec,
+        }])
+    }
+
     async fn build_executor(
         self: Arc<Self>,
         spec: Spec,
-        _context: Arc<FlowInstanceContext>,
+        context: Arc<FlowInstanceContext>,
     ) -> Result<Box<dyn SourceExecutor>> {
-        Ok(Box::new(Executor::new(spec).await?))
+        Ok(Box::new(Executor::new(spec, &context).await?))
+    }
+}
+
+////////////////////////////////////////////////////////////
+// Setup of the page token table
+////////////////////////////////////////////////////////////
+
+const PAGE_TOKEN_TABLE_KIND: &str = "GoogleDrivePageTokenTable";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct PageTokenTableKey {
+    table_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PageTokenTableSetupState {}
+
+/// Declared by the `GoogleDrive` source when `recent_changes_poll_interval` is set.
+/// All such sources of a flow share one table, keyed by their drives and root folders.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageTokenTableDeclaration {
+    key: PageTokenTableKey,
+    state: PageTokenTableSetupState,
+}
+
+#[derive(Debug)]
+pub struct PageTokenTableSetupStatus {
+    drop_existing: bool,
+    create: bool,
+}
+
+impl setup::ResourceSetupStatus for PageTokenTableSetupStatus {
+    fn describe_changes(&self) -> Vec<String> {
+        let mut result = vec![];
+        if self.drop_existing {
+            result.push("Drop the page token table".to_string());
+        }
+        if self.create {
+            result.push("Create the page token table".to_string());
+        }
+        result
+    }
+
+    fn change_type(&self) -> setup::SetupChangeType {
+        match (self.drop_existing, self.create) {
+            (false, false) => setup::SetupChangeType::NoChange,
+            (false, true) => setup::SetupChangeType::Create,
+            (true, false) => setup::SetupChangeType::Delete,
+            (true, true) => setup::SetupChangeType::Update,
+        }
+    }
+}
+
+impl PageTokenTableSetupStatus {
+    async fn apply(&self, key: &PageTokenTableKey, db_pool: &PgPool) -> Result<()> {
+        if self.drop_existing {
+            sqlx::query(&format!("DROP TABLE IF EXISTS {}", key.table_name))
+                .execute(db_pool)
+                .await?;
+        }
+        if self.create {
+            sqlx::query(&format!(
+                "CREATE TABLE IF NOT EXISTS {} (
+                    state_key TEXT PRIMARY KEY,
+                    page_token TEXT NOT NULL
+                )",
+                key.table_name
+            ))
+            .execute(db_pool)
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Manages the table in the internal storage holding page tokens of `GoogleDrive` sources of a flow.
+/// It's a setup-only target: it's never exported to, and only used through declarations of the `GoogleDrive` source.
+pub struct PageTokenTableFactory;
+
+#[async_trait]
+impl StorageFactoryBase for PageTokenTableFactory {
+    type Spec = serde_json::Value;
+    type DeclarationSpec = PageTokenTableDeclaration;
+    type Key = PageTokenTableKey;
+    type SetupState = PageTokenTableSetupState;
+    type SetupStatus = PageTokenTableSetupStatus;
+    type ExportContext = ();
+
+    fn name(&self) -> &str {
+        PAGE_TOKEN_TABLE_KIND
+    }
+
+    async fn build(
+        self: Arc<Self>,
+        data_collections: Vec<TypedExportDataCollectionSpec<Self>>,
+        declarations: Vec<PageTokenTableDeclaration>,
+        _context: Arc<FlowInstanceContext>,
+    ) -> Result<(
+        Vec<TypedExportDataCollectionBuildOutput<Self>>,
+        Vec<(PageTokenTableKey, PageTokenTableSetupState)>,
+    )> {
+        if !data_collections.is_empty() {
+            api_bail!("`{PAGE_TOKEN_TABLE_KIND}` cannot be used as an export target");
+        }
+        // Sources of the same flow declare the same table.
+        let setup_states = declarations
+            .into_iter()
+            .map(|d| (d.key, d.state))
+            .collect::<IndexMap<_, _>>();
+        Ok((vec![], setup_states.into_iter().collect()))
+    }
+
+    async fn check_setup_status(
+        &self,
+        _key: PageTokenTableKey,
+        desired: Option<PageTokenTableSetupState>,
+        existing: setup::CombinedState<PageTokenTableSetupState>,
+        _auth_registry: &Arc<AuthRegistry>,
+    ) -> Result<PageTokenTableSetupStatus> {
+        Ok(PageTokenTableSetupStatus {
+            drop_existing: desired.is_none() && existing.possible_versions().next().is_some(),
+            create: desired.is_some() && !existing.always_exists(),
+        })
+    }
+
+    fn check_state_compatibility(
+        &self,
+        _desired: &PageTokenTableSetupState,
+        _existing: &PageTokenTableSetupState,
+    ) -> Result<SetupStateCompatibility> {
+        Ok(SetupStateCompatibility::Compatible)
+    }
+
+    fn describe_resource(&self, key: &PageTokenTableKey) -> Result<String> {
+        Ok(format!("Google Drive page token table {}", key.table_name))
+    }
+
+    async fn apply_mutation(
+        &self,
+        _mutations: Vec<ExportTargetMutationWithContext<'async_trait, ()>>,
+    ) -> Result<()> {
+        bail!("`{PAGE_TOKEN_TABLE_KIND}` cannot be used as an export target")
+    }
+
+    async fn apply_setup_changes(
+        &self,
+        setup_status: Vec<TypedResourceSetupChangeItem<'async_trait, Self>>,
+        _auth_registry: &Arc<AuthRegistry>,
+    ) -> Result<()> {
+        let db_pool = get_lib_context()?.require_builtin_db_pool()?.clone();
+        for setup_change in setup_status.iter() {
+            setup_change
+                .setup_status
+                .apply(&setup_change.key, &db_pool)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_token_tracker() {
+        let tracker = PageTokenTracker::default();
+        let seq1 = tracker.add("1".to_string());
+        let seq2 = tracker.add("2".to_string());
+        let seq3 = tracker.add("3".to_string());
+
+        // Not committable until the earlier poll is acknowledged.
+        assert_eq!(tracker.ack(seq2), None);
+        assert_eq!(tracker.ack(seq1), Some("2".to_string()));
+        assert_eq!(tracker.ack(seq3), Some("3".to_string()));
+
+        let seq4 = tracker.add("4".to_string());
+        assert_eq!(tracker.ack(seq4), Some("4".to_string()));
+    }
+
+    #[test]
+    fn test_known_files() {
+        let mut known_files = KnownFiles::default();
+        let id = |id: &str| Arc::<str>::from(id);
+        known_files.insert(id("docs"), id("root"), true);
+        known_files.insert(id("a"), id("docs"), false);
+        known_files.insert(id("sub"), id("docs"), true);
+        known_files.insert(id("b"), id("sub"), false);
+        known_files.insert(id("c"), id("root"), false);
+
+        // Moved to another known folder.
+        known_files.insert(id("a"), id("sub"), false);
+        assert_eq!(known_files.parent("a"), Some(&id("sub")));
+
+        let mut removed = known_files.remove(&id("docs"));
+        removed.sort();
+        assert_eq!(removed, vec![id("a"), id("b")]);
+        assert!(!known_files.contains("sub"));
+        assert!(!known_files.is_folder("sub"));
+        assert!(known_files.contains("c"));
+        assert_eq!(known_files.remove(&id("c")), vec![id("c")]);
+        assert!(!known_files.contains("c"));
+    }
+
+    #[tokio::test]
+    async fn test_page_token_table_setup_status() {
+        use setup::ResourceSetupStatus;
+
+        let key = PageTokenTableKey {
+            table_name: page_token_table_name("my-flow"),
+        };
+        assert_eq!(
+            key.table_name,
+            "my__flow__cocoindex_google_drive_page_tokens"
+        );
+        let auth_registry = Arc::new(AuthRegistry::default());
+        let check = |desired, existing| {
+            StorageFactoryBase::check_setup_status(
+                &PageTokenTableFactory,
+                key.clone(),
+                desired,
+                existing,
+                &auth_registry,
+            )
+        };
+        let absent = setup::CombinedState {
+            current: None,
+            staging: vec![],
+            legacy_state_key: None,
+        };
+        let present = setup::CombinedState::from_desired(PageTokenTableSetupState {});
+
+        let status = check(Some(PageTokenTableSetupState {}), absent)
+            .await
+            .unwrap();
+        assert_eq!(status.change_type(), setup::SetupChangeType::Create);
+        let status = check(Some(PageTokenTableSetupState {}), present.clone())
+            .await
+            .unwrap();
+        assert_eq!(status.change_type(), setup::SetupChangeType::NoChange);
+        let status = check(None, present).await.unwrap();
+        assert_eq!(status.change_type(), setup::SetupChangeType::Delete);
     }
 }
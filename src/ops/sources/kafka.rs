@@ -0,0 +1,506 @@
+use async_stream::{stream, try_stream};
+use log::{debug, warn};
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::message::{BorrowedMessage, Message};
+use rdkafka::{ClientConfig, Offset, TopicPartitionList};
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+
+use crate::ops::sdk::*;
+
+const METADATA_TIMEOUT: Duration = Duration::from_secs(30);
+/// If no message arrives within this duration during a scan, positions of partitions are checked,
+/// as offsets at the end of a partition may be taken by records never delivered (e.g. transaction markers).
+const SCAN_IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub enum OrdinalSource {
+    #[default]
+    Offset,
+    Timestamp,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Spec {
+    bootstrap_servers: String,
+    topic: String,
+    group_id: String,
+    #[serde(default)]
+    ordinal_source: OrdinalSource,
+    #[serde(default)]
+    binary_key: bool,
+    #[serde(default)]
+    binary: bool,
+    consumer_options: Option<HashMap<String, String>>,
+}
+
+/// Latest state of a key in the topic.
+struct KeyState {
+    ordinal: Ordinal,
+    /// `None` means the latest message of the key is a tombstone.
+    value: Option<FieldValues>,
+}
+
+impl KeyState {
+    fn source_value(&self) -> SourceValue {
+        match &self.value {
+            Some(value) => SourceValue::Existence(value.clone()),
+            None => SourceValue::NonExistence,
+        }
+    }
+}
+
+/// Offsets of messages consumed by the change stream, to commit offsets only after messages before them are processed.
+#[derive(Default)]
+struct PartitionOffsets {
+    /// Offsets of messages still being processed.
+    pending: BTreeSet<i64>,
+    /// The offset after the last consumed message.
+    next_offset: i64,
+    committed_offset: Option<i64>,
+}
+
+#[derive(Default)]
+struct OffsetTracker {
+    partitions: Mutex<HashMap<i32, PartitionOffsets>>,
+}
+
+impl OffsetTracker {
+    fn consume(&self, partition: i32, offset: i64) {
+        let mut partitions = self.partitions.lock().unwrap();
+        let offsets = partitions.entry(partition).or_default();
+        // Messages before the first consumed one are already committed.
+        offsets.committed_offset.get_or_insert(offset);
+        offsets.pending.insert(offset);
+        offsets.next_offset = std::cmp::max(offsets.next_offset, offset + 1);
+    }
+
+    /// Returns the offset to commit for the partition, if it advances.
+    fn ack(&self, partition: i32, offset: i64) -> Option<i64> {
+        let mut partitions = self.partitions.lock().unwrap();
+        let offsets = partitions.get_mut(&partition)?;
+        offsets.pending.remove(&offset);
+        let committable = offsets
+            .pending
+            .first()
+            .copied()
+            .unwrap_or(offsets.next_offset);
+        if offsets
+            .committed_offset
+            .is_some_and(|committed| committed >= committable)
+        {
+            return None;
+        }
+        offsets.committed_offset = Some(committable);
+        Some(committable)
+    }
+}
+
+struct Executor {
+    bootstrap_servers: String,
+    topic: String,
+    group_id: String,
+    ordinal_source: OrdinalSource,
+    binary_key: bool,
+    binary: bool,
+    consumer_options: HashMap<String, String>,
+    /// Latest state of all keys, from the last scan of the topic and messages from the change stream since then.
+    keys: tokio::sync::Mutex<Option<HashMap<KeyValue, KeyState>>>,
+}
+
+impl Executor {
+    fn create_consumer(&self, group_id: Option<&str>) -> Result<StreamConsumer> {
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", &self.bootstrap_servers)
+            .set("enable.auto.commit", "false")
+            .set("enable.auto.offset.store", "false")
+            .set("auto.offset.reset", "earliest");
+        if let Some(group_id) = group_id {
+            config.set("group.id", group_id);
+        }
+        for (key, value) in self.consumer_options.iter() {
+            config.set(key, value);
+        }
+        Ok(config.create()?)
+    }
+
+    /// Returns the key and the state of the key after the message.
+    fn decode_message(&self, message: &BorrowedMessage<'_>) -> Option<(KeyValue, KeyState)> {
+        let key = message.key()?;
+        let key = if self.binary_key {
+            KeyValue::Bytes(key.to_vec().into())
+        } else {
+            KeyValue::Str(String::from_utf8_lossy(key).into())
+        };
+        // Timestamps only have millisecond precision, so messages of a key within the same millisecond share an ordinal.
+        let ordinal = match self.ordinal_source {
+            OrdinalSource::Offset => Ordinal(Some(message.offset())),
+            OrdinalSource::Timestamp => {
+                Ordinal(message.timestamp().to_millis().map(|millis| millis * 1000))
+            }
+        };
+        let value = message.payload().map(|payload| {
+            let value = if self.binary {
+                payload.to_vec().into()
+            } else {
+                String::from_utf8_lossy(payload).to_string().into()
+            };
+            FieldValues {
+                fields: vec![value],
+            }
+        });
+        Some((key, KeyState { ordinal, value }))
+    }
+
+    /// Read all messages currently in the topic, to get the latest state of each key.
+    async fn scan_topic(&self) -> Result<HashMap<KeyValue, KeyState>> {
+        let consumer = Arc::new(self.create_consumer(None)?);
+        let topic = self.topic.clone();
+        let end_offsets = tokio::task::spawn_blocking({
+            let consumer = consumer.clone();
+            move || -> Result<HashMap<i32, i64>> {
+                let metadata = consumer.fetch_metadata(Some(&topic), METADATA_TIMEOUT)?;
+                let Some(topic_metadata) = metadata.topics().first() else {
+                    api_bail!("Topic `{topic}` not found");
+                };
+                if let Some(err) = topic_metadata.error() {
+                    api_bail!("Failed to get metadata of topic `{topic}`: {err:?}");
+                }
+                let mut end_offsets = HashMap::new();
+                for partition in topic_metadata.partitions() {
+                    let (low, high) =
+                        consumer.fetch_watermarks(&topic, partition.id(), METADATA_TIMEOUT)?;
+                    if high > low {
+                        end_offsets.insert(partition.id(), high);
+                    }
+                }
+                Ok(end_offsets)
+            }
+        })
+        .await??;
+
+        let mut keys = HashMap::new();
+        if end_offsets.is_empty() {
+            return Ok(keys);
+        }
+        let mut assignment = TopicPartitionList::new();
+        for partition in end_offsets.keys() {
+            assignment.add_partition_offset(&self.topic, *partition, Offset::Beginning)?;
+        }
+        consumer.assign(&assignment)?;
+
+        let mut remaining = end_offsets;
+        while !remaining.is_empty() {
+            let message = match tokio::time::timeout(SCAN_IDLE_TIMEOUT, consumer.recv()).await {
+                Ok(message) => message?,
+                Err(_) => {
+                    for entry in consumer.position()?.elements() {
+                        if let (Offset::Offset(position), Some(end_offset)) =
+                            (entry.offset(), remaining.get(&entry.partition()))
+                        {
+                            if position >= *end_offset {
+                                remaining.remove(&entry.partition());
+                            }
+                        }
+                    }
+                    continue;
+                }
+            };
+            let partition = message.partition();
+            let Some(end_offset) = remaining.get(&partition).copied() else {
+                continue;
+            };
+            if message.offset() >= end_offset {
+                remaining.remove(&partition);
+                continue;
+            }
+            // Tombstones are kept, so deletions are reported with their ordinals.
+            match self.decode_message(&message) {
+                Some((key, state)) => {
+                    keys.insert(key, state);
+                }
+                None => debug!(
+                    "Skipped message without key at partition {partition}, offset {}",
+                    message.offset()
+                ),
+            }
+            if message.offset() + 1 >= end_offset {
+                remaining.remove(&partition);
+            }
+        }
+        Ok(keys)
+    }
+}
+
+#[async_trait]
+impl SourceExecutor for Executor {
+    fn list<'a>(
+        &'a self,
+        options: &'a SourceExecutorListOptions,
+    ) -> BoxStream<'a, Result<Vec<PartialSourceRowMetadata>>> {
+        try_stream! {
+            let scanned_keys = self.scan_topic().await?;
+            let rows = scanned_keys
+                .iter()
+                .filter(|(_, state)| state.value.is_some())
+                .map(|(key, state)| PartialSourceRowMetadata {
+                    key: key.clone(),
+                    ordinal: options.include_ordinal.then_some(state.ordinal),
+                })
+                .collect::<Vec<_>>();
+            *self.keys.lock().await = Some(scanned_keys);
+            yield rows;
+        }
+        .boxed()
+    }
+
+    async fn get_value(
+        &self,
+        key: &KeyValue,
+        options: &SourceExecutorGetOptions,
+    ) -> Result<PartialSourceRowData> {
+        let mut keys = self.keys.lock().await;
+        if keys.is_none() {
+            *keys = Some(self.scan_topic().await?);
+        }
+        let Some(state) = keys.as_ref().and_then(|keys| keys.get(key)) else {
+            return Ok(PartialSourceRowData {
+                value: Some(SourceValue::NonExistence),
+                ordinal: Some(Ordinal::unavailable()),
+            });
+        };
+        Ok(PartialSourceRowData {
+            value: (options.include_value || state.value.is_none()).then(|| state.source_value()),
+            ordinal: options.include_ordinal.then_some(state.ordinal),
+        })
+    }
+
+    async fn change_stream(
+        &self,
+    ) -> Result<Option<BoxStream<'async_trait, Result<SourceChangeMessage>>>> {
+        let consumer = Arc::new(self.create_consumer(Some(&self.group_id))?);
+        consumer.subscribe(&[&self.topic])?;
+        let offset_tracker = Arc::new(OffsetTracker::default());
+        let topic = self.topic.clone();
+        let commit = move |consumer: &StreamConsumer, partition: i32, offset: i64| -> Result<()> {
+            let mut offsets = TopicPartitionList::new();
+            offsets.add_partition_offset(&topic, partition, Offset::Offset(offset))?;
+            consumer.commit(&offsets, CommitMode::Async)?;
+            Ok(())
+        };
+        let commit = Arc::new(commit);
+        // Errors are yielded without ending the stream, so consumption goes on after transient failures.
+        let stream = stream! {
+            loop {
+                let message = match consumer.recv().await {
+                    Ok(message) => message,
+                    Err(e) => {
+                        yield Err(e.into());
+                        continue;
+                    }
+                };
+                let (partition, offset) = (message.partition(), message.offset());
+                offset_tracker.consume(partition, offset);
+                let Some((key, state)) = self.decode_message(&message) else {
+                    warn!("Skipped message without key at partition {partition}, offset {offset}");
+                    if let Some(commit_offset) = offset_tracker.ack(partition, offset) {
+                        if let Err(e) = commit(&consumer, partition, commit_offset) {
+                            yield Err(e);
+                        }
+                    }
+                    continue;
+                };
+                let data = SourceData {
+                    value: state.source_value(),
+                    ordinal: state.ordinal,
+                };
+                if let Some(keys) = self.keys.lock().await.as_mut() {
+                    keys.insert(key.clone(), state);
+                }
+                let consumer = consumer.clone();
+                let offset_tracker = offset_tracker.clone();
+                let commit = commit.clone();
+                yield Ok(SourceChangeMessage {
+                    changes: vec![SourceChange {
+                        key,
+                        data: Some(data),
+                    }],
+                    ack_fn: Some(Box::new(move || {
+                        async move {
+                            if let Some(commit_offset) = offset_tracker.ack(partition, offset) {
+                                commit(&consumer, partition, commit_offset)?;
+                            }
+                            Ok(())
+                        }
+                        .boxed()
+                    })),
+                });
+            }
+        };
+        Ok(Some(stream.boxed()))
+    }
+}
+
+pub struct Factory;
+
+#[async_trait]
+impl SourceFactoryBase for Factory {
+    type Spec = Spec;
+
+    fn name(&self) -> &str {
+        "Kafka"
+    }
+
+    async fn get_output_schema(
+        &self,
+        spec: &Spec,
+        _context: &FlowInstanceContext,
+    ) -> Result<EnrichedValueType> {
+        let mut struct_schema = StructSchema::default();
+        let mut schema_builder = StructSchemaBuilder::new(&mut struct_schema);
+        schema_builder.add_field(FieldSchema::new(
+            "key",
+            make_output_type(if spec.binary_key {
+                BasicValueType::Bytes
+            } else {
+                BasicValueType::Str
+            }),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "value",
+            make_output_type(if spec.binary {
+                BasicValueType::Bytes
+            } else {
+                BasicValueType::Str
+            }),
+        ));
+        Ok(make_output_type(TableSchema::new(
+            TableKind::KTable,
+            struct_schema,
+        )))
+    }
+
+    async fn build_executor(
+        self: Arc<Self>,
+        spec: Spec,
+        _context: Arc<FlowInstanceContext>,
+    ) -> Result<Box<dyn SourceExecutor>> {
+        Ok(Box::new(Executor {
+            bootstrap_servers: spec.bootstrap_servers,
+            topic: spec.topic,
+            group_id: spec.group_id,
+            ordinal_source: spec.ordinal_source,
+            binary_key: spec.binary_key,
+            binary: spec.binary,
+            consumer_options: spec.consumer_options.unwrap_or_default(),
+            keys: tokio::sync::Mutex::new(None),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rdkafka::producer::{FutureProducer, FutureRecord};
+
+    #[test]
+    fn test_offset_tracker_commits_in_order() {
+        let tracker = OffsetTracker::default();
+        for offset in 10..13 {
+            tracker.consume(0, offset);
+        }
+        tracker.consume(1, 5);
+
+        // Offset 10 is still pending, so nothing can be committed.
+        assert_eq!(tracker.ack(0, 11), None);
+        assert_eq!(tracker.ack(0, 10), Some(12));
+        assert_eq!(tracker.ack(1, 5), Some(6));
+        assert_eq!(tracker.ack(0, 12), Some(13));
+        assert_eq!(tracker.ack(2, 0), None);
+    }
+
+    /// Runs against a broker given by `COCOINDEX_TEST_KAFKA_BOOTSTRAP_SERVERS`, e.g. started by
+    /// `docker run -d -p 9092:9092 apache/kafka`. Skipped if it's not set.
+    #[tokio::test]
+    async fn test_local_broker() {
+        let Ok(bootstrap_servers) = std::env::var("COCOINDEX_TEST_KAFKA_BOOTSTRAP_SERVERS") else {
+            return;
+        };
+        let topic = format!("cocoindex-test-{}", uuid::Uuid::new_v4());
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &bootstrap_servers)
+            .create()
+            .unwrap();
+        let send = |key: &'static str, payload: Option<&'static str>| {
+            let producer = producer.clone();
+            let topic = topic.clone();
+            async move {
+                let mut record = FutureRecord::<str, str>::to(&topic).key(key);
+                if let Some(payload) = payload {
+                    record = record.payload(payload);
+                }
+                producer
+                    .send(record, Duration::from_secs(10))
+                    .await
+                    .map_err(|(e, _)| e)
+                    .unwrap();
+            }
+        };
+        send("a", Some("a1")).await;
+        send("b", Some("b1")).await;
+        send("a", Some("a2")).await;
+        send("b", None).await;
+
+        let executor = Executor {
+            bootstrap_servers,
+            topic,
+            group_id: format!("cocoindex-test-{}", uuid::Uuid::new_v4()),
+            ordinal_source: OrdinalSource::Offset,
+            binary_key: false,
+            binary: false,
+            consumer_options: HashMap::new(),
+            keys: tokio::sync::Mutex::new(None),
+        };
+        let rows = executor
+            .list(&SourceExecutorListOptions {
+                include_ordinal: true,
+            })
+            .next()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].key, KeyValue::Str("a".into()));
+        assert_eq!(rows[0].ordinal, Some(Ordinal(Some(2))));
+
+        // A deleted key is reported with the tombstone's ordinal.
+        let data = executor
+            .get_value(
+                &KeyValue::Str("b".into()),
+                &SourceExecutorGetOptions {
+                    include_value: false,
+                    include_ordinal: true,
+                },
+            )
+            .await
+            .unwrap();
+        assert!(matches!(data.value, Some(SourceValue::NonExistence)));
+        assert_eq!(data.ordinal, Some(Ordinal(Some(3))));
+
+        let mut change_stream = executor.change_stream().await.unwrap().unwrap();
+        for expected_offset in 0..4 {
+            let message = tokio::time::timeout(Duration::from_secs(30), change_stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            let data = message.changes[0].data.as_ref().unwrap();
+            assert_eq!(data.ordinal, Ordinal(Some(expected_offset)));
+            if expected_offset == 3 {
+                assert!(matches!(data.value, SourceValue::NonExistence));
+            }
+            (message.ack_fn.unwrap())().await.unwrap();
+        }
+    }
+}
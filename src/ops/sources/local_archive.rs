@@ -0,0 +1,667 @@
+use async_stream::try_stream;
+use log::warn;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::base::field_attrs;
+use crate::ops::shared::pattern_matcher::PatternMatcher;
+use crate::{fields_value, ops::sdk::*};
+
+/// Separator between the archive path and the member path in keys.
+const MEMBER_PATH_SEPARATOR: char = '!';
+
+#[derive(Debug, Deserialize)]
+pub struct Spec {
+    path: String,
+    binary: bool,
+    included_patterns: Option<Vec<String>>,
+    excluded_patterns: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if file_name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if file_name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+fn normalize_member_path(path: &str) -> &str {
+    path.trim_start_matches("./")
+}
+
+fn zip_time_to_ordinal(time: zip::DateTime) -> Ordinal {
+    chrono::NaiveDate::from_ymd_opt(time.year() as i32, time.month() as u32, time.day() as u32)
+        .and_then(|date| {
+            date.and_hms_opt(
+                time.hour() as u32,
+                time.minute() as u32,
+                time.second() as u32,
+            )
+        })
+        .map_or(Ordinal::unavailable(), |datetime| {
+            Ordinal(Some(datetime.and_utc().timestamp_micros()))
+        })
+}
+
+/// Where to read the content of a member from.
+enum MemberLocation {
+    /// Name of the member in the zip archive, before normalization.
+    Zip(String),
+    /// Offset and size of the member's content in the tar file.
+    Tar { offset: u64, size: u64 },
+    /// Gzipped tar archives can't be seeked, so the archive is decompressed up to the member.
+    TarGz,
+}
+
+/// Visit regular file members of an archive in order, with the member path, mtime as ordinal and location,
+/// until `visit` returns `Some`.
+fn visit_archive_members<T>(
+    path: &Path,
+    format: ArchiveFormat,
+    mut visit: impl FnMut(&str, Ordinal, MemberLocation, &mut dyn Read) -> Result<Option<T>>,
+) -> Result<Option<T>> {
+    fn visit_tar_entries<'a, R: Read + 'a, T>(
+        entries: impl Iterator<Item = std::io::Result<tar::Entry<'a, R>>>,
+        seekable: bool,
+        visit: &mut impl FnMut(&str, Ordinal, MemberLocation, &mut dyn Read) -> Result<Option<T>>,
+    ) -> Result<Option<T>> {
+        for entry in entries {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let member_path = entry.path()?;
+            let Some(member_path) = member_path.to_str().map(str::to_string) else {
+                warn!("Skipped ill-formed member path: {}", member_path.display());
+                continue;
+            };
+            let ordinal = Ordinal(Some(entry.header().mtime()? as i64 * 1_000_000));
+            let location = if seekable {
+                MemberLocation::Tar {
+                    offset: entry.raw_file_position(),
+                    size: entry.size(),
+                }
+            } else {
+                MemberLocation::TarGz
+            };
+            if let Some(result) = visit(
+                normalize_member_path(&member_path),
+                ordinal,
+                location,
+                &mut entry,
+            )? {
+                return Ok(Some(result));
+            }
+        }
+        Ok(None)
+    }
+
+    let file = File::open(path)?;
+    match format {
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(file)?;
+            for idx in 0..archive.len() {
+                let mut member = archive.by_index(idx)?;
+                if !member.is_file() {
+                    continue;
+                }
+                let member_path = member.name().to_string();
+                let ordinal = member
+                    .last_modified()
+                    .map_or(Ordinal::unavailable(), zip_time_to_ordinal);
+                if let Some(result) = visit(
+                    normalize_member_path(&member_path),
+                    ordinal,
+                    MemberLocation::Zip(member_path.clone()),
+                    &mut member,
+                )? {
+                    return Ok(Some(result));
+                }
+            }
+            Ok(None)
+        }
+        ArchiveFormat::Tar => {
+            let mut archive = tar::Archive::new(file);
+            visit_tar_entries(archive.entries_with_seek()?, true, &mut visit)
+        }
+        ArchiveFormat::TarGz => {
+            let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+            visit_tar_entries(archive.entries()?, false, &mut visit)
+        }
+    }
+}
+
+/// Split a key into the archive path and the member path, at the first separator following an archive file name.
+/// Either path may contain the separator itself.
+fn split_member_key(key: &str) -> Option<(&str, &str)> {
+    key.match_indices(MEMBER_PATH_SEPARATOR)
+        .map(|(idx, _)| (&key[..idx], &key[idx + MEMBER_PATH_SEPARATOR.len_utf8()..]))
+        .find(|(archive, _)| ArchiveFormat::from_path(Path::new(archive)).is_some())
+}
+
+struct IndexedMember {
+    ordinal: Ordinal,
+    location: MemberLocation,
+}
+
+/// Included members of an archive, so a member can be fetched without scanning the archive.
+struct ArchiveIndex {
+    path: PathBuf,
+    modified: std::time::SystemTime,
+    /// Keyed by normalized member paths, in archive order.
+    members: IndexMap<String, IndexedMember>,
+}
+
+impl ArchiveIndex {
+    fn read_member(&self, member_path: &str, member: &IndexedMember) -> Result<Vec<u8>> {
+        let mut content = Vec::new();
+        match &member.location {
+            MemberLocation::Zip(name) => {
+                let mut archive = zip::ZipArchive::new(File::open(&self.path)?)?;
+                archive.by_name(name)?.read_to_end(&mut content)?;
+            }
+            MemberLocation::Tar { offset, size } => {
+                let mut file = File::open(&self.path)?;
+                file.seek(SeekFrom::Start(*offset))?;
+                file.take(*size).read_to_end(&mut content)?;
+            }
+            MemberLocation::TarGz => {
+                content = visit_archive_members(
+                    &self.path,
+                    ArchiveFormat::TarGz,
+                    |curr_member_path, _, _, reader| {
+                        if curr_member_path != member_path {
+                            return Ok(None);
+                        }
+                        let mut content = Vec::new();
+                        reader.read_to_end(&mut content)?;
+                        Ok(Some(content))
+                    },
+                )?
+                .ok_or_else(invariance_violation)?;
+            }
+        }
+        Ok(content)
+    }
+}
+
+struct ArchiveReader {
+    /// Archive paths in keys are relative to it.
+    base_dir: PathBuf,
+    /// Set when the spec points to a single archive file, instead of a directory.
+    single_archive: Option<String>,
+    pattern_matcher: PatternMatcher,
+    /// Keyed by archive paths relative to `base_dir`.
+    indexes: Mutex<HashMap<String, Arc<ArchiveIndex>>>,
+}
+
+impl ArchiveReader {
+    fn is_member_included(&self, member_path: &str) -> bool {
+        self.pattern_matcher.is_file_included(member_path) && !self.is_excluded(member_path)
+    }
+
+    /// Whether an archive is excluded. The archive specified as the single one is never excluded.
+    fn is_archive_excluded(&self, archive: &str) -> bool {
+        self.single_archive.is_none() && self.is_excluded(archive)
+    }
+
+    /// A path (of an archive or a member) is excluded if itself or any of its parent directories matches the
+    /// excluded patterns.
+    fn is_excluded(&self, path: &str) -> bool {
+        path.match_indices('/')
+            .map(|(idx, _)| &path[..idx])
+            .chain(std::iter::once(path))
+            .any(|path| self.pattern_matcher.is_excluded(path))
+    }
+
+    /// Relative paths of all archives to import.
+    fn list_archives(&self) -> Result<Vec<String>> {
+        if let Some(single_archive) = &self.single_archive {
+            return Ok(vec![single_archive.clone()]);
+        }
+        let mut archives = Vec::new();
+        let mut dirs = vec![self.base_dir.clone()];
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let path = entry?.path();
+                let is_dir = path.is_dir();
+                if !is_dir && ArchiveFormat::from_path(&path).is_none() {
+                    continue;
+                }
+                let Some(relative_path) = path
+                    .strip_prefix(&self.base_dir)
+                    .ok()
+                    .and_then(|p| p.to_str())
+                else {
+                    warn!("Skipped ill-formed path: {}", path.display());
+                    continue;
+                };
+                if self.pattern_matcher.is_excluded(relative_path) {
+                    continue;
+                }
+                if is_dir {
+                    dirs.push(path);
+                } else {
+                    archives.push(relative_path.to_string());
+                }
+            }
+        }
+        archives.sort();
+        self.indexes
+            .lock()
+            .unwrap()
+            .retain(|archive, _| archives.binary_search(archive).is_ok());
+        Ok(archives)
+    }
+
+    /// Get the index of an archive, scanning it only if it's changed since last indexed.
+    /// Returns `None` if the archive doesn't exist.
+    fn index_archive(&self, archive: &str) -> Result<Option<Arc<ArchiveIndex>>> {
+        let path = self.base_dir.join(archive);
+        let Some(format) = ArchiveFormat::from_path(&path) else {
+            return Ok(None);
+        };
+        let modified = match path.metadata() {
+            Ok(metadata) if metadata.is_file() => metadata.modified()?,
+            Ok(_) => return Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => Err(e)?,
+        };
+        if let Some(index) = self.indexes.lock().unwrap().get(archive) {
+            if index.modified == modified {
+                return Ok(Some(index.clone()));
+            }
+        }
+
+        let mut members = IndexMap::new();
+        visit_archive_members::<()>(&path, format, |member_path, ordinal, location, _| {
+            if self.is_member_included(member_path) {
+                members.insert(member_path.to_string(), IndexedMember { ordinal, location });
+            }
+            Ok(None)
+        })
+        .with_context(|| format!("Failed to read archive: {}", path.display()))?;
+        let index = Arc::new(ArchiveIndex {
+            path,
+            modified,
+            members,
+        });
+        self.indexes
+            .lock()
+            .unwrap()
+            .insert(archive.to_string(), index.clone());
+        Ok(Some(index))
+    }
+
+    fn list_members(&self, archive: &str) -> Result<Vec<PartialSourceRowMetadata>> {
+        let Some(index) = self.index_archive(archive)? else {
+            return Ok(vec![]);
+        };
+        Ok(index
+            .members
+            .iter()
+            .map(|(member_path, member)| PartialSourceRowMetadata {
+                key: KeyValue::Str(format!("{archive}{MEMBER_PATH_SEPARATOR}{member_path}").into()),
+                ordinal: Some(member.ordinal),
+            })
+            .collect())
+    }
+
+    fn get_member(
+        &self,
+        key: &str,
+        include_value: bool,
+    ) -> Result<Option<(Ordinal, Option<Vec<u8>>)>> {
+        let Some((archive, member_path)) = split_member_key(key) else {
+            api_bail!("Invalid key for archive member: {key}");
+        };
+        if !self.is_member_included(member_path)
+            || self.is_archive_excluded(archive)
+            || self
+                .single_archive
+                .as_ref()
+                .is_some_and(|single_archive| single_archive != archive)
+        {
+            return Ok(None);
+        }
+        let Some(index) = self.index_archive(archive)? else {
+            return Ok(None);
+        };
+        let Some(member) = index.members.get(member_path) else {
+            return Ok(None);
+        };
+        let content = if include_value {
+            Some(
+                index
+                    .read_member(member_path, member)
+                    .with_context(|| format!("Failed to read archive: {}", index.path.display()))?,
+            )
+        } else {
+            None
+        };
+        Ok(Some((member.ordinal, content)))
+    }
+}
+
+struct Executor {
+    reader: Arc<ArchiveReader>,
+    binary: bool,
+}
+
+#[async_trait]
+impl SourceExecutor for Executor {
+    fn list<'a>(
+        &'a self,
+        options: &'a SourceExecutorListOptions,
+    ) -> BoxStream<'a, Result<Vec<PartialSourceRowMetadata>>> {
+        try_stream! {
+            let reader = self.reader.clone();
+            let archives = tokio::task::spawn_blocking(move || reader.list_archives()).await??;
+            for archive in archives {
+                let reader = self.reader.clone();
+                let mut rows =
+                    tokio::task::spawn_blocking(move || reader.list_members(&archive)).await??;
+                if !options.include_ordinal {
+                    for row in rows.iter_mut() {
+                        row.ordinal = None;
+                    }
+                }
+                yield rows;
+            }
+        }
+        .boxed()
+    }
+
+    async fn get_value(
+        &self,
+        key: &KeyValue,
+        options: &SourceExecutorGetOptions,
+    ) -> Result<PartialSourceRowData> {
+        let reader = self.reader.clone();
+        let key = key.str_value()?.clone();
+        let include_value = options.include_value;
+        let member =
+            tokio::task::spawn_blocking(move || reader.get_member(&key, include_value)).await??;
+        let Some((ordinal, content)) = member else {
+            return Ok(PartialSourceRowData {
+                value: Some(SourceValue::NonExistence),
+                ordinal: Some(Ordinal::unavailable()),
+            });
+        };
+        let value = content.map(|content| {
+            SourceValue::Existence(if self.binary {
+                fields_value!(content)
+            } else {
+                fields_value!(String::from_utf8_lossy(&content).to_string())
+            })
+        });
+        Ok(PartialSourceRowData {
+            value,
+            ordinal: options.include_ordinal.then_some(ordinal),
+        })
+    }
+}
+
+pub struct Factory;
+
+#[async_trait]
+impl SourceFactoryBase for Factory {
+    type Spec = Spec;
+
+    fn name(&self) -> &str {
+        "LocalArchive"
+    }
+
+    async fn get_output_schema(
+        &self,
+        spec: &Spec,
+        _context: &FlowInstanceContext,
+    ) -> Result<EnrichedValueType> {
+        let mut struct_schema = StructSchema::default();
+        let mut schema_builder = StructSchemaBuilder::new(&mut struct_schema);
+        let filename_field = schema_builder.add_field(FieldSchema::new(
+            "filename",
+            make_output_type(BasicValueType::Str),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "content",
+            make_output_type(if spec.binary {
+                BasicValueType::Bytes
+            } else {
+                BasicValueType::Str
+            })
+            .with_attr(
+                field_attrs::CONTENT_FILENAME,
+                serde_json::to_value(filename_field.to_field_ref())?,
+            ),
+        ));
+
+        Ok(make_output_type(TableSchema::new(
+            TableKind::KTable,
+            struct_schema,
+        )))
+    }
+
+    async fn build_executor(
+        self: Arc<Self>,
+        spec: Spec,
+        _context: Arc<FlowInstanceContext>,
+    ) -> Result<Box<dyn SourceExecutor>> {
+        let path = PathBuf::from(spec.path);
+        let (base_dir, single_archive) = if path.is_file() {
+            if ArchiveFormat::from_path(&path).is_none() {
+                api_bail!(
+                    "Unsupported archive format: {}. Supported ones are .zip, .tar, .tar.gz and .tgz",
+                    path.display()
+                );
+            }
+            let file_name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or_else(|| api_error!("Ill-formed archive path: {}", path.display()))?
+                .to_string();
+            let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            (base_dir, Some(file_name))
+        } else {
+            (path, None)
+        };
+        Ok(Box::new(Executor {
+            reader: Arc::new(ArchiveReader {
+                base_dir,
+                single_archive,
+                pattern_matcher: PatternMatcher::new(
+                    spec.included_patterns,
+                    spec.excluded_patterns,
+                )?,
+                indexes: Mutex::new(HashMap::new()),
+            }),
+            binary: spec.binary,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::shared::test_dir::TestDir;
+    use std::io::Write;
+
+    fn test_dir() -> TestDir {
+        let dir = TestDir::new("archive");
+        std::fs::create_dir(dir.0.join("nested")).unwrap();
+        std::fs::create_dir(dir.0.join("tmp")).unwrap();
+        dir
+    }
+
+    fn write_zip(path: &Path, members: &[(&str, &str)]) {
+        let mut writer = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .last_modified_time(zip::DateTime::from_date_and_time(2024, 1, 2, 3, 4, 6).unwrap());
+        for (name, content) in members {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    fn write_tar_gz(path: &Path, members: &[(&str, &str, u64)]) {
+        let encoder =
+            flate2::write::GzEncoder::new(File::create(path).unwrap(), Default::default());
+        write_tar_members(encoder, members).finish().unwrap();
+    }
+
+    fn write_tar(path: &Path, members: &[(&str, &str, u64)]) {
+        write_tar_members(File::create(path).unwrap(), members);
+    }
+
+    fn write_tar_members<W: Write>(writer: W, members: &[(&str, &str, u64)]) -> W {
+        let mut builder = tar::Builder::new(writer);
+        for (name, content, mtime) in members {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mtime(*mtime);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn reader(base_dir: &Path, excluded_patterns: Option<Vec<String>>) -> ArchiveReader {
+        ArchiveReader {
+            base_dir: base_dir.to_path_buf(),
+            single_archive: None,
+            pattern_matcher: PatternMatcher::new(None, excluded_patterns).unwrap(),
+            indexes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    #[test]
+    fn test_list_and_get_members() {
+        let dir = test_dir();
+        write_zip(
+            &dir.0.join("bundle.zip"),
+            &[("docs/a.md", "a"), ("tmp/x.md", "x")],
+        );
+        write_tar_gz(
+            &dir.0.join("nested/dump.tar.gz"),
+            &[("./data/b.txt", "b", 1_700_000_000)],
+        );
+        // Excluded patterns also apply to archive paths and their directories.
+        write_zip(&dir.0.join("tmp/skipped.zip"), &[("docs/a.md", "a")]);
+        write_zip(&dir.0.join("nested/skipped.zip"), &[("docs/a.md", "a")]);
+        std::fs::write(dir.0.join("other.txt"), "not an archive").unwrap();
+        let reader = reader(
+            &dir.0,
+            Some(vec!["tmp".to_string(), "**/skipped.zip".to_string()]),
+        );
+
+        let mut rows = reader
+            .list_archives()
+            .unwrap()
+            .iter()
+            .flat_map(|archive| reader.list_members(archive).unwrap())
+            .map(|row| (row.key.str_value().unwrap().to_string(), row.ordinal))
+            .collect::<Vec<_>>();
+        rows.sort_by(|a, b| a.0.cmp(&b.0));
+        let zip_mtime = chrono::NaiveDate::from_ymd_opt(2024, 1, 2)
+            .unwrap()
+            .and_hms_opt(3, 4, 6)
+            .unwrap()
+            .and_utc()
+            .timestamp_micros();
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    "bundle.zip!docs/a.md".to_string(),
+                    Some(Ordinal(Some(zip_mtime)))
+                ),
+                (
+                    "nested/dump.tar.gz!data/b.txt".to_string(),
+                    Some(Ordinal(Some(1_700_000_000_000_000)))
+                ),
+            ]
+        );
+
+        let (ordinal, content) = reader
+            .get_member("nested/dump.tar.gz!data/b.txt", true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(ordinal, Ordinal(Some(1_700_000_000_000_000)));
+        assert_eq!(content, Some(b"b".to_vec()));
+        assert!(
+            reader
+                .get_member("bundle.zip!tmp/x.md", true)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            reader
+                .get_member("bundle.zip!docs/missing.md", true)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            reader
+                .get_member("missing.zip!docs/a.md", true)
+                .unwrap()
+                .is_none()
+        );
+        for key in ["tmp/skipped.zip!docs/a.md", "nested/skipped.zip!docs/a.md"] {
+            assert!(reader.get_member(key, true).unwrap().is_none(), "{key}");
+        }
+    }
+
+    #[test]
+    fn test_get_members_from_index() {
+        let dir = test_dir();
+        let members = [("a.txt", "a", 1), ("b!c.txt", "bc", 2), ("d.txt", "d", 3)];
+        write_tar(&dir.0.join("x!y.tar"), &members);
+        write_tar_gz(&dir.0.join("nested/z.tgz"), &members);
+        write_zip(
+            &dir.0.join("w.zip"),
+            &members.map(|(name, content, _)| (name, content)),
+        );
+        let reader = reader(&dir.0, None);
+
+        for archive in ["x!y.tar", "nested/z.tgz", "w.zip"] {
+            // Fetch out of order and repeatedly, as rows are processed in no particular order.
+            for (name, content, _) in [members[2], members[1], members[0], members[1]] {
+                let (_, fetched) = reader
+                    .get_member(&format!("{archive}!{name}"), true)
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(
+                    fetched,
+                    Some(content.as_bytes().to_vec()),
+                    "{archive}!{name}"
+                );
+            }
+        }
+        assert_eq!(
+            split_member_key("x!y.tar!b!c.txt"),
+            Some(("x!y.tar", "b!c.txt"))
+        );
+        assert_eq!(split_member_key("a!b"), None);
+    }
+}
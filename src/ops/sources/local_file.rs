@@ -1,11 +1,14 @@
 use async_stream::try_stream;
-use globset::{Glob, GlobSet, GlobSetBuilder};
 use log::warn;
+use notify::{RecursiveMode, Watcher};
 use std::borrow::Cow;
 use std::path::Path;
+use std::time::Duration;
 use std::{path::PathBuf, sync::Arc};
+use tokio::sync::mpsc;
 
 use crate::base::field_attrs;
+use crate::ops::shared::pattern_matcher::PatternMatcher;
 use crate::{fields_value, ops::sdk::*};
 
 #[derive(Debug, Deserialize)]
@@ -14,28 +17,75 @@ pub struct Spec {
     binary: bool,
     included_patterns: Option<Vec<String>>,
     excluded_patterns: Option<Vec<String>>,
+    #[serde(default)]
+    watch_changes: bool,
 }
 
 struct Executor {
     root_path: PathBuf,
     binary: bool,
-    included_glob_set: Option<GlobSet>,
-    excluded_glob_set: Option<GlobSet>,
+    pattern_matcher: PatternMatcher,
+    watch_changes: bool,
 }
 
+/// Events arriving within this interval after the previous one are merged into the same change message.
+const WATCH_DEBOUNCE_INTERVAL: Duration = Duration::from_millis(200);
+/// Upper bound of the delay for a change message, in case events keep arriving.
+const WATCH_MAX_DELAY: Duration = Duration::from_secs(1);
+
 impl Executor {
-    fn is_excluded(&self, path: impl AsRef<Path> + Copy) -> bool {
-        self.excluded_glob_set
-            .as_ref()
-            .is_some_and(|glob_set| glob_set.is_match(path))
+    fn relative_path<'p>(&self, path: &'p Path) -> Option<&'p Path> {
+        path.strip_prefix(&self.root_path).ok()
     }
 
-    fn is_file_included(&self, path: impl AsRef<Path> + Copy) -> bool {
-        self.included_glob_set
-            .as_ref()
-            .is_none_or(|glob_set| glob_set.is_match(path))
-            && !self.is_excluded(path)
+    fn key_for_file(&self, path: &Path) -> Option<KeyValue> {
+        let relative_path = self.relative_path(path)?;
+        if !self.pattern_matcher.is_file_included(relative_path) {
+            return None;
+        }
+        match relative_path.to_str() {
+            Some(relative_path) => Some(KeyValue::Str(relative_path.into())),
+            None => {
+                warn!("Skipped ill-formed file path: {}", path.display());
+                None
+            }
+        }
     }
+
+    /// Watch `dir` and all its non-excluded subdirectories.
+    /// Keys of files found under them are appended to `found_keys`, if provided.
+    async fn watch_dir(
+        &self,
+        watcher: &mut impl Watcher,
+        dir: PathBuf,
+        mut found_keys: Option<&mut Vec<KeyValue>>,
+    ) -> Result<()> {
+        let mut dirs = vec![dir];
+        while let Some(dir) = dirs.pop() {
+            watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+            let mut entries = tokio::fs::read_dir(&dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if is_dir(&path).await {
+                    if self.relative_path(&path).is_some_and(|relative_path| {
+                        !self.pattern_matcher.is_excluded(relative_path)
+                    }) {
+                        dirs.push(path);
+                    }
+                } else if let Some(found_keys) = found_keys.as_deref_mut() {
+                    found_keys.extend(self.key_for_file(&path));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Same as `Path::is_dir()`, without blocking.
+async fn is_dir(path: &Path) -> bool {
+    tokio::fs::metadata(path)
+        .await
+        .is_ok_and(|metadata| metadata.is_dir())
 }
 
 #[async_trait]
@@ -59,10 +109,10 @@ impl SourceExecutor for Executor {
                     }
                     let relative_path = path_components.as_path();
                     if path.is_dir() {
-                        if !self.is_excluded(relative_path) {
+                        if !self.pattern_matcher.is_excluded(relative_path) {
                             new_dirs.push(Cow::Owned(path));
                         }
-                    } else if self.is_file_included(relative_path) {
+                    } else if self.pattern_matcher.is_file_included(relative_path) {
                         let ordinal: Option<Ordinal> = if options.include_ordinal {
                             Some(path.metadata()?.modified()?.try_into()?)
                         } else {
@@ -89,7 +139,10 @@ impl SourceExecutor for Executor {
         key: &KeyValue,
         options: &SourceExecutorGetOptions,
     ) -> Result<PartialSourceRowData> {
-        if !self.is_file_included(key.str_value()?.as_ref()) {
+        if !self
+            .pattern_matcher
+            .is_file_included(key.str_value()?.as_ref())
+        {
             return Ok(PartialSourceRowData {
                 value: Some(SourceValue::NonExistence),
                 ordinal: Some(Ordinal::unavailable()),
@@ -97,7 +150,16 @@ impl SourceExecutor for Executor {
         }
         let path = self.root_path.join(key.str_value()?.as_ref());
         let ordinal = if options.include_ordinal {
-            Some(path.metadata()?.modified()?.try_into()?)
+            match path.metadata() {
+                Ok(metadata) => Some(metadata.modified()?.try_into()?),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    return Ok(PartialSourceRowData {
+                        value: Some(SourceValue::NonExistence),
+                        ordinal: Some(Ordinal::unavailable()),
+                    });
+                }
+                Err(e) => Err(e)?,
+            }
         } else {
             None
         };
@@ -121,6 +183,78 @@ impl SourceExecutor for Executor {
         };
         Ok(PartialSourceRowData { value, ordinal })
     }
+
+    async fn change_stream(
+        &self,
+    ) -> Result<Option<BoxStream<'async_trait, Result<SourceChangeMessage>>>> {
+        if !self.watch_changes {
+            return Ok(None);
+        }
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = event_tx.send(event);
+        })?;
+        self.watch_dir(&mut watcher, self.root_path.clone(), None)
+            .await?;
+
+        let stream = try_stream! {
+            let mut watcher = watcher;
+            while let Some(event) = event_rx.recv().await {
+                let mut changed_paths = IndexSet::<PathBuf>::new();
+                let mut event: notify::Result<notify::Event> = event;
+                let deadline = tokio::time::Instant::now() + WATCH_MAX_DELAY;
+                loop {
+                    // Errors of the watcher are not fatal, so they don't end the stream.
+                    match event {
+                        Ok(notify::Event { kind, paths, .. }) => {
+                            if !kind.is_access() {
+                                changed_paths.extend(paths);
+                            }
+                        }
+                        Err(e) => {
+                            warn!("Error in watching {}: {e}", self.root_path.display());
+                        }
+                    }
+                    let timeout = WATCH_DEBOUNCE_INTERVAL
+                        .min(deadline.saturating_duration_since(tokio::time::Instant::now()));
+                    match tokio::time::timeout(timeout, event_rx.recv()).await {
+                        Ok(Some(next_event)) => event = next_event,
+                        Ok(None) | Err(_) => break,
+                    }
+                }
+
+                let mut keys = Vec::new();
+                for path in changed_paths {
+                    if is_dir(&path).await {
+                        // A new directory may be created (or moved in) with files inside.
+                        if self.relative_path(&path).is_some_and(|relative_path| {
+                            !self.pattern_matcher.is_excluded(relative_path)
+                        }) {
+                            // The directory may be removed again before it's watched.
+                            let result =
+                                self.watch_dir(&mut watcher, path.clone(), Some(&mut keys)).await;
+                            if let Err(e) = result {
+                                warn!("Failed to watch directory {}: {e:?}", path.display());
+                            }
+                        }
+                    } else {
+                        keys.extend(self.key_for_file(&path));
+                    }
+                }
+                if !keys.is_empty() {
+                    yield SourceChangeMessage {
+                        changes: keys
+                            .into_iter()
+                            .unique()
+                            .map(|key| SourceChange { key, data: None })
+                            .collect(),
+                        ack_fn: None,
+                    };
+                }
+            }
+        };
+        Ok(Some(stream.boxed()))
+    }
 }
 
 pub struct Factory;
@@ -168,19 +302,115 @@ impl SourceFactoryBase for Factory {
         spec: Spec,
         _context: Arc<FlowInstanceContext>,
     ) -> Result<Box<dyn SourceExecutor>> {
+        let mut root_path = PathBuf::from(spec.path);
+        if spec.watch_changes {
+            // Paths in watch events are absolute, and may have symlinks resolved (e.g. on macOS).
+            // So is the root, so it can be stripped off from them.
+            root_path = tokio::fs::canonicalize(&root_path)
+                .await
+                .with_context(|| format!("Failed to resolve path: {}", root_path.display()))?;
+        }
         Ok(Box::new(Executor {
-            root_path: PathBuf::from(spec.path),
+            root_path,
             binary: spec.binary,
-            included_glob_set: spec.included_patterns.map(build_glob_set).transpose()?,
-            excluded_glob_set: spec.excluded_patterns.map(build_glob_set).transpose()?,
+            pattern_matcher: PatternMatcher::new(spec.included_patterns, spec.excluded_patterns)?,
+            watch_changes: spec.watch_changes,
         }))
     }
 }
 
-fn build_glob_set(patterns: Vec<String>) -> Result<GlobSet> {
-    let mut builder = GlobSetBuilder::new();
-    for pattern in patterns {
-        builder.add(Glob::new(pattern.as_str())?);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::shared::test_dir::TestDir;
+    use std::collections::BTreeSet;
+
+    /// Receives change messages until all expected keys are seen, and no others.
+    async fn expect_changed_keys(
+        stream: &mut BoxStream<'_, Result<SourceChangeMessage>>,
+        expected: &[&str],
+    ) {
+        let expected = expected
+            .iter()
+            .map(|k| k.to_string())
+            .collect::<BTreeSet<_>>();
+        let mut keys = BTreeSet::new();
+        while keys != expected {
+            let message = tokio::time::timeout(Duration::from_secs(10), stream.next())
+                .await
+                .expect("timed out waiting for changes")
+                .unwrap()
+                .unwrap();
+            for change in message.changes {
+                let key = change.key.str_value().unwrap().to_string();
+                assert!(expected.contains(&key), "unexpected change of `{key}`");
+                keys.insert(key);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_changes() {
+        let dir = TestDir::new("local-file");
+        let root = &dir.0;
+        std::fs::create_dir(root.join("excluded")).unwrap();
+        let executor = Executor {
+            root_path: root.clone(),
+            binary: false,
+            pattern_matcher: PatternMatcher::new(
+                None,
+                Some(vec!["excluded".to_string(), "**/*.tmp".to_string()]),
+            )
+            .unwrap(),
+            watch_changes: true,
+        };
+        let mut stream = executor.change_stream().await.unwrap().unwrap();
+
+        std::fs::write(root.join("a.txt"), "a").unwrap();
+        std::fs::write(root.join("excluded/b.txt"), "b").unwrap();
+        std::fs::write(root.join("c.tmp"), "c").unwrap();
+        expect_changed_keys(&mut stream, &["a.txt"]).await;
+
+        // Files in new directories are picked up, even if created before the directory is watched.
+        std::fs::create_dir(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub/d.txt"), "d").unwrap();
+        expect_changed_keys(&mut stream, &["sub/d.txt"]).await;
+
+        std::fs::write(root.join("a.txt"), "a2").unwrap();
+        expect_changed_keys(&mut stream, &["a.txt"]).await;
+
+        std::fs::remove_file(root.join("a.txt")).unwrap();
+        std::fs::remove_file(root.join("sub/d.txt")).unwrap();
+        expect_changed_keys(&mut stream, &["a.txt", "sub/d.txt"]).await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_watch_changes_with_relative_path() {
+        let dir = TestDir::new("local-file");
+        let cwd = std::env::current_dir().unwrap();
+        let relative_path = std::iter::repeat_n(Path::new(".."), cwd.components().count() - 1)
+            .collect::<PathBuf>()
+            .join(dir.0.strip_prefix("/").unwrap());
+        let spec = Spec {
+            path: relative_path.to_str().unwrap().to_string(),
+            binary: false,
+            included_patterns: None,
+            excluded_patterns: None,
+            watch_changes: true,
+        };
+        let context = Arc::new(FlowInstanceContext {
+            flow_instance_name: "test".to_string(),
+            auth_registry: Arc::new(AuthRegistry::default()),
+            py_exec_ctx: None,
+        });
+        let executor = Arc::new(Factory)
+            .build_executor(spec, context)
+            .await
+            .unwrap();
+        let mut stream = executor.change_stream().await.unwrap().unwrap();
+
+        std::fs::write(dir.0.join("a.txt"), "a").unwrap();
+        expect_changed_keys(&mut stream, &["a.txt"]).await;
     }
-    Ok(builder.build()?)
 }
@@ -0,0 +1,676 @@
+use async_stream::try_stream;
+use log::warn;
+use mail_parser::{Address, MessageParser, MimeHeaders, PartType};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+use crate::ops::sdk::*;
+use crate::ops::shared::pattern_matcher::PatternMatcher;
+
+/// Number of recently read mbox files kept in memory, so messages of the same file don't re-read it.
+const MBOX_CACHE_CAPACITY: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum MailboxFormat {
+    Maildir,
+    Mbox,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Spec {
+    path: String,
+    format: MailboxFormat,
+    included_patterns: Option<Vec<String>>,
+    excluded_patterns: Option<Vec<String>>,
+}
+
+/// Where a message is stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MessageLocation {
+    /// Path of the Maildir message file or the mbox file, relative to the base directory.
+    filename: String,
+    /// Index of the message in the mbox file.
+    mbox_index: Option<usize>,
+}
+
+/// Keys of messages in a file, with their locations.
+type FileMessages = Arc<Vec<(Arc<str>, MessageLocation)>>;
+
+#[derive(Clone)]
+struct ListedFile {
+    ordinal: Ordinal,
+    messages: FileMessages,
+}
+
+#[derive(Default)]
+struct ListedState {
+    files: HashMap<String, ListedFile>,
+    key_to_location: HashMap<Arc<str>, (Ordinal, MessageLocation)>,
+}
+
+struct MboxFile {
+    filename: String,
+    ordinal: Ordinal,
+    messages: Vec<Vec<u8>>,
+}
+
+struct MailboxReader {
+    /// Filenames are relative to it.
+    base_dir: PathBuf,
+    /// Set when the spec points to a single mbox file, instead of a directory.
+    single_file: Option<String>,
+    format: MailboxFormat,
+    pattern_matcher: PatternMatcher,
+    /// State of the last listing, to look up messages by keys.
+    listed_state: Mutex<Option<ListedState>>,
+    mbox_files: Mutex<VecDeque<Arc<MboxFile>>>,
+}
+
+/// The later of the modification and status change time of the file.
+/// Maildir moves messages (e.g. from `new` to `cur`, or to add flags to filenames) without changing the modification
+/// time, so the status change time tells the `filename` field changes.
+fn file_ordinal(path: &Path) -> Result<Option<Ordinal>> {
+    let metadata = match path.metadata() {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => Err(e)?,
+    };
+    let modified: Ordinal = metadata.modified()?.try_into()?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let changed = metadata.ctime() * 1_000_000 + metadata.ctime_nsec() / 1_000;
+        Ok(Some(Ordinal(modified.0.max(Some(changed)))))
+    }
+    #[cfg(not(unix))]
+    Ok(Some(modified))
+}
+
+fn format_addresses(address: Option<&Address<'_>>) -> Option<String> {
+    let formatted = address?
+        .iter()
+        .filter_map(|addr| match (&addr.name, &addr.address) {
+            (Some(name), Some(address)) => Some(format!("{name} <{address}>")),
+            (None, Some(address)) => Some(address.to_string()),
+            (Some(name), None) => Some(name.to_string()),
+            (None, None) => None,
+        })
+        .join(", ");
+    (!formatted.is_empty()).then_some(formatted)
+}
+
+/// Key of a message: its Message-ID, or the location if it has none.
+fn message_key(raw_message: &[u8], location: &MessageLocation) -> Arc<str> {
+    if let Some(message_id) = MessageParser::new()
+        .parse_headers(raw_message)
+        .and_then(|message| message.message_id().map(|id| id.to_string()))
+    {
+        return message_id.into();
+    }
+    match location.mbox_index {
+        Some(idx) => format!("{}#{}", location.filename, idx + 1).into(),
+        None => location.filename.as_str().into(),
+    }
+}
+
+fn parse_message(filename: &str, raw_message: &[u8]) -> Result<FieldValues> {
+    let message = MessageParser::new()
+        .parse(raw_message)
+        .ok_or_else(|| anyhow!("Failed to parse message in {filename}"))?;
+    let date = message
+        .date()
+        .and_then(|date| chrono::DateTime::parse_from_rfc3339(&date.to_rfc3339()).ok());
+    let text_body = message.text_bodies().find_map(|part| match &part.body {
+        PartType::Text(text) => Some(text.to_string()),
+        _ => None,
+    });
+    let html_body = message.html_bodies().find_map(|part| match &part.body {
+        PartType::Html(html) => Some(html.to_string()),
+        _ => None,
+    });
+    let attachments = message
+        .attachments()
+        .map(|part| {
+            let mime_type = match part.content_type() {
+                Some(content_type) => match content_type.subtype() {
+                    Some(subtype) => format!("{}/{subtype}", content_type.ctype()),
+                    None => content_type.ctype().to_string(),
+                },
+                None => "application/octet-stream".to_string(),
+            }
+            .to_ascii_lowercase();
+            ScopeValue(FieldValues {
+                fields: vec![
+                    part.attachment_name().map(|name| name.to_string()).into(),
+                    mime_type.into(),
+                    part.contents().to_vec().into(),
+                ],
+            })
+        })
+        .collect();
+    Ok(FieldValues {
+        fields: vec![
+            filename.to_string().into(),
+            format_addresses(message.from()).into(),
+            format_addresses(message.to()).into(),
+            format_addresses(message.cc()).into(),
+            message.subject().map(|subject| subject.to_string()).into(),
+            date.into(),
+            text_body.into(),
+            html_body.into(),
+            Value::LTable(attachments),
+        ],
+    })
+}
+
+impl MailboxReader {
+    /// Relative paths of Maildir message files, or mbox files.
+    fn list_files(&self) -> Result<Vec<String>> {
+        if let Some(single_file) = &self.single_file {
+            return Ok(vec![single_file.clone()]);
+        }
+        let mut files = Vec::new();
+        let mut dirs = vec![self.base_dir.clone()];
+        while let Some(dir) = dirs.pop() {
+            // Messages of a Maildir are under its `cur` and `new` subdirectories.
+            let is_message_dir = self.format == MailboxFormat::Mbox
+                || dir
+                    .file_name()
+                    .is_some_and(|name| name == "cur" || name == "new");
+            for entry in std::fs::read_dir(&dir)? {
+                let path = entry?.path();
+                let Some(relative_path) = path
+                    .strip_prefix(&self.base_dir)
+                    .ok()
+                    .and_then(|p| p.to_str())
+                else {
+                    warn!("Skipped ill-formed file path: {}", path.display());
+                    continue;
+                };
+                if path.is_dir() {
+                    if !self.pattern_matcher.is_excluded(relative_path) {
+                        dirs.push(path);
+                    }
+                } else if is_message_dir
+                    && !path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with('.'))
+                    && self.pattern_matcher.is_file_included(relative_path)
+                {
+                    files.push(relative_path.to_string());
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Read messages of an mbox file, or get them from recently read ones if the file is unchanged.
+    fn read_mbox(&self, filename: &str, ordinal: Ordinal) -> Result<Arc<MboxFile>> {
+        {
+            let mbox_files = self.mbox_files.lock().unwrap();
+            if let Some(mbox_file) = mbox_files
+                .iter()
+                .find(|f| f.filename == filename && f.ordinal == ordinal)
+            {
+                return Ok(mbox_file.clone());
+            }
+        }
+        let reader = std::io::BufReader::new(std::fs::File::open(self.base_dir.join(filename))?);
+        let messages = mail_parser::mailbox::mbox::MessageIterator::new(reader)
+            .map(|message| Ok(message?.contents().to_vec()))
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("Failed to read mbox file {filename}"))?;
+        let mbox_file = Arc::new(MboxFile {
+            filename: filename.to_string(),
+            ordinal,
+            messages,
+        });
+        let mut mbox_files = self.mbox_files.lock().unwrap();
+        mbox_files.retain(|f| f.filename != filename);
+        if mbox_files.len() >= MBOX_CACHE_CAPACITY {
+            mbox_files.pop_front();
+        }
+        mbox_files.push_back(mbox_file.clone());
+        Ok(mbox_file)
+    }
+
+    fn list_file_messages(
+        &self,
+        filename: &str,
+        ordinal: Ordinal,
+    ) -> Result<Vec<(Arc<str>, MessageLocation)>> {
+        match self.format {
+            MailboxFormat::Maildir => {
+                let raw_message = std::fs::read(self.base_dir.join(filename))?;
+                let location = MessageLocation {
+                    filename: filename.to_string(),
+                    mbox_index: None,
+                };
+                Ok(vec![(message_key(&raw_message, &location), location)])
+            }
+            MailboxFormat::Mbox => {
+                let mbox_file = self.read_mbox(filename, ordinal)?;
+                Ok(mbox_file
+                    .messages
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, raw_message)| {
+                        let location = MessageLocation {
+                            filename: filename.to_string(),
+                            mbox_index: Some(idx),
+                        };
+                        (message_key(raw_message, &location), location)
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// List keys of all messages by files, reusing ones of files unchanged since the last listing.
+    /// A key appearing in multiple locations is only listed for the first one.
+    fn list_messages(&self) -> Result<Vec<(Ordinal, Vec<Arc<str>>)>> {
+        // The new state is built on the side, so concurrent lookups still see the previous one.
+        let mut prev_files = self
+            .listed_state
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|state| state.files.clone())
+            .unwrap_or_default();
+        let mut state = ListedState::default();
+        let mut result = Vec::new();
+        for filename in self.list_files()? {
+            let Some(ordinal) = file_ordinal(&self.base_dir.join(&filename))? else {
+                continue;
+            };
+            let messages = match prev_files.remove(&filename) {
+                Some(listed_file) if listed_file.ordinal == ordinal => listed_file.messages,
+                _ => Arc::new(self.list_file_messages(&filename, ordinal)?),
+            };
+            let mut keys = Vec::with_capacity(messages.len());
+            for (key, location) in messages.iter() {
+                if let Some((_, existing)) = state.key_to_location.get(key) {
+                    warn!(
+                        "Message {key} exists in both {} and {}, using the former",
+                        existing.filename, location.filename
+                    );
+                    continue;
+                }
+                state
+                    .key_to_location
+                    .insert(key.clone(), (ordinal, location.clone()));
+                keys.push(key.clone());
+            }
+            result.push((ordinal, keys));
+            state
+                .files
+                .insert(filename, ListedFile { ordinal, messages });
+        }
+        *self.listed_state.lock().unwrap() = Some(state);
+        Ok(result)
+    }
+
+    fn lookup(&self, key: &str) -> Option<(Ordinal, MessageLocation)> {
+        self.listed_state
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|state| state.key_to_location.get(key).cloned())
+    }
+
+    /// Get the ordinal and value of a message. Returns `None` if it doesn't exist.
+    fn get_message(&self, key: &str) -> Result<Option<(Ordinal, FieldValues)>> {
+        let mut location = self.lookup(key);
+        // Files may have changed since the last listing. List again in this case.
+        let is_stale = match &location {
+            Some((ordinal, location)) => {
+                file_ordinal(&self.base_dir.join(&location.filename))? != Some(*ordinal)
+            }
+            None => true,
+        };
+        if is_stale {
+            self.list_messages()?;
+            location = self.lookup(key);
+        }
+        let Some((ordinal, location)) = location else {
+            return Ok(None);
+        };
+        let values = match location.mbox_index {
+            None => {
+                let raw_message = match std::fs::read(self.base_dir.join(&location.filename)) {
+                    Ok(raw_message) => raw_message,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                    Err(e) => Err(e)?,
+                };
+                parse_message(&location.filename, &raw_message)?
+            }
+            Some(idx) => {
+                let mbox_file = self.read_mbox(&location.filename, ordinal)?;
+                let Some(raw_message) = mbox_file.messages.get(idx) else {
+                    return Ok(None);
+                };
+                parse_message(&location.filename, raw_message)?
+            }
+        };
+        Ok(Some((ordinal, values)))
+    }
+}
+
+struct Executor {
+    reader: Arc<MailboxReader>,
+}
+
+#[async_trait]
+impl SourceExecutor for Executor {
+    fn list<'a>(
+        &'a self,
+        options: &'a SourceExecutorListOptions,
+    ) -> BoxStream<'a, Result<Vec<PartialSourceRowMetadata>>> {
+        try_stream! {
+            let reader = self.reader.clone();
+            let files = tokio::task::spawn_blocking(move || reader.list_messages()).await??;
+            for (ordinal, keys) in files {
+                yield keys
+                    .into_iter()
+                    .map(|key| PartialSourceRowMetadata {
+                        key: KeyValue::Str(key),
+                        ordinal: options.include_ordinal.then_some(ordinal),
+                    })
+                    .collect();
+            }
+        }
+        .boxed()
+    }
+
+    async fn get_value(
+        &self,
+        key: &KeyValue,
+        options: &SourceExecutorGetOptions,
+    ) -> Result<PartialSourceRowData> {
+        let reader = self.reader.clone();
+        let key = key.str_value()?.clone();
+        let message = tokio::task::spawn_blocking(move || reader.get_message(&key)).await??;
+        let Some((ordinal, values)) = message else {
+            return Ok(PartialSourceRowData {
+                value: Some(SourceValue::NonExistence),
+                ordinal: Some(Ordinal::unavailable()),
+            });
+        };
+        Ok(PartialSourceRowData {
+            value: options
+                .include_value
+                .then_some(SourceValue::Existence(values)),
+            ordinal: options.include_ordinal.then_some(ordinal),
+        })
+    }
+}
+
+pub struct Factory;
+
+impl Factory {
+    fn build_reader(&self, spec: Spec) -> Result<MailboxReader> {
+        let path = PathBuf::from(&spec.path);
+        let (base_dir, single_file) = if path.is_file() {
+            if spec.format != MailboxFormat::Mbox {
+                api_bail!("`path` must be a directory for Maildir");
+            }
+            let file_name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or_else(|| api_error!("Ill-formed file path: {}", path.display()))?
+                .to_string();
+            let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            (base_dir, Some(file_name))
+        } else {
+            (path, None)
+        };
+        Ok(MailboxReader {
+            base_dir,
+            single_file,
+            format: spec.format,
+            pattern_matcher: PatternMatcher::new(spec.included_patterns, spec.excluded_patterns)?,
+            listed_state: Mutex::new(None),
+            mbox_files: Mutex::new(VecDeque::new()),
+        })
+    }
+}
+
+fn nullable_type(typ: BasicValueType) -> EnrichedValueType {
+    EnrichedValueType {
+        nullable: true,
+        ..make_output_type(typ)
+    }
+}
+
+#[async_trait]
+impl SourceFactoryBase for Factory {
+    type Spec = Spec;
+
+    fn name(&self) -> &str {
+        "Mailbox"
+    }
+
+    async fn get_output_schema(
+        &self,
+        _spec: &Spec,
+        _context: &FlowInstanceContext,
+    ) -> Result<EnrichedValueType> {
+        let mut struct_schema = StructSchema::default();
+        let mut schema_builder = StructSchemaBuilder::new(&mut struct_schema);
+        schema_builder.add_field(FieldSchema::new(
+            "message_id",
+            make_output_type(BasicValueType::Str),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "filename",
+            make_output_type(BasicValueType::Str),
+        ));
+        for name in ["from", "to", "cc", "subject"] {
+            schema_builder.add_field(FieldSchema::new(name, nullable_type(BasicValueType::Str)));
+        }
+        schema_builder.add_field(FieldSchema::new(
+            "date",
+            nullable_type(BasicValueType::OffsetDateTime),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "text_body",
+            nullable_type(BasicValueType::Str),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "html_body",
+            nullable_type(BasicValueType::Str),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "attachments",
+            make_output_type(TableSchema::new(
+                TableKind::LTable,
+                StructSchema {
+                    fields: Arc::new(vec![
+                        FieldSchema::new("filename", nullable_type(BasicValueType::Str)),
+                        FieldSchema::new("mime_type", make_output_type(BasicValueType::Str)),
+                        FieldSchema::new("content", make_output_type(BasicValueType::Bytes)),
+                    ]),
+                    description: None,
+                },
+            )),
+        ));
+        Ok(make_output_type(TableSchema::new(
+            TableKind::KTable,
+            struct_schema,
+        )))
+    }
+
+    async fn build_executor(
+        self: Arc<Self>,
+        spec: Spec,
+        _context: Arc<FlowInstanceContext>,
+    ) -> Result<Box<dyn SourceExecutor>> {
+        Ok(Box::new(Executor {
+            reader: Arc::new(self.build_reader(spec)?),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::shared::test_dir::TestDir;
+
+    fn spec(path: &Path, format: MailboxFormat) -> Spec {
+        Spec {
+            path: path.to_string_lossy().to_string(),
+            format,
+            included_patterns: None,
+            excluded_patterns: None,
+        }
+    }
+
+    fn listed_keys(reader: &MailboxReader) -> Vec<String> {
+        let mut keys = reader
+            .list_messages()
+            .unwrap()
+            .iter()
+            .flat_map(|(_, keys)| keys.iter().map(|key| key.to_string()))
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
+
+    const MULTIPART_MESSAGE: &str = "From: Alice <alice@example.com>\r\n\
+To: Bob <bob@example.com>, carol@example.com\r\n\
+Subject: Report\r\n\
+Date: Tue, 1 Jul 2025 10:52:37 +0200\r\n\
+Message-ID: <report@example.com>\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/mixed; boundary=\"b1\"\r\n\
+\r\n\
+--b1\r\n\
+Content-Type: multipart/alternative; boundary=\"b2\"\r\n\
+\r\n\
+--b2\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+\r\n\
+Hello Bob.\r\n\
+--b2\r\n\
+Content-Type: text/html; charset=utf-8\r\n\
+\r\n\
+<p>Hello Bob.</p>\r\n\
+--b2--\r\n\
+--b1\r\n\
+Content-Type: application/octet-stream\r\n\
+Content-Disposition: attachment; filename=\"data.bin\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+AAEC\r\n\
+--b1--\r\n";
+
+    #[test]
+    fn test_maildir() {
+        let dir = TestDir::new("mailbox");
+        for sub_dir in ["cur", "new", "tmp", ".Sent/cur"] {
+            std::fs::create_dir_all(dir.0.join(sub_dir)).unwrap();
+        }
+        std::fs::write(dir.0.join("cur/1.host:2,S"), MULTIPART_MESSAGE).unwrap();
+        std::fs::write(
+            dir.0.join("new/2.host"),
+            "From: bob@example.com\r\nSubject: No ID\r\n\r\nBody\r\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.0.join(".Sent/cur/3.host:2,S"),
+            "Message-ID: <sent@example.com>\r\n\r\nSent\r\n",
+        )
+        .unwrap();
+        std::fs::write(dir.0.join("tmp/4.host"), "Subject: Partial\r\n\r\n").unwrap();
+        // A copy of the same message is only listed once.
+        std::fs::write(dir.0.join("new/5.host"), MULTIPART_MESSAGE).unwrap();
+
+        let reader = Factory
+            .build_reader(spec(&dir.0, MailboxFormat::Maildir))
+            .unwrap();
+        assert_eq!(
+            listed_keys(&reader),
+            vec!["new/2.host", "report@example.com", "sent@example.com"]
+        );
+
+        let (_, values) = reader.get_message("report@example.com").unwrap().unwrap();
+        let date = chrono::DateTime::parse_from_rfc3339("2025-07-01T10:52:37+02:00").unwrap();
+        assert_eq!(
+            values.fields,
+            vec![
+                "cur/1.host:2,S".to_string().into(),
+                "Alice <alice@example.com>".to_string().into(),
+                "Bob <bob@example.com>, carol@example.com"
+                    .to_string()
+                    .into(),
+                Value::Null,
+                "Report".to_string().into(),
+                date.into(),
+                "Hello Bob.".to_string().into(),
+                "<p>Hello Bob.</p>".to_string().into(),
+                Value::LTable(vec![ScopeValue(FieldValues {
+                    fields: vec![
+                        "data.bin".to_string().into(),
+                        "application/octet-stream".to_string().into(),
+                        vec![0u8, 1, 2].into(),
+                    ],
+                })]),
+            ]
+        );
+
+        let (ordinal, values) = reader.get_message("sent@example.com").unwrap().unwrap();
+        assert_eq!(values.fields[0], ".Sent/cur/3.host:2,S".to_string().into());
+        // Moved with a flag added. The modification time is kept, but the ordinal still increases.
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::rename(
+            dir.0.join(".Sent/cur/3.host:2,S"),
+            dir.0.join(".Sent/cur/3.host:2,RS"),
+        )
+        .unwrap();
+        let (new_ordinal, values) = reader.get_message("sent@example.com").unwrap().unwrap();
+        assert_eq!(values.fields[0], ".Sent/cur/3.host:2,RS".to_string().into());
+        assert!(new_ordinal.0 > ordinal.0);
+
+        let (_, values) = reader.get_message("new/2.host").unwrap().unwrap();
+        assert_eq!(values.fields[4], "No ID".to_string().into());
+        assert_eq!(values.fields[8], Value::LTable(vec![]));
+
+        std::fs::remove_file(dir.0.join("cur/1.host:2,S")).unwrap();
+        let (_, values) = reader.get_message("report@example.com").unwrap().unwrap();
+        assert_eq!(values.fields[0], "new/5.host".to_string().into());
+        std::fs::remove_file(dir.0.join("new/5.host")).unwrap();
+        assert!(reader.get_message("report@example.com").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_mbox() {
+        let dir = TestDir::new("mailbox");
+        let mbox_path = dir.0.join("inbox.mbox");
+        std::fs::write(
+            &mbox_path,
+            format!(
+                "From alice@example.com Tue Jul  1 10:52:37 2025\n{}\n\
+From bob@example.com Tue Jul  1 11:00:00 2025\nSubject: Second\n\nNo ID\n",
+                MULTIPART_MESSAGE.replace("\r\n", "\n")
+            ),
+        )
+        .unwrap();
+
+        let reader = Factory
+            .build_reader(spec(&mbox_path, MailboxFormat::Mbox))
+            .unwrap();
+        assert_eq!(
+            listed_keys(&reader),
+            vec!["inbox.mbox#2", "report@example.com"]
+        );
+
+        let (_, values) = reader.get_message("report@example.com").unwrap().unwrap();
+        assert_eq!(values.fields[0], "inbox.mbox".to_string().into());
+        assert_eq!(values.fields[6], "Hello Bob.".to_string().into());
+        let (_, values) = reader.get_message("inbox.mbox#2").unwrap().unwrap();
+        assert_eq!(values.fields[4], "Second".to_string().into());
+        assert!(reader.get_message("missing@example.com").unwrap().is_none());
+    }
+}
@@ -1,3 +1,12 @@
 pub mod amazon_s3;
+pub mod azure_blob;
+pub mod git_repository;
 pub mod google_drive;
+pub mod kafka;
+pub mod local_archive;
 pub mod local_file;
+pub mod mailbox;
+pub mod postgres;
+pub mod sqlite;
+pub mod structured_file;
+pub mod web_crawler;
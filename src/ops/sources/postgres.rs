@@ -0,0 +1,1117 @@
+use crate::ops::sdk::*;
+
+use crate::ops::shared::postgres::get_db_pool;
+use crate::settings::DatabaseConnectionSpec;
+use async_stream::{stream, try_stream};
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use blake2::{Blake2s256, Digest};
+use sqlx::postgres::{PgListener, PgRow};
+use sqlx::{PgPool, Row};
+
+const LIST_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationSpec {
+    /// Name of the channel to LISTEN on. Derived from the table name if not specified.
+    channel_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Spec {
+    database: Option<spec::AuthEntryReference<DatabaseConnectionSpec>>,
+    table_name: String,
+    included_columns: Option<Vec<String>>,
+    ordinal_column: Option<String>,
+    notification: Option<NotificationSpec>,
+}
+
+/// How values of a column are selected and decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    Bool,
+    Int64,
+    Float32,
+    Float64,
+    Str,
+    Bytes,
+    Uuid,
+    Date,
+    Time,
+    LocalDateTime,
+    OffsetDateTime,
+    Json,
+    Vector(Option<usize>),
+}
+
+impl ColumnKind {
+    fn from_pg_type(type_name: &str, formatted_type: &str) -> Self {
+        match type_name {
+            "bool" => ColumnKind::Bool,
+            "int2" | "int4" | "int8" => ColumnKind::Int64,
+            "float4" => ColumnKind::Float32,
+            "float8" => ColumnKind::Float64,
+            "bytea" => ColumnKind::Bytes,
+            "uuid" => ColumnKind::Uuid,
+            "date" => ColumnKind::Date,
+            "time" => ColumnKind::Time,
+            "timestamp" => ColumnKind::LocalDateTime,
+            "timestamptz" => ColumnKind::OffsetDateTime,
+            "json" | "jsonb" => ColumnKind::Json,
+            "vector" => ColumnKind::Vector(
+                formatted_type
+                    .strip_prefix("vector(")
+                    .and_then(|s| s.strip_suffix(')'))
+                    .and_then(|s| s.parse().ok()),
+            ),
+            // Arrays are represented as JSON.
+            t if t.starts_with('_') => ColumnKind::Json,
+            // Everything else (text, varchar, numeric, enums, etc.) is represented by its text form,
+            // e.g. `numeric` values may not fit into a float without losing precision.
+            _ => ColumnKind::Str,
+        }
+    }
+
+    fn basic_value_type(&self) -> BasicValueType {
+        match self {
+            ColumnKind::Bool => BasicValueType::Bool,
+            ColumnKind::Int64 => BasicValueType::Int64,
+            ColumnKind::Float32 => BasicValueType::Float32,
+            ColumnKind::Float64 => BasicValueType::Float64,
+            ColumnKind::Str => BasicValueType::Str,
+            ColumnKind::Bytes => BasicValueType::Bytes,
+            ColumnKind::Uuid => BasicValueType::Uuid,
+            ColumnKind::Date => BasicValueType::Date,
+            ColumnKind::Time => BasicValueType::Time,
+            ColumnKind::LocalDateTime => BasicValueType::LocalDateTime,
+            ColumnKind::OffsetDateTime => BasicValueType::OffsetDateTime,
+            ColumnKind::Json => BasicValueType::Json,
+            ColumnKind::Vector(dimension) => BasicValueType::Vector(VectorTypeSchema {
+                element_type: Box::new(BasicValueType::Float32),
+                dimension: *dimension,
+            }),
+        }
+    }
+
+    fn is_key_compatible(&self) -> bool {
+        matches!(
+            self,
+            ColumnKind::Bool
+                | ColumnKind::Int64
+                | ColumnKind::Str
+                | ColumnKind::Bytes
+                | ColumnKind::Uuid
+                | ColumnKind::Date
+        )
+    }
+
+    /// SQL expression to select the column, casting it to a type we can decode.
+    fn select_expr(&self, quoted_name: &str, pg_type_name: &str) -> String {
+        match self {
+            ColumnKind::Int64 => format!("{quoted_name}::int8"),
+            ColumnKind::Float64 => format!("{quoted_name}::float8"),
+            ColumnKind::Json if pg_type_name.starts_with('_') => {
+                format!("to_jsonb({quoted_name})::text")
+            }
+            ColumnKind::Json | ColumnKind::Str => format!("{quoted_name}::text"),
+            _ => quoted_name.to_string(),
+        }
+    }
+
+    fn decode_value(&self, row: &PgRow, idx: usize) -> Result<Value> {
+        let value = match self {
+            ColumnKind::Bool => row.try_get::<Option<bool>, _>(idx)?.map(BasicValue::Bool),
+            ColumnKind::Int64 => row.try_get::<Option<i64>, _>(idx)?.map(BasicValue::Int64),
+            ColumnKind::Float32 => row.try_get::<Option<f32>, _>(idx)?.map(BasicValue::Float32),
+            ColumnKind::Float64 => row.try_get::<Option<f64>, _>(idx)?.map(BasicValue::Float64),
+            ColumnKind::Str => row
+                .try_get::<Option<String>, _>(idx)?
+                .map(|v| BasicValue::Str(v.into())),
+            ColumnKind::Bytes => row
+                .try_get::<Option<Vec<u8>>, _>(idx)?
+                .map(|v| BasicValue::Bytes(v.into())),
+            ColumnKind::Uuid => row
+                .try_get::<Option<uuid::Uuid>, _>(idx)?
+                .map(BasicValue::Uuid),
+            ColumnKind::Date => row
+                .try_get::<Option<chrono::NaiveDate>, _>(idx)?
+                .map(BasicValue::Date),
+            ColumnKind::Time => row
+                .try_get::<Option<chrono::NaiveTime>, _>(idx)?
+                .map(BasicValue::Time),
+            ColumnKind::LocalDateTime => row
+                .try_get::<Option<chrono::NaiveDateTime>, _>(idx)?
+                .map(BasicValue::LocalDateTime),
+            ColumnKind::OffsetDateTime => row
+                .try_get::<Option<chrono::DateTime<chrono::Utc>>, _>(idx)?
+                .map(|v| BasicValue::OffsetDateTime(v.fixed_offset())),
+            ColumnKind::Json => row
+                .try_get::<Option<String>, _>(idx)?
+                .map(|v| serde_json::from_str(&v))
+                .transpose()?
+                .map(|v| BasicValue::Json(Arc::new(v))),
+            ColumnKind::Vector(_) => row.try_get::<Option<pgvector::Vector>, _>(idx)?.map(|v| {
+                BasicValue::Vector(
+                    v.as_slice()
+                        .iter()
+                        .map(|e| BasicValue::Float32(*e))
+                        .collect(),
+                )
+            }),
+        };
+        Ok(value.map_or(Value::Null, Value::Basic))
+    }
+
+    fn decode_key(&self, row: &PgRow, idx: usize) -> Result<KeyValue> {
+        match self.decode_value(row, idx)? {
+            Value::Basic(v) => v.into_key(),
+            Value::Null => bail!("primary key column at index {idx} is null"),
+            _ => unreachable!(),
+        }
+    }
+
+    /// SQL expression producing the JSON representation of the key column in notification payloads.
+    fn notification_payload_expr(&self, column_ref: &str) -> String {
+        match self {
+            ColumnKind::Bytes => format!("encode({column_ref}, 'base64')"),
+            // Same text form as selected, e.g. `numeric` keys are otherwise sent as JSON numbers.
+            ColumnKind::Str => format!("{column_ref}::text"),
+            _ => column_ref.to_string(),
+        }
+    }
+
+    fn parse_notification_key(&self, value: &serde_json::Value) -> Result<KeyValue> {
+        let key = match (self, value) {
+            (ColumnKind::Bool, serde_json::Value::Bool(v)) => KeyValue::Bool(*v),
+            (ColumnKind::Int64, serde_json::Value::Number(v)) => KeyValue::Int64(
+                v.as_i64()
+                    .ok_or_else(|| anyhow!("invalid integer key: {v}"))?,
+            ),
+            (ColumnKind::Str, serde_json::Value::String(v)) => KeyValue::Str(v.as_str().into()),
+            (ColumnKind::Bytes, serde_json::Value::String(v)) => {
+                KeyValue::Bytes(BASE64_STANDARD.decode(v.replace('\n', ""))?.into())
+            }
+            (ColumnKind::Uuid, serde_json::Value::String(v)) => KeyValue::Uuid(v.parse()?),
+            (ColumnKind::Date, serde_json::Value::String(v)) => KeyValue::Date(v.parse()?),
+            (kind, v) => bail!("unexpected key value for {kind:?} column: {v}"),
+        };
+        Ok(key)
+    }
+}
+
+#[derive(Debug)]
+struct ColumnInfo {
+    name: String,
+    pg_type_name: String,
+    kind: ColumnKind,
+    nullable: bool,
+}
+
+impl ColumnInfo {
+    fn quoted_name(&self) -> String {
+        quote_identifier(&self.name)
+    }
+
+    fn select_expr(&self) -> String {
+        self.kind
+            .select_expr(&self.quoted_name(), &self.pg_type_name)
+    }
+
+    /// Condition matching the key column against the `$n` parameter, bound from the key value.
+    /// Columns of other types mapped to `Str` (e.g. `numeric`, enums) are compared in the same text form as selected,
+    /// as a text parameter can't be compared with them.
+    fn key_match_expr(&self, param_idx: usize) -> String {
+        match self.kind {
+            ColumnKind::Str => format!("{}::text = ${param_idx}", self.quoted_name()),
+            _ => format!("{} = ${param_idx}", self.quoted_name()),
+        }
+    }
+
+    fn field_schema(&self) -> FieldSchema {
+        FieldSchema::new(
+            &self.name,
+            EnrichedValueType {
+                typ: ValueType::Basic(self.kind.basic_value_type()),
+                nullable: self.nullable,
+                attrs: Default::default(),
+            },
+        )
+    }
+}
+
+struct TableSchemaInfo {
+    key_columns: Vec<ColumnInfo>,
+    value_columns: Vec<ColumnInfo>,
+    ordinal_column: Option<ColumnInfo>,
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quotes a possibly schema-qualified table name, e.g. `public.products`.
+fn quote_table_name(table_name: &str) -> String {
+    table_name.split('.').map(quote_identifier).join(".")
+}
+
+/// Postgres truncates identifiers longer than this (in bytes), and rejects such channel names in `pg_notify()`.
+const MAX_IDENTIFIER_LEN: usize = 63;
+
+/// Shortens the name to fit into the identifier length limit, with a hash suffix to keep it distinct.
+fn bounded_identifier(name: String) -> String {
+    if name.len() <= MAX_IDENTIFIER_LEN {
+        return name;
+    }
+    let hash = hex::encode(&Blake2s256::digest(name.as_bytes())[..4]);
+    let mut prefix_len = MAX_IDENTIFIER_LEN - hash.len() - 1;
+    while !name.is_char_boundary(prefix_len) {
+        prefix_len -= 1;
+    }
+    format!("{}_{hash}", &name[..prefix_len])
+}
+
+fn default_channel_name(table_name: &str) -> String {
+    let table_part = table_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    bounded_identifier(format!("{table_part}__cocoindex_changes"))
+}
+
+fn notification_channel(spec: &Spec) -> Result<Option<String>> {
+    let Some(notification) = &spec.notification else {
+        return Ok(None);
+    };
+    let channel = match &notification.channel_name {
+        Some(channel) => {
+            if channel.len() > MAX_IDENTIFIER_LEN {
+                api_bail!("Channel name `{channel}` is longer than {MAX_IDENTIFIER_LEN} bytes");
+            }
+            channel.clone()
+        }
+        None => default_channel_name(&spec.table_name),
+    };
+    Ok(Some(channel))
+}
+
+async fn fetch_table_schema(pool: &PgPool, spec: &Spec) -> Result<TableSchemaInfo> {
+    let quoted_table_name = quote_table_name(&spec.table_name);
+    let columns = sqlx::query(
+        "SELECT a.attname::text, t.typname::text, format_type(a.atttypid, a.atttypmod), a.attnotnull \
+         FROM pg_attribute a JOIN pg_type t ON a.atttypid = t.oid \
+         WHERE a.attrelid = $1::regclass AND a.attnum > 0 AND NOT a.attisdropped \
+         ORDER BY a.attnum",
+    )
+    .bind(&quoted_table_name)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| -> Result<ColumnInfo> {
+        let pg_type_name: String = row.try_get(1)?;
+        let formatted_type: String = row.try_get(2)?;
+        let not_null: bool = row.try_get(3)?;
+        Ok(ColumnInfo {
+            name: row.try_get(0)?,
+            kind: ColumnKind::from_pg_type(&pg_type_name, &formatted_type),
+            pg_type_name,
+            nullable: !not_null,
+        })
+    })
+    .collect::<Result<Vec<_>>>()?;
+    if columns.is_empty() {
+        api_bail!("Table `{}` has no columns", spec.table_name);
+    }
+
+    let key_column_names: Vec<String> = sqlx::query_scalar(
+        "SELECT a.attname::text \
+         FROM pg_index i JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey) \
+         WHERE i.indrelid = $1::regclass AND i.indisprimary \
+         ORDER BY array_position(i.indkey::int2[], a.attnum)",
+    )
+    .bind(&quoted_table_name)
+    .fetch_all(pool)
+    .await?;
+    if key_column_names.is_empty() {
+        api_bail!("Table `{}` has no primary key", spec.table_name);
+    }
+
+    let mut columns_by_name: IndexMap<String, ColumnInfo> = columns
+        .into_iter()
+        .map(|column| (column.name.clone(), column))
+        .collect();
+    let key_columns = key_column_names
+        .iter()
+        .map(|name| {
+            let column = columns_by_name
+                .shift_remove(name)
+                .ok_or_else(|| anyhow!("primary key column `{name}` not found"))?;
+            if !column.kind.is_key_compatible() {
+                api_bail!(
+                    "Primary key column `{}` has unsupported type `{}`",
+                    column.name,
+                    column.pg_type_name
+                );
+            }
+            Ok(column)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let ordinal_column = spec
+        .ordinal_column
+        .as_ref()
+        .map(|name| -> Result<ColumnInfo> {
+            let column = columns_by_name
+                .get(name)
+                .or_else(|| key_columns.iter().find(|c| &c.name == name))
+                .ok_or_else(|| api_error!("Ordinal column `{name}` not found"))?;
+            match column.kind {
+                ColumnKind::Int64 | ColumnKind::LocalDateTime | ColumnKind::OffsetDateTime => {}
+                _ => api_bail!(
+                    "Ordinal column `{name}` must be an integer or timestamp column, got `{}`",
+                    column.pg_type_name
+                ),
+            }
+            Ok(ColumnInfo {
+                name: column.name.clone(),
+                pg_type_name: column.pg_type_name.clone(),
+                kind: column.kind,
+                nullable: column.nullable,
+            })
+        })
+        .transpose()?;
+
+    let value_columns = match &spec.included_columns {
+        Some(included_columns) => included_columns
+            .iter()
+            .map(|name| {
+                columns_by_name.shift_remove(name).ok_or_else(|| {
+                    api_error!("Column `{name}` not found or is part of the primary key")
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => columns_by_name.into_values().collect(),
+    };
+
+    Ok(TableSchemaInfo {
+        key_columns,
+        value_columns,
+        ordinal_column,
+    })
+}
+
+struct Executor {
+    db_pool: PgPool,
+    table_name: String,
+    schema: TableSchemaInfo,
+    notification_channel: Option<String>,
+}
+
+impl Executor {
+    /// SQL expression converting the ordinal column into microseconds (for timestamps) or the raw integer.
+    fn ordinal_select_expr(&self) -> Option<String> {
+        self.schema.ordinal_column.as_ref().map(|c| match c.kind {
+            ColumnKind::LocalDateTime | ColumnKind::OffsetDateTime => {
+                format!("(EXTRACT(EPOCH FROM {}) * 1000000)::int8", c.quoted_name())
+            }
+            _ => format!("{}::int8", c.quoted_name()),
+        })
+    }
+
+    fn decode_key(&self, row: &PgRow) -> Result<KeyValue> {
+        if let [column] = self.schema.key_columns.as_slice() {
+            column.kind.decode_key(row, 0)
+        } else {
+            Ok(KeyValue::Struct(
+                self.schema
+                    .key_columns
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, column)| column.kind.decode_key(row, idx))
+                    .collect::<Result<Vec<_>>>()?,
+            ))
+        }
+    }
+
+    fn key_parts<'a>(&self, key: &'a KeyValue) -> Result<&'a [KeyValue]> {
+        if self.schema.key_columns.len() == 1 {
+            Ok(std::slice::from_ref(key))
+        } else {
+            match key {
+                KeyValue::Struct(parts) if parts.len() == self.schema.key_columns.len() => {
+                    Ok(parts)
+                }
+                _ => bail!(
+                    "expect struct key value with {} fields",
+                    self.schema.key_columns.len()
+                ),
+            }
+        }
+    }
+
+    fn parse_notification_payload(&self, payload: &str) -> Result<KeyValue> {
+        let payload: serde_json::Map<String, serde_json::Value> = serde_json::from_str(payload)?;
+        let mut parts = self
+            .schema
+            .key_columns
+            .iter()
+            .map(|column| {
+                let value = payload.get(&column.name).ok_or_else(|| {
+                    anyhow!("key column `{}` missing in notification", column.name)
+                })?;
+                column.kind.parse_notification_key(value)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(if parts.len() == 1 {
+            parts.pop().unwrap()
+        } else {
+            KeyValue::Struct(parts)
+        })
+    }
+}
+
+#[async_trait]
+impl SourceExecutor for Executor {
+    fn list<'a>(
+        &'a self,
+        options: &'a SourceExecutorListOptions,
+    ) -> BoxStream<'a, Result<Vec<PartialSourceRowMetadata>>> {
+        let ordinal_expr = if options.include_ordinal {
+            self.ordinal_select_expr()
+        } else {
+            None
+        };
+        let num_key_columns = self.schema.key_columns.len();
+        let select_exprs = self
+            .schema
+            .key_columns
+            .iter()
+            .map(|c| c.select_expr())
+            .chain(ordinal_expr.iter().cloned())
+            .join(", ");
+        let sql = format!(
+            "SELECT {select_exprs} FROM {}",
+            quote_table_name(&self.table_name)
+        );
+        try_stream! {
+            let mut rows = sqlx::query(&sql).fetch(&self.db_pool);
+            let mut batch = Vec::new();
+            while let Some(row) = rows.try_next().await? {
+                let ordinal = if options.include_ordinal {
+                    Some(match ordinal_expr {
+                        Some(_) => Ordinal(row.try_get::<Option<i64>, _>(num_key_columns)?),
+                        None => Ordinal::unavailable(),
+                    })
+                } else {
+                    None
+                };
+                batch.push(PartialSourceRowMetadata {
+                    key: self.decode_key(&row)?,
+                    ordinal,
+                });
+                if batch.len() >= LIST_BATCH_SIZE {
+                    yield std::mem::take(&mut batch);
+                }
+            }
+            if !batch.is_empty() {
+                yield batch;
+            }
+        }
+        .boxed()
+    }
+
+    async fn get_value(
+        &self,
+        key: &KeyValue,
+        options: &SourceExecutorGetOptions,
+    ) -> Result<PartialSourceRowData> {
+        let ordinal_expr = if options.include_ordinal {
+            self.ordinal_select_expr()
+        } else {
+            None
+        };
+        let value_exprs = if options.include_value {
+            self.schema
+                .value_columns
+                .iter()
+                .map(|c| c.select_expr())
+                .collect()
+        } else {
+            vec![]
+        };
+        let num_value_columns = value_exprs.len();
+        // Always select something, so the existence of the row can be told.
+        let select_exprs = value_exprs
+            .into_iter()
+            .chain(ordinal_expr.iter().cloned())
+            .chain(std::iter::once("1".to_string()))
+            .join(", ");
+        let where_clause = self
+            .schema
+            .key_columns
+            .iter()
+            .enumerate()
+            .map(|(idx, c)| c.key_match_expr(idx + 1))
+            .join(" AND ");
+        let sql = format!(
+            "SELECT {select_exprs} FROM {} WHERE {where_clause}",
+            quote_table_name(&self.table_name)
+        );
+        let mut query = sqlx::query(&sql);
+        for part in self.key_parts(key)? {
+            query = match part {
+                KeyValue::Bytes(v) => query.bind(v.as_ref()),
+                KeyValue::Str(v) => query.bind(v.as_ref()),
+                KeyValue::Bool(v) => query.bind(v),
+                KeyValue::Int64(v) => query.bind(v),
+                KeyValue::Uuid(v) => query.bind(v),
+                KeyValue::Date(v) => query.bind(v),
+                KeyValue::Range(_) | KeyValue::Struct(_) => {
+                    bail!("unsupported key part: {part}")
+                }
+            };
+        }
+        let row = match query.fetch_optional(&self.db_pool).await? {
+            Some(row) => row,
+            None => {
+                return Ok(PartialSourceRowData {
+                    value: Some(SourceValue::NonExistence),
+                    ordinal: Some(Ordinal::unavailable()),
+                });
+            }
+        };
+        let value = if options.include_value {
+            let fields = self
+                .schema
+                .value_columns
+                .iter()
+                .enumerate()
+                .map(|(idx, c)| c.kind.decode_value(&row, idx))
+                .collect::<Result<Vec<_>>>()?;
+            Some(SourceValue::Existence(FieldValues { fields }))
+        } else {
+            None
+        };
+        let ordinal = if options.include_ordinal {
+            Some(match ordinal_expr {
+                Some(_) => Ordinal(row.try_get::<Option<i64>, _>(num_value_columns)?),
+                None => Ordinal::unavailable(),
+            })
+        } else {
+            None
+        };
+        Ok(PartialSourceRowData { value, ordinal })
+    }
+
+    async fn change_stream(
+        &self,
+    ) -> Result<Option<BoxStream<'async_trait, Result<SourceChangeMessage>>>> {
+        let channel = if let Some(channel) = &self.notification_channel {
+            channel
+        } else {
+            return Ok(None);
+        };
+        let mut listener = PgListener::connect_with(&self.db_pool).await?;
+        listener.listen(channel).await?;
+        let stream = stream! {
+            loop {
+                let notification = match listener.recv().await {
+                    Ok(notification) => notification,
+                    Err(e) => {
+                        // The listener reconnects on the next `recv()`.
+                        yield Err(e.into());
+                        continue;
+                    }
+                };
+                let key = match self.parse_notification_payload(notification.payload()) {
+                    Ok(key) => key,
+                    Err(e) => {
+                        warn!(
+                            "Skipped ill-formed notification on channel `{channel}`: {e:?}, payload: {}",
+                            notification.payload()
+                        );
+                        continue;
+                    }
+                };
+                yield Ok(SourceChangeMessage {
+                    changes: vec![SourceChange { key, data: None }],
+                    ack_fn: None,
+                });
+            }
+        };
+        Ok(Some(stream.boxed()))
+    }
+}
+
+pub struct Factory;
+
+#[async_trait]
+impl SourceFactoryBase for Factory {
+    type Spec = Spec;
+
+    fn name(&self) -> &str {
+        "Postgres"
+    }
+
+    async fn get_output_schema(
+        &self,
+        spec: &Spec,
+        context: &FlowInstanceContext,
+    ) -> Result<EnrichedValueType> {
+        let db_pool = get_db_pool(spec.database.as_ref(), &context.auth_registry).await?;
+        let schema = fetch_table_schema(&db_pool, spec).await?;
+
+        let mut struct_schema = StructSchema::default();
+        let mut schema_builder = StructSchemaBuilder::new(&mut struct_schema);
+        if let [key_column] = schema.key_columns.as_slice() {
+            schema_builder.add_field(key_column.field_schema());
+        } else {
+            schema_builder.add_field(FieldSchema::new(
+                "_key",
+                make_output_type(StructSchema {
+                    fields: Arc::new(
+                        schema
+                            .key_columns
+                            .iter()
+                            .map(|c| c.field_schema())
+                            .collect(),
+                    ),
+                    description: None,
+                }),
+            ));
+        }
+        for column in schema.value_columns.iter() {
+            schema_builder.add_field(column.field_schema());
+        }
+        Ok(make_output_type(TableSchema::new(
+            TableKind::KTable,
+            struct_schema,
+        )))
+    }
+
+    async fn get_setup_declarations(
+        &self,
+        spec: &Spec,
+        context: &FlowInstanceContext,
+    ) -> Result<Vec<OpSpec>> {
+        let Some(channel) = notification_channel(spec)? else {
+            return Ok(vec![]);
+        };
+        let db_pool = get_db_pool(spec.database.as_ref(), &context.auth_registry).await?;
+        let schema = fetch_table_schema(&db_pool, spec).await?;
+        let declaration = NotificationTriggerDeclaration {
+            key: NotificationTriggerKey {
+                database: spec.database.clone(),
+                table_name: spec.table_name.clone(),
+                channel_name: channel.clone(),
+            },
+            state: NotificationTriggerSetupState {
+                function_name: bounded_identifier(format!("{channel}_fn")),
+                trigger_name: bounded_identifier(format!("{channel}_trigger")),
+                function_body: notification_function_body(&schema.key_columns, &channel),
+            },
+        };
+        let serde_json::Value::Object(spec) = serde_json::to_value(declaration)? else {
+            unreachable!()
+        };
+        Ok(vec![OpSpec {
+            kind: NOTIFICATION_TRIGGER_KIND.to_string(),
+            spec,
+        }])
+    }
+
+    async fn build_executor(
+        self: Arc<Self>,
+        spec: Spec,
+        context: Arc<FlowInstanceContext>,
+    ) -> Result<Box<dyn SourceExecutor>> {
+        let db_pool = get_db_pool(spec.database.as_ref(), &context.auth_registry).await?;
+        let schema = fetch_table_schema(&db_pool, &spec).await?;
+        let notification_channel = notification_channel(&spec)?;
+        Ok(Box::new(Executor {
+            db_pool,
+            table_name: spec.table_name,
+            schema,
+            notification_channel,
+        }))
+    }
+}
+
+////////////////////////////////////////////////////////////
+// Setup of the notification trigger
+////////////////////////////////////////////////////////////
+
+const NOTIFICATION_TRIGGER_KIND: &str = "PostgresNotificationTrigger";
+
+/// Body of the trigger function, which sends the primary key of each changed row to the channel.
+/// For updates changing the primary key, the old key is also sent.
+fn notification_function_body(key_columns: &[ColumnInfo], channel: &str) -> String {
+    let payload_expr = |record: &str| {
+        let fields = key_columns
+            .iter()
+            .map(|c| {
+                format!(
+                    "'{}', {}",
+                    c.name.replace('\'', "''"),
+                    c.kind
+                        .notification_payload_expr(&format!("{record}.{}", c.quoted_name()))
+                )
+            })
+            .join(", ");
+        format!("json_build_object({fields})::text")
+    };
+    let key_changed_cond = key_columns
+        .iter()
+        .map(|c| format!("OLD.{0} IS DISTINCT FROM NEW.{0}", c.quoted_name()))
+        .join(" OR ");
+    let channel_literal = channel.replace('\'', "''");
+    format!(
+        "
+BEGIN
+  IF TG_OP = 'INSERT' OR TG_OP = 'UPDATE' THEN
+    PERFORM pg_notify('{channel_literal}', {new_payload});
+  END IF;
+  IF TG_OP = 'DELETE' OR (TG_OP = 'UPDATE' AND ({key_changed_cond})) THEN
+    PERFORM pg_notify('{channel_literal}', {old_payload});
+  END IF;
+  RETURN NULL;
+END;
+",
+        new_payload = payload_expr("NEW"),
+        old_payload = payload_expr("OLD"),
+    )
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct NotificationTriggerKey {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    database: Option<spec::AuthEntryReference<DatabaseConnectionSpec>>,
+    table_name: String,
+    channel_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NotificationTriggerSetupState {
+    function_name: String,
+    trigger_name: String,
+    function_body: String,
+}
+
+/// Declared by the `Postgres` source when notification is enabled.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotificationTriggerDeclaration {
+    key: NotificationTriggerKey,
+    state: NotificationTriggerSetupState,
+}
+
+#[derive(Debug)]
+pub struct NotificationTriggerSetupStatus {
+    /// Function and trigger names of existing versions to drop.
+    objects_to_drop: IndexSet<(String, String)>,
+    trigger_to_create: Option<NotificationTriggerSetupState>,
+}
+
+impl setup::ResourceSetupStatus for NotificationTriggerSetupStatus {
+    fn describe_changes(&self) -> Vec<String> {
+        let mut result = vec![];
+        for (function_name, trigger_name) in self.objects_to_drop.iter() {
+            result.push(format!(
+                "Drop trigger `{trigger_name}` and function `{function_name}`"
+            ));
+        }
+        if let Some(state) = &self.trigger_to_create {
+            result.push(format!(
+                "Create trigger `{}` with function `{}`",
+                state.trigger_name, state.function_name
+            ));
+        }
+        result
+    }
+
+    fn change_type(&self) -> setup::SetupChangeType {
+        match (
+            self.objects_to_drop.is_empty(),
+            self.trigger_to_create.is_some(),
+        ) {
+            (true, false) => setup::SetupChangeType::NoChange,
+            (true, true) => setup::SetupChangeType::Create,
+            (false, false) => setup::SetupChangeType::Delete,
+            (false, true) => setup::SetupChangeType::Update,
+        }
+    }
+}
+
+impl NotificationTriggerSetupStatus {
+    async fn apply(&self, key: &NotificationTriggerKey, db_pool: &PgPool) -> Result<()> {
+        let table_name = quote_table_name(&key.table_name);
+        let mut txn = db_pool.begin().await?;
+        for (function_name, trigger_name) in self.objects_to_drop.iter() {
+            sqlx::query(&format!(
+                "DROP TRIGGER IF EXISTS {} ON {table_name}",
+                quote_identifier(trigger_name)
+            ))
+            .execute(&mut *txn)
+            .await?;
+            sqlx::query(&format!(
+                "DROP FUNCTION IF EXISTS {}()",
+                quote_identifier(function_name)
+            ))
+            .execute(&mut *txn)
+            .await?;
+        }
+        if let Some(state) = &self.trigger_to_create {
+            let function_name = quote_identifier(&state.function_name);
+            let trigger_name = quote_identifier(&state.trigger_name);
+            sqlx::query(&format!(
+                "CREATE OR REPLACE FUNCTION {function_name}() RETURNS TRIGGER AS $${}$$ LANGUAGE plpgsql",
+                state.function_body
+            ))
+            .execute(&mut *txn)
+            .await?;
+            // `CREATE OR REPLACE TRIGGER` is only available since Postgres 14.
+            sqlx::query(&format!(
+                "DROP TRIGGER IF EXISTS {trigger_name} ON {table_name}"
+            ))
+            .execute(&mut *txn)
+            .await?;
+            sqlx::query(&format!(
+                "CREATE TRIGGER {trigger_name} \
+                 AFTER INSERT OR UPDATE OR DELETE ON {table_name} \
+                 FOR EACH ROW EXECUTE FUNCTION {function_name}()"
+            ))
+            .execute(&mut *txn)
+            .await?;
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+}
+
+/// Manages the trigger (and its function) sending notifications for changes of the table.
+/// It's a setup-only target: it's never exported to, and only used through declarations of the `Postgres` source.
+pub struct NotificationTriggerFactory;
+
+#[async_trait]
+impl StorageFactoryBase for NotificationTriggerFactory {
+    type Spec = serde_json::Value;
+    type DeclarationSpec = NotificationTriggerDeclaration;
+    type Key = NotificationTriggerKey;
+    type SetupState = NotificationTriggerSetupState;
+    type SetupStatus = NotificationTriggerSetupStatus;
+    type ExportContext = ();
+
+    fn name(&self) -> &str {
+        NOTIFICATION_TRIGGER_KIND
+    }
+
+    async fn build(
+        self: Arc<Self>,
+        data_collections: Vec<TypedExportDataCollectionSpec<Self>>,
+        declarations: Vec<NotificationTriggerDeclaration>,
+        _context: Arc<FlowInstanceContext>,
+    ) -> Result<(
+        Vec<TypedExportDataCollectionBuildOutput<Self>>,
+        Vec<(NotificationTriggerKey, NotificationTriggerSetupState)>,
+    )> {
+        if !data_collections.is_empty() {
+            api_bail!("`{NOTIFICATION_TRIGGER_KIND}` cannot be used as an export target");
+        }
+        Ok((
+            vec![],
+            declarations.into_iter().map(|d| (d.key, d.state)).collect(),
+        ))
+    }
+
+    async fn check_setup_status(
+        &self,
+        _key: NotificationTriggerKey,
+        desired: Option<NotificationTriggerSetupState>,
+        existing: setup::CombinedState<NotificationTriggerSetupState>,
+        _auth_registry: &Arc<AuthRegistry>,
+    ) -> Result<NotificationTriggerSetupStatus> {
+        let objects_to_drop = existing
+            .possible_versions()
+            .filter(|v| {
+                desired.as_ref().is_none_or(|desired| {
+                    desired.function_name != v.function_name
+                        || desired.trigger_name != v.trigger_name
+                })
+            })
+            .map(|v| (v.function_name.clone(), v.trigger_name.clone()))
+            .collect();
+        let trigger_to_create = desired.filter(|desired| {
+            !existing.always_exists() || existing.possible_versions().any(|v| v != desired)
+        });
+        Ok(NotificationTriggerSetupStatus {
+            objects_to_drop,
+            trigger_to_create,
+        })
+    }
+
+    fn check_state_compatibility(
+        &self,
+        _desired: &NotificationTriggerSetupState,
+        _existing: &NotificationTriggerSetupState,
+    ) -> Result<SetupStateCompatibility> {
+        // The trigger holds no data, so it can always be recreated.
+        Ok(SetupStateCompatibility::Compatible)
+    }
+
+    fn describe_resource(&self, key: &NotificationTriggerKey) -> Result<String> {
+        Ok(format!(
+            "Postgres notification trigger on table {} (channel: {}){}",
+            key.table_name,
+            key.channel_name,
+            key.database
+                .as_ref()
+                .map_or_else(|| "".to_string(), |database| format!(" @ {database}"))
+        ))
+    }
+
+    async fn apply_mutation(
+        &self,
+        _mutations: Vec<ExportTargetMutationWithContext<'async_trait, ()>>,
+    ) -> Result<()> {
+        bail!("`{NOTIFICATION_TRIGGER_KIND}` cannot be used as an export target")
+    }
+
+    async fn apply_setup_changes(
+        &self,
+        setup_status: Vec<TypedResourceSetupChangeItem<'async_trait, Self>>,
+        auth_registry: &Arc<AuthRegistry>,
+    ) -> Result<()> {
+        for setup_change in setup_status.iter() {
+            let db_pool = get_db_pool(setup_change.key.database.as_ref(), auth_registry).await?;
+            setup_change
+                .setup_status
+                .apply(&setup_change.key, &db_pool)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column_kind_from_pg_type() {
+        assert_eq!(
+            ColumnKind::from_pg_type("int4", "integer"),
+            ColumnKind::Int64
+        );
+        assert_eq!(
+            ColumnKind::from_pg_type("varchar", "character varying(10)"),
+            ColumnKind::Str
+        );
+        assert_eq!(
+            ColumnKind::from_pg_type("numeric", "numeric(10,2)"),
+            ColumnKind::Str
+        );
+        assert_eq!(
+            ColumnKind::from_pg_type("_text", "text[]"),
+            ColumnKind::Json
+        );
+        assert_eq!(
+            ColumnKind::from_pg_type("vector", "vector(384)"),
+            ColumnKind::Vector(Some(384))
+        );
+        assert_eq!(
+            ColumnKind::from_pg_type("vector", "vector"),
+            ColumnKind::Vector(None)
+        );
+    }
+
+    #[test]
+    fn test_parse_notification_key() {
+        assert_eq!(
+            ColumnKind::Int64
+                .parse_notification_key(&serde_json::json!(42))
+                .unwrap(),
+            KeyValue::Int64(42)
+        );
+        assert_eq!(
+            ColumnKind::Bytes
+                .parse_notification_key(&serde_json::json!("aGVsbG8="))
+                .unwrap(),
+            KeyValue::Bytes(b"hello".to_vec().into())
+        );
+        assert!(
+            ColumnKind::Int64
+                .parse_notification_key(&serde_json::json!("42"))
+                .is_err()
+        );
+        assert!(
+            ColumnKind::Str
+                .parse_notification_key(&serde_json::json!(1.5))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_notification_function_body() {
+        let key_columns = [
+            ColumnInfo {
+                name: "id".to_string(),
+                pg_type_name: "numeric".to_string(),
+                kind: ColumnKind::Str,
+                nullable: false,
+            },
+            ColumnInfo {
+                name: "data".to_string(),
+                pg_type_name: "bytea".to_string(),
+                kind: ColumnKind::Bytes,
+                nullable: false,
+            },
+        ];
+        let body = notification_function_body(&key_columns, "items_changes");
+        assert!(body.contains(
+            "json_build_object('id', NEW.\"id\"::text, 'data', encode(NEW.\"data\", 'base64'))::text"
+        ));
+    }
+
+    #[test]
+    fn test_key_match_expr() {
+        let column = |name: &str, pg_type_name: &str, type_str: &str| ColumnInfo {
+            name: name.to_string(),
+            pg_type_name: pg_type_name.to_string(),
+            kind: ColumnKind::from_pg_type(pg_type_name, type_str),
+            nullable: false,
+        };
+        assert_eq!(
+            column("id", "numeric", "numeric(10,0)").key_match_expr(1),
+            "\"id\"::text = $1"
+        );
+        assert_eq!(
+            column("id", "int4", "integer").key_match_expr(2),
+            "\"id\" = $2"
+        );
+        assert_eq!(
+            column("id", "uuid", "uuid").key_match_expr(1),
+            "\"id\" = $1"
+        );
+    }
+
+    #[test]
+    fn test_quote_table_name() {
+        assert_eq!(quote_table_name("public.items"), "\"public\".\"items\"");
+        assert_eq!(quote_table_name("we\"ird"), "\"we\"\"ird\"");
+    }
+
+    #[test]
+    fn test_bounded_identifier() {
+        assert_eq!(
+            default_channel_name("public.items"),
+            "public_items__cocoindex_changes"
+        );
+        let long_name = format!("{}_trigger", "t".repeat(60));
+        let bounded = bounded_identifier(long_name.clone());
+        assert_eq!(bounded.len(), MAX_IDENTIFIER_LEN);
+        assert!(bounded.starts_with("tttt"));
+        assert_ne!(
+            bounded,
+            bounded_identifier(format!("{}_fn", "t".repeat(60)))
+        );
+        assert_eq!(bounded, bounded_identifier(long_name));
+    }
+}
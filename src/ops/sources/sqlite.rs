@@ -0,0 +1,693 @@
+use crate::ops::sdk::*;
+
+use async_stream::{stream, try_stream};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::{Column, ConnectOptions, Executor as _, Row, TypeInfo};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const LIST_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct Spec {
+    path: String,
+    table_name: Option<String>,
+    query: Option<String>,
+    key_column: String,
+    included_columns: Option<Vec<String>>,
+    ordinal_column: Option<String>,
+    change_poll_interval: Option<Duration>,
+}
+
+/// How values of a column are selected and decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    Bool,
+    Int64,
+    Float64,
+    Str,
+    Bytes,
+    Date,
+    Time,
+    LocalDateTime,
+}
+
+impl ColumnKind {
+    /// `type_name` is the type reported by SQLite for the column, based on its declared type affinity.
+    fn from_sqlite_type(type_name: &str) -> Self {
+        match type_name.to_ascii_uppercase().as_str() {
+            "BOOLEAN" => ColumnKind::Bool,
+            "INTEGER" | "INT8" | "BIGINT" => ColumnKind::Int64,
+            "REAL" | "NUMERIC" => ColumnKind::Float64,
+            "BLOB" => ColumnKind::Bytes,
+            "DATE" => ColumnKind::Date,
+            "TIME" => ColumnKind::Time,
+            "DATETIME" => ColumnKind::LocalDateTime,
+            // Everything else (text, or values without a declared type) is represented by its text form.
+            _ => ColumnKind::Str,
+        }
+    }
+
+    fn basic_value_type(&self) -> BasicValueType {
+        match self {
+            ColumnKind::Bool => BasicValueType::Bool,
+            ColumnKind::Int64 => BasicValueType::Int64,
+            ColumnKind::Float64 => BasicValueType::Float64,
+            ColumnKind::Str => BasicValueType::Str,
+            ColumnKind::Bytes => BasicValueType::Bytes,
+            ColumnKind::Date => BasicValueType::Date,
+            ColumnKind::Time => BasicValueType::Time,
+            ColumnKind::LocalDateTime => BasicValueType::LocalDateTime,
+        }
+    }
+
+    fn is_key_compatible(&self) -> bool {
+        matches!(
+            self,
+            ColumnKind::Bool
+                | ColumnKind::Int64
+                | ColumnKind::Str
+                | ColumnKind::Bytes
+                | ColumnKind::Date
+        )
+    }
+
+    /// SQL expression to select the column. Values are casted as SQLite columns may hold values of any type.
+    fn select_expr(&self, quoted_name: &str) -> String {
+        match self {
+            ColumnKind::Int64 => format!("CAST({quoted_name} AS INTEGER)"),
+            ColumnKind::Float64 => format!("CAST({quoted_name} AS REAL)"),
+            ColumnKind::Str => format!("CAST({quoted_name} AS TEXT)"),
+            _ => quoted_name.to_string(),
+        }
+    }
+
+    fn decode_value(&self, row: &SqliteRow, idx: usize) -> Result<Value> {
+        let value = match self {
+            ColumnKind::Bool => row.try_get::<Option<bool>, _>(idx)?.map(BasicValue::Bool),
+            ColumnKind::Int64 => row.try_get::<Option<i64>, _>(idx)?.map(BasicValue::Int64),
+            ColumnKind::Float64 => row.try_get::<Option<f64>, _>(idx)?.map(BasicValue::Float64),
+            ColumnKind::Str => row
+                .try_get::<Option<String>, _>(idx)?
+                .map(|v| BasicValue::Str(v.into())),
+            ColumnKind::Bytes => row
+                .try_get::<Option<Vec<u8>>, _>(idx)?
+                .map(|v| BasicValue::Bytes(v.into())),
+            ColumnKind::Date => row
+                .try_get::<Option<chrono::NaiveDate>, _>(idx)?
+                .map(BasicValue::Date),
+            ColumnKind::Time => row
+                .try_get::<Option<chrono::NaiveTime>, _>(idx)?
+                .map(BasicValue::Time),
+            ColumnKind::LocalDateTime => row
+                .try_get::<Option<chrono::NaiveDateTime>, _>(idx)?
+                .map(BasicValue::LocalDateTime),
+        };
+        Ok(value.map_or(Value::Null, Value::Basic))
+    }
+
+    fn decode_key(&self, row: &SqliteRow, idx: usize) -> Result<KeyValue> {
+        match self.decode_value(row, idx)? {
+            Value::Basic(v) => v.into_key(),
+            Value::Null => bail!("key column at index {idx} is null"),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ColumnInfo {
+    name: String,
+    type_name: String,
+    kind: ColumnKind,
+    nullable: bool,
+}
+
+impl ColumnInfo {
+    fn quoted_name(&self) -> String {
+        quote_identifier(&self.name)
+    }
+
+    fn select_expr(&self) -> String {
+        self.kind.select_expr(&self.quoted_name())
+    }
+
+    fn field_schema(&self) -> FieldSchema {
+        FieldSchema::new(
+            &self.name,
+            EnrichedValueType {
+                typ: ValueType::Basic(self.kind.basic_value_type()),
+                nullable: self.nullable,
+                attrs: Default::default(),
+            },
+        )
+    }
+}
+
+struct RowsSchemaInfo {
+    key_column: ColumnInfo,
+    value_columns: Vec<ColumnInfo>,
+    ordinal_column: Option<ColumnInfo>,
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// The relation to select rows from: either the table or the query as a subquery.
+fn source_relation(spec: &Spec) -> Result<String> {
+    match (&spec.table_name, &spec.query) {
+        (Some(table_name), None) => Ok(quote_identifier(table_name)),
+        (None, Some(query)) => Ok(format!("({})", query.trim().trim_end_matches(';'))),
+        _ => api_bail!("Exactly one of `table_name` and `query` must be specified"),
+    }
+}
+
+fn connect_options(spec: &Spec) -> SqliteConnectOptions {
+    SqliteConnectOptions::new()
+        .filename(&spec.path)
+        .read_only(true)
+}
+
+async fn fetch_rows_schema(
+    pool: &SqlitePool,
+    relation: &str,
+    spec: &Spec,
+) -> Result<RowsSchemaInfo> {
+    let describe = pool.describe(&format!("SELECT * FROM {relation}")).await?;
+    let mut columns_by_name: IndexMap<String, ColumnInfo> = describe
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(idx, column)| {
+            let type_name = column.type_info().name().to_string();
+            let info = ColumnInfo {
+                name: column.name().to_string(),
+                kind: ColumnKind::from_sqlite_type(&type_name),
+                type_name,
+                nullable: describe.nullable(idx).unwrap_or(true),
+            };
+            (info.name.clone(), info)
+        })
+        .collect();
+
+    let mut key_column = columns_by_name
+        .shift_remove(&spec.key_column)
+        .ok_or_else(|| api_error!("Key column `{}` not found", spec.key_column))?;
+    if !key_column.kind.is_key_compatible() {
+        api_bail!(
+            "Key column `{}` has unsupported type `{}`",
+            key_column.name,
+            key_column.type_name
+        );
+    }
+    key_column.nullable = false;
+
+    let ordinal_column = spec
+        .ordinal_column
+        .as_ref()
+        .map(|name| -> Result<ColumnInfo> {
+            let column = columns_by_name
+                .get(name)
+                .or_else(|| (&key_column.name == name).then_some(&key_column))
+                .ok_or_else(|| api_error!("Ordinal column `{name}` not found"))?;
+            match column.kind {
+                ColumnKind::Int64 | ColumnKind::Date | ColumnKind::LocalDateTime => {}
+                _ => api_bail!(
+                    "Ordinal column `{name}` must be an integer, date or datetime column, got `{}`",
+                    column.type_name
+                ),
+            }
+            Ok(column.clone())
+        })
+        .transpose()?;
+
+    let value_columns = match &spec.included_columns {
+        Some(included_columns) => included_columns
+            .iter()
+            .map(|name| {
+                columns_by_name
+                    .shift_remove(name)
+                    .ok_or_else(|| api_error!("Column `{name}` not found or is the key column"))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => columns_by_name.into_values().collect(),
+    };
+
+    Ok(RowsSchemaInfo {
+        key_column,
+        value_columns,
+        ordinal_column,
+    })
+}
+
+struct Executor {
+    pool: SqlitePool,
+    connect_options: SqliteConnectOptions,
+    relation: String,
+    schema: RowsSchemaInfo,
+    change_poll_interval: Option<Duration>,
+}
+
+impl Executor {
+    /// SQL expression converting the ordinal column into microseconds (for dates and datetimes, which SQLite
+    /// keeps in millisecond precision) or the raw integer.
+    fn ordinal_select_expr(&self) -> Option<String> {
+        self.schema.ordinal_column.as_ref().map(|c| match c.kind {
+            ColumnKind::Date | ColumnKind::LocalDateTime => format!(
+                "CAST(ROUND((julianday({}) - 2440587.5) * 86400000) AS INTEGER) * 1000",
+                c.quoted_name()
+            ),
+            _ => format!("CAST({} AS INTEGER)", c.quoted_name()),
+        })
+    }
+
+    /// Current ordinals of all rows.
+    async fn list_ordinals(&self) -> Result<HashMap<KeyValue, Ordinal>> {
+        let mut ordinals = HashMap::new();
+        let mut rows_stream = self.list(&SourceExecutorListOptions {
+            include_ordinal: true,
+        });
+        while let Some(rows) = rows_stream.next().await {
+            for row in rows? {
+                ordinals.insert(row.key, row.ordinal.unwrap_or(Ordinal::unavailable()));
+            }
+        }
+        Ok(ordinals)
+    }
+}
+
+/// Keys of rows that may have changed between two listings.
+fn changed_keys(
+    prev: &HashMap<KeyValue, Ordinal>,
+    current: &HashMap<KeyValue, Ordinal>,
+) -> Vec<KeyValue> {
+    let updated = current.iter().filter_map(|(key, ordinal)| {
+        let unchanged = ordinal.0.is_some() && prev.get(key) == Some(ordinal);
+        (!unchanged).then(|| key.clone())
+    });
+    let deleted = prev
+        .keys()
+        .filter(|key| !current.contains_key(key))
+        .cloned();
+    updated.chain(deleted).collect()
+}
+
+#[async_trait]
+impl SourceExecutor for Executor {
+    fn list<'a>(
+        &'a self,
+        options: &'a SourceExecutorListOptions,
+    ) -> BoxStream<'a, Result<Vec<PartialSourceRowMetadata>>> {
+        let ordinal_expr = if options.include_ordinal {
+            self.ordinal_select_expr()
+        } else {
+            None
+        };
+        let select_exprs = std::iter::once(self.schema.key_column.select_expr())
+            .chain(ordinal_expr.iter().cloned())
+            .join(", ");
+        let sql = format!("SELECT {select_exprs} FROM {}", self.relation);
+        try_stream! {
+            let mut rows = sqlx::query(&sql).fetch(&self.pool);
+            let mut batch = Vec::new();
+            while let Some(row) = rows.try_next().await? {
+                let ordinal = if options.include_ordinal {
+                    Some(match ordinal_expr {
+                        Some(_) => Ordinal(row.try_get::<Option<i64>, _>(1)?),
+                        None => Ordinal::unavailable(),
+                    })
+                } else {
+                    None
+                };
+                batch.push(PartialSourceRowMetadata {
+                    key: self.schema.key_column.kind.decode_key(&row, 0)?,
+                    ordinal,
+                });
+                if batch.len() >= LIST_BATCH_SIZE {
+                    yield std::mem::take(&mut batch);
+                }
+            }
+            if !batch.is_empty() {
+                yield batch;
+            }
+        }
+        .boxed()
+    }
+
+    async fn get_value(
+        &self,
+        key: &KeyValue,
+        options: &SourceExecutorGetOptions,
+    ) -> Result<PartialSourceRowData> {
+        let ordinal_expr = if options.include_ordinal {
+            self.ordinal_select_expr()
+        } else {
+            None
+        };
+        let value_exprs = if options.include_value {
+            self.schema
+                .value_columns
+                .iter()
+                .map(|c| c.select_expr())
+                .collect()
+        } else {
+            vec![]
+        };
+        let num_value_columns = value_exprs.len();
+        // Always select something, so the existence of the row can be told.
+        let select_exprs = value_exprs
+            .into_iter()
+            .chain(ordinal_expr.iter().cloned())
+            .chain(std::iter::once("1".to_string()))
+            .join(", ");
+        let sql = format!(
+            "SELECT {select_exprs} FROM {} WHERE {} = ?",
+            self.relation,
+            self.schema.key_column.quoted_name()
+        );
+        let query = sqlx::query(&sql);
+        let query = match key {
+            KeyValue::Bytes(v) => query.bind(v.as_ref()),
+            KeyValue::Str(v) => query.bind(v.as_ref()),
+            KeyValue::Bool(v) => query.bind(v),
+            KeyValue::Int64(v) => query.bind(v),
+            KeyValue::Date(v) => query.bind(v),
+            KeyValue::Uuid(_) | KeyValue::Range(_) | KeyValue::Struct(_) => {
+                bail!("unsupported key: {key}")
+            }
+        };
+        let row = match query.fetch_optional(&self.pool).await? {
+            Some(row) => row,
+            None => {
+                return Ok(PartialSourceRowData {
+                    value: Some(SourceValue::NonExistence),
+                    ordinal: Some(Ordinal::unavailable()),
+                });
+            }
+        };
+        let value = if options.include_value {
+            let fields = self
+                .schema
+                .value_columns
+                .iter()
+                .enumerate()
+                .map(|(idx, c)| c.kind.decode_value(&row, idx))
+                .collect::<Result<Vec<_>>>()?;
+            Some(SourceValue::Existence(FieldValues { fields }))
+        } else {
+            None
+        };
+        let ordinal = if options.include_ordinal {
+            Some(match ordinal_expr {
+                Some(_) => Ordinal(row.try_get::<Option<i64>, _>(num_value_columns)?),
+                None => Ordinal::unavailable(),
+            })
+        } else {
+            None
+        };
+        Ok(PartialSourceRowData { value, ordinal })
+    }
+
+    async fn change_stream(
+        &self,
+    ) -> Result<Option<BoxStream<'async_trait, Result<SourceChangeMessage>>>> {
+        let Some(poll_interval) = self.change_poll_interval else {
+            return Ok(None);
+        };
+        // `data_version` only changes on commits from other connections, so keep a dedicated one.
+        let mut conn = self.connect_options.connect().await?;
+        let mut prev_data_version: i64 = sqlx::query_scalar("PRAGMA data_version")
+            .fetch_one(&mut conn)
+            .await?;
+        let mut prev_ordinals = self.list_ordinals().await?;
+        let mut interval = tokio::time::interval(poll_interval);
+        interval.tick().await;
+        // Errors (e.g. `SQLITE_BUSY` while a writer commits) are yielded without ending the stream.
+        // The data version is only updated after a successful listing, so changes are picked up by the next poll.
+        let stream = stream! {
+            loop {
+                interval.tick().await;
+                let data_version: i64 = match sqlx::query_scalar("PRAGMA data_version")
+                    .fetch_one(&mut conn)
+                    .await
+                {
+                    Ok(data_version) => data_version,
+                    Err(e) => {
+                        yield Err(e.into());
+                        continue;
+                    }
+                };
+                if data_version == prev_data_version {
+                    continue;
+                }
+                let ordinals = match self.list_ordinals().await {
+                    Ok(ordinals) => ordinals,
+                    Err(e) => {
+                        yield Err(e);
+                        continue;
+                    }
+                };
+                prev_data_version = data_version;
+                let changes: Vec<_> = changed_keys(&prev_ordinals, &ordinals)
+                    .into_iter()
+                    .map(|key| SourceChange { key, data: None })
+                    .collect();
+                prev_ordinals = ordinals;
+                if !changes.is_empty() {
+                    yield Ok(SourceChangeMessage {
+                        changes,
+                        ack_fn: None,
+                    });
+                }
+            }
+        };
+        Ok(Some(stream.boxed()))
+    }
+}
+
+pub struct Factory;
+
+impl Factory {
+    async fn connect(&self, spec: &Spec) -> Result<(SqlitePool, String, RowsSchemaInfo)> {
+        let relation = source_relation(spec)?;
+        let pool = SqlitePoolOptions::new()
+            .connect_with(connect_options(spec))
+            .await
+            .with_context(|| format!("Failed to open SQLite database: {}", spec.path))?;
+        let schema = fetch_rows_schema(&pool, &relation, spec).await?;
+        Ok((pool, relation, schema))
+    }
+}
+
+#[async_trait]
+impl SourceFactoryBase for Factory {
+    type Spec = Spec;
+
+    fn name(&self) -> &str {
+        "Sqlite"
+    }
+
+    async fn get_output_schema(
+        &self,
+        spec: &Spec,
+        _context: &FlowInstanceContext,
+    ) -> Result<EnrichedValueType> {
+        let (_, _, schema) = self.connect(spec).await?;
+
+        let mut struct_schema = StructSchema::default();
+        let mut schema_builder = StructSchemaBuilder::new(&mut struct_schema);
+        schema_builder.add_field(schema.key_column.field_schema());
+        for column in schema.value_columns.iter() {
+            schema_builder.add_field(column.field_schema());
+        }
+        Ok(make_output_type(TableSchema::new(
+            TableKind::KTable,
+            struct_schema,
+        )))
+    }
+
+    async fn build_executor(
+        self: Arc<Self>,
+        spec: Spec,
+        _context: Arc<FlowInstanceContext>,
+    ) -> Result<Box<dyn SourceExecutor>> {
+        let (pool, relation, schema) = self.connect(&spec).await?;
+        Ok(Box::new(Executor {
+            pool,
+            connect_options: connect_options(&spec),
+            relation,
+            schema,
+            change_poll_interval: spec.change_poll_interval,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::shared::test_dir::TestDir;
+    use sqlx::SqliteConnection;
+
+    struct TestDb {
+        dir: TestDir,
+    }
+
+    impl TestDb {
+        async fn new() -> (Self, SqliteConnection) {
+            let db = Self {
+                dir: TestDir::new("sqlite"),
+            };
+            let mut conn = SqliteConnectOptions::new()
+                .filename(db.path())
+                .create_if_missing(true)
+                .connect()
+                .await
+                .unwrap();
+            conn.execute(
+                "CREATE TABLE items (
+                    id INTEGER PRIMARY KEY,
+                    name VARCHAR(20) NOT NULL,
+                    score REAL,
+                    payload BLOB,
+                    updated_at DATETIME NOT NULL
+                );
+                INSERT INTO items VALUES
+                    (1, 'a', 1.5, x'01', '2024-01-02 03:04:05'),
+                    (2, 'b', NULL, NULL, '2024-01-03 00:00:00');",
+            )
+            .await
+            .unwrap();
+            (db, conn)
+        }
+
+        fn path(&self) -> std::path::PathBuf {
+            self.dir.0.join("test.db")
+        }
+
+        fn spec(&self) -> Spec {
+            Spec {
+                path: self.path().to_string_lossy().to_string(),
+                table_name: Some("items".to_string()),
+                query: None,
+                key_column: "id".to_string(),
+                included_columns: None,
+                ordinal_column: Some("updated_at".to_string()),
+                change_poll_interval: Some(Duration::from_millis(10)),
+            }
+        }
+
+        async fn executor(&self, spec: Spec) -> Executor {
+            let (pool, relation, schema) = Factory.connect(&spec).await.unwrap();
+            Executor {
+                pool,
+                connect_options: connect_options(&spec),
+                relation,
+                schema,
+                change_poll_interval: spec.change_poll_interval,
+            }
+        }
+    }
+
+    #[test]
+    fn test_column_kind_from_sqlite_type() {
+        assert_eq!(ColumnKind::from_sqlite_type("integer"), ColumnKind::Int64);
+        assert_eq!(ColumnKind::from_sqlite_type("REAL"), ColumnKind::Float64);
+        assert_eq!(ColumnKind::from_sqlite_type("BLOB"), ColumnKind::Bytes);
+        assert_eq!(
+            ColumnKind::from_sqlite_type("DATETIME"),
+            ColumnKind::LocalDateTime
+        );
+        assert_eq!(ColumnKind::from_sqlite_type("NULL"), ColumnKind::Str);
+    }
+
+    #[tokio::test]
+    async fn test_read_table() {
+        let (db, _conn) = TestDb::new().await;
+        let executor = db.executor(db.spec()).await;
+        assert_eq!(
+            executor
+                .schema
+                .value_columns
+                .iter()
+                .map(|c| (c.name.as_str(), c.kind, c.nullable))
+                .collect::<Vec<_>>(),
+            vec![
+                ("name", ColumnKind::Str, false),
+                ("score", ColumnKind::Float64, true),
+                ("payload", ColumnKind::Bytes, true),
+                ("updated_at", ColumnKind::LocalDateTime, false),
+            ]
+        );
+
+        let ordinals = executor.list_ordinals().await.unwrap();
+        let expected_ordinal = chrono::NaiveDate::from_ymd_opt(2024, 1, 2)
+            .unwrap()
+            .and_hms_opt(3, 4, 5)
+            .unwrap()
+            .and_utc()
+            .timestamp_micros();
+        assert_eq!(ordinals.len(), 2);
+        assert_eq!(
+            ordinals[&KeyValue::Int64(1)],
+            Ordinal(Some(expected_ordinal))
+        );
+
+        let data = executor
+            .get_value(
+                &KeyValue::Int64(2),
+                &SourceExecutorGetOptions {
+                    include_value: true,
+                    include_ordinal: true,
+                },
+            )
+            .await
+            .unwrap();
+        let Some(SourceValue::Existence(values)) = data.value else {
+            panic!("expected an existing row");
+        };
+        assert_eq!(values.fields[0], Value::Basic(BasicValue::Str("b".into())));
+        assert_eq!(values.fields[1], Value::Null);
+
+        let data = executor
+            .get_value(
+                &KeyValue::Int64(3),
+                &SourceExecutorGetOptions {
+                    include_value: true,
+                    include_ordinal: true,
+                },
+            )
+            .await
+            .unwrap();
+        assert!(matches!(data.value, Some(SourceValue::NonExistence)));
+    }
+
+    #[tokio::test]
+    async fn test_change_stream_on_data_version() {
+        let (db, mut conn) = TestDb::new().await;
+        let mut spec = db.spec();
+        spec.table_name = None;
+        spec.query = Some("SELECT id, name, updated_at FROM items WHERE id > 0;".to_string());
+        let executor = db.executor(spec).await;
+        let mut change_stream = executor.change_stream().await.unwrap().unwrap();
+
+        conn.execute(
+            "UPDATE items SET name = 'c', updated_at = '2024-02-01 00:00:00' WHERE id = 2;
+             DELETE FROM items WHERE id = 1;",
+        )
+        .await
+        .unwrap();
+        let message = tokio::time::timeout(Duration::from_secs(5), change_stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let mut keys = message
+            .changes
+            .into_iter()
+            .map(|change| change.key)
+            .collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, vec![KeyValue::Int64(1), KeyValue::Int64(2)]);
+    }
+}
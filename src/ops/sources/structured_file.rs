@@ -0,0 +1,889 @@
+use async_stream::try_stream;
+use log::warn;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+use crate::ops::sdk::*;
+use crate::ops::shared::csv_cell;
+use crate::ops::shared::pattern_matcher::PatternMatcher;
+use crate::service::error::{SharedError, SharedResultExt};
+
+/// Name of the key field when rows are keyed by file and row number.
+const ROW_KEY_FIELD_NAME: &str = "_key";
+/// Maximum number of records to sample from a file for schema inference.
+const SCHEMA_INFERENCE_MAX_RECORDS: usize = 1000;
+/// Number of recently parsed files kept in memory, so rows of the same file don't re-parse it.
+/// Keys are kept for all listed files, so files evicted are only re-parsed when their rows are fetched.
+const PARSED_FILE_CACHE_CAPACITY: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum FileFormat {
+    Jsonl,
+    Csv,
+    Parquet,
+}
+
+impl FileFormat {
+    fn default_included_patterns(&self) -> &'static [&'static str] {
+        match self {
+            FileFormat::Jsonl => &["*.jsonl", "*.ndjson"],
+            FileFormat::Csv => &["*.csv"],
+            FileFormat::Parquet => &["*.parquet"],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Spec {
+    path: String,
+    format: FileFormat,
+    key_column: Option<String>,
+    record_type: Option<EnrichedValueType>,
+    included_patterns: Option<Vec<String>>,
+    excluded_patterns: Option<Vec<String>>,
+}
+
+type Record = serde_json::Map<String, serde_json::Value>;
+
+/// Read all records from a file, each with its 1-based row number.
+/// For JSONL files, the row number is the line number, so skipping blank lines doesn't shift it.
+/// Cells of CSV files are converted based on `column_types` if available.
+fn read_records(
+    path: &Path,
+    format: FileFormat,
+    column_types: &HashMap<String, ValueType>,
+    max_records: Option<usize>,
+) -> Result<Vec<(usize, Record)>> {
+    let max_records = max_records.unwrap_or(usize::MAX);
+    let mut records = Vec::new();
+    match format {
+        FileFormat::Jsonl => {
+            let reader = std::io::BufReader::new(File::open(path)?);
+            for (idx, line) in reader.lines().enumerate() {
+                if records.len() >= max_records {
+                    break;
+                }
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str(&line)
+                    .with_context(|| format!("Invalid JSON at line {}", idx + 1))?
+                {
+                    serde_json::Value::Object(record) => records.push((idx + 1, record)),
+                    _ => api_bail!("Expected a JSON object at line {}", idx + 1),
+                }
+            }
+        }
+        FileFormat::Csv => {
+            let mut reader = csv::Reader::from_path(path)?;
+            let headers = reader.headers()?.clone();
+            for row in reader.records() {
+                if records.len() >= max_records {
+                    break;
+                }
+                let row = row?;
+                records.push((
+                    records.len() + 1,
+                    headers
+                        .iter()
+                        .zip(row.iter())
+                        .map(|(column, cell)| {
+                            (
+                                column.to_string(),
+                                csv_cell::to_json(cell, column_types.get(column)),
+                            )
+                        })
+                        .collect(),
+                ));
+            }
+        }
+        FileFormat::Parquet => {
+            let reader =
+                parquet::file::serialized_reader::SerializedFileReader::new(File::open(path)?)?;
+            for row in reader.into_iter().take(max_records) {
+                match row?.to_json_value() {
+                    serde_json::Value::Object(record) => records.push((records.len() + 1, record)),
+                    _ => api_bail!("Expected a group for a parquet row"),
+                }
+            }
+        }
+    }
+    Ok(records)
+}
+
+fn infer_json_type(value: &serde_json::Value) -> Option<BasicValueType> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::Bool(_) => Some(BasicValueType::Bool),
+        serde_json::Value::Number(n) if n.is_i64() => Some(BasicValueType::Int64),
+        serde_json::Value::Number(_) => Some(BasicValueType::Float64),
+        serde_json::Value::String(_) => Some(BasicValueType::Str),
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => Some(BasicValueType::Json),
+    }
+}
+
+fn merge_types(
+    existing: Option<BasicValueType>,
+    new: Option<BasicValueType>,
+) -> Option<BasicValueType> {
+    match (existing, new) {
+        (None, t) | (t, None) => t,
+        (Some(a), Some(b)) if a == b => Some(a),
+        (Some(BasicValueType::Int64), Some(BasicValueType::Float64))
+        | (Some(BasicValueType::Float64), Some(BasicValueType::Int64)) => {
+            Some(BasicValueType::Float64)
+        }
+        _ => Some(BasicValueType::Json),
+    }
+}
+
+fn infer_parquet_type(field: &parquet::schema::types::Type) -> BasicValueType {
+    use parquet::basic::{ConvertedType, Repetition, Type as PhysicalType};
+
+    let basic_info = field.get_basic_info();
+    if !field.is_primitive()
+        || (basic_info.has_repetition() && basic_info.repetition() == Repetition::REPEATED)
+    {
+        return BasicValueType::Json;
+    }
+    match basic_info.converted_type() {
+        ConvertedType::UTF8 | ConvertedType::ENUM | ConvertedType::JSON => {
+            return BasicValueType::Str;
+        }
+        ConvertedType::NONE
+        | ConvertedType::INT_8
+        | ConvertedType::INT_16
+        | ConvertedType::INT_32
+        | ConvertedType::INT_64
+        | ConvertedType::UINT_8
+        | ConvertedType::UINT_16
+        | ConvertedType::UINT_32 => {}
+        // Dates, times, decimals etc. are represented as strings.
+        _ => return BasicValueType::Str,
+    }
+    match field.get_physical_type() {
+        PhysicalType::BOOLEAN => BasicValueType::Bool,
+        PhysicalType::INT32 | PhysicalType::INT64 => BasicValueType::Int64,
+        PhysicalType::FLOAT | PhysicalType::DOUBLE => BasicValueType::Float64,
+        PhysicalType::BYTE_ARRAY | PhysicalType::FIXED_LEN_BYTE_ARRAY => BasicValueType::Bytes,
+        PhysicalType::INT96 => BasicValueType::Str,
+    }
+}
+
+fn nullable_field(name: &str, typ: BasicValueType) -> FieldSchema {
+    FieldSchema::new(
+        name,
+        EnrichedValueType {
+            nullable: true,
+            ..make_output_type(typ)
+        },
+    )
+}
+
+/// Infer fields of records from a sample file.
+fn infer_fields(path: &Path, format: FileFormat) -> Result<Vec<FieldSchema>> {
+    let fields = match format {
+        FileFormat::Jsonl => {
+            let records = read_records(
+                path,
+                format,
+                &HashMap::new(),
+                Some(SCHEMA_INFERENCE_MAX_RECORDS),
+            )?;
+            let mut types = IndexMap::<String, Option<BasicValueType>>::new();
+            for (_, record) in records.iter() {
+                for (name, value) in record.iter() {
+                    let typ = types.entry(name.clone()).or_default();
+                    *typ = merge_types(typ.take(), infer_json_type(value));
+                }
+            }
+            types
+                .into_iter()
+                .map(|(name, typ)| nullable_field(&name, typ.unwrap_or(BasicValueType::Json)))
+                .collect()
+        }
+        FileFormat::Csv => csv::Reader::from_path(path)?
+            .headers()?
+            .iter()
+            .map(|name| nullable_field(name, BasicValueType::Str))
+            .collect(),
+        FileFormat::Parquet => {
+            use parquet::file::reader::FileReader;
+
+            let reader =
+                parquet::file::serialized_reader::SerializedFileReader::new(File::open(path)?)?;
+            reader
+                .metadata()
+                .file_metadata()
+                .schema()
+                .get_fields()
+                .iter()
+                .map(|field| nullable_field(field.name(), infer_parquet_type(field)))
+                .collect()
+        }
+    };
+    Ok(fields)
+}
+
+struct RecordSchema {
+    /// `None` means rows are keyed by file and row number.
+    key_column: Option<FieldSchema>,
+    value_fields: Vec<FieldSchema>,
+    column_types: HashMap<String, ValueType>,
+}
+
+impl RecordSchema {
+    fn key_field(&self) -> FieldSchema {
+        match &self.key_column {
+            Some(key_column) => key_column.clone(),
+            None => FieldSchema::new(
+                ROW_KEY_FIELD_NAME,
+                make_output_type(StructSchema {
+                    fields: Arc::new(vec![
+                        FieldSchema::new("filename", make_output_type(BasicValueType::Str)),
+                        FieldSchema::new("row_number", make_output_type(BasicValueType::Int64)),
+                    ]),
+                    description: None,
+                }),
+            ),
+        }
+    }
+}
+
+struct ParsedFile {
+    ordinal: Ordinal,
+    rows: IndexMap<KeyValue, FieldValues>,
+}
+
+/// Parsing result of a file, shared by concurrent readers so the file is only parsed once.
+type ParsedFileCell = Arc<OnceLock<Result<Arc<ParsedFile>, SharedError>>>;
+
+#[derive(Clone)]
+struct ListedFile {
+    ordinal: Ordinal,
+    keys: Arc<Vec<KeyValue>>,
+}
+
+#[derive(Default)]
+struct ListedState {
+    files: HashMap<String, ListedFile>,
+    key_to_file: HashMap<KeyValue, String>,
+}
+
+struct RecordsReader {
+    /// Filenames in keys are relative to it.
+    base_dir: PathBuf,
+    /// Set when the spec points to a single file, instead of a directory.
+    single_file: Option<String>,
+    format: FileFormat,
+    pattern_matcher: PatternMatcher,
+    schema: RecordSchema,
+    /// Recently parsed files keyed by filename, least recently used first.
+    parsed_files: Mutex<IndexMap<String, (Ordinal, ParsedFileCell)>>,
+    /// Keys of files in the last listing. Only used when rows are keyed by a column.
+    listed_state: Mutex<Option<ListedState>>,
+}
+
+fn file_ordinal(path: &Path) -> Result<Option<Ordinal>> {
+    match path.metadata() {
+        Ok(metadata) => Ok(Some(metadata.modified()?.try_into()?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e)?,
+    }
+}
+
+impl RecordsReader {
+    /// Relative paths of all files to import.
+    fn list_files(&self) -> Result<Vec<String>> {
+        if let Some(single_file) = &self.single_file {
+            return Ok(vec![single_file.clone()]);
+        }
+        let mut files = Vec::new();
+        let mut dirs = vec![self.base_dir.clone()];
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let path = entry?.path();
+                let Some(relative_path) = path
+                    .strip_prefix(&self.base_dir)
+                    .ok()
+                    .and_then(|p| p.to_str())
+                else {
+                    warn!("Skipped ill-formed file path: {}", path.display());
+                    continue;
+                };
+                if path.is_dir() {
+                    if !self.pattern_matcher.is_excluded(relative_path) {
+                        dirs.push(path);
+                    }
+                } else if self.pattern_matcher.is_file_included(relative_path) {
+                    files.push(relative_path.to_string());
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    fn row_key(&self, filename: &str, row_number: usize, record: &mut Record) -> Result<KeyValue> {
+        match &self.schema.key_column {
+            Some(key_column) => {
+                let value = record.remove(&key_column.name).unwrap_or_default();
+                let key = value::Value::<value::ScopeValue>::from_json(
+                    value,
+                    &key_column.value_type.typ,
+                )?;
+                if key.is_null() {
+                    api_bail!("Key column `{}` is missing", key_column.name);
+                }
+                key.into_key()
+            }
+            None => Ok(KeyValue::Struct(vec![
+                KeyValue::Str(filename.into()),
+                KeyValue::Int64(row_number as i64),
+            ])),
+        }
+    }
+
+    /// Parse a file into rows, or get it from recently parsed ones if the file is unchanged.
+    /// Concurrent calls for the same file share a single parse.
+    /// Returns `None` if the file doesn't exist.
+    fn parse_file(&self, filename: &str) -> Result<Option<Arc<ParsedFile>>> {
+        let path = self.base_dir.join(filename);
+        let Some(ordinal) = file_ordinal(&path)? else {
+            return Ok(None);
+        };
+        let cell = {
+            let mut parsed_files = self.parsed_files.lock().unwrap();
+            let cell = match parsed_files.shift_remove(filename) {
+                Some((cached_ordinal, cell)) if cached_ordinal == ordinal => cell,
+                _ => ParsedFileCell::default(),
+            };
+            parsed_files.insert(filename.to_string(), (ordinal, cell.clone()));
+            while parsed_files.len() > PARSED_FILE_CACHE_CAPACITY {
+                parsed_files.shift_remove_index(0);
+            }
+            cell
+        };
+        let parsed_file = cell
+            .get_or_init(|| {
+                self.read_parsed_file(filename, &path, ordinal)
+                    .map(Arc::new)
+                    .map_err(SharedError::new)
+            })
+            .clone()
+            .std_result()?;
+        Ok(Some(parsed_file))
+    }
+
+    fn read_parsed_file(
+        &self,
+        filename: &str,
+        path: &Path,
+        ordinal: Ordinal,
+    ) -> Result<ParsedFile> {
+        let records = read_records(path, self.format, &self.schema.column_types, None)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        let mut rows = IndexMap::with_capacity(records.len());
+        for (row_number, mut record) in records {
+            let parse_row = |record: &mut Record| -> Result<(KeyValue, FieldValues)> {
+                let key = self.row_key(filename, row_number, record)?;
+                let values = FieldValues::from_json(
+                    serde_json::Value::Object(std::mem::take(record)),
+                    &self.schema.value_fields,
+                )?;
+                Ok((key, values))
+            };
+            let (key, values) = parse_row(&mut record).with_context(|| {
+                format!("Invalid record at row {row_number} of file {filename}")
+            })?;
+            if rows.insert(key, values).is_some() {
+                warn!("Duplicated key at row {row_number} of file {filename}, overriding");
+            }
+        }
+        Ok(ParsedFile { ordinal, rows })
+    }
+
+    /// List keys of records in all files, grouped by file, reusing keys of files unchanged since the last listing.
+    /// When a key exists in multiple files, it's only listed with the last one, which is also where `get_row()` reads it.
+    fn list_keys(&self) -> Result<Vec<(Ordinal, Arc<Vec<KeyValue>>)>> {
+        // Keep the previous state in place until the new one is complete, so concurrent `get_row()` calls still find keys.
+        let prev_files = self
+            .listed_state
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|state| state.files.clone())
+            .unwrap_or_default();
+        let mut state = ListedState::default();
+        let mut listed_files = Vec::new();
+        for filename in self.list_files()? {
+            let path = self.base_dir.join(&filename);
+            let Some(ordinal) = file_ordinal(&path)? else {
+                continue;
+            };
+            let keys = match prev_files.get(&filename) {
+                Some(listed_file) if listed_file.ordinal == ordinal => listed_file.keys.clone(),
+                _ => match self.parse_file(&filename)? {
+                    Some(parsed_file) => Arc::new(parsed_file.rows.keys().cloned().collect()),
+                    None => continue,
+                },
+            };
+            if self.schema.key_column.is_some() {
+                for key in keys.iter() {
+                    if let Some(existing) = state.key_to_file.insert(key.clone(), filename.clone())
+                    {
+                        warn!(
+                            "Key {key} exists in both {existing} and {filename}, using the latter"
+                        );
+                    }
+                }
+            }
+            listed_files.push((filename.clone(), ordinal, keys.clone()));
+            state.files.insert(filename, ListedFile { ordinal, keys });
+        }
+
+        let result = listed_files
+            .into_iter()
+            .map(|(filename, ordinal, keys)| {
+                let has_overridden_keys = self.schema.key_column.is_some()
+                    && keys.iter().any(|key| state.key_to_file[key] != filename);
+                let keys = if has_overridden_keys {
+                    Arc::new(
+                        keys.iter()
+                            .filter(|key| state.key_to_file[*key] == filename)
+                            .cloned()
+                            .collect(),
+                    )
+                } else {
+                    keys
+                };
+                (ordinal, keys)
+            })
+            .collect();
+        *self.listed_state.lock().unwrap() = Some(state);
+        Ok(result)
+    }
+
+    /// Get the ordinal and value of a row. Returns `None` if it doesn't exist.
+    fn get_row(&self, key: &KeyValue) -> Result<Option<(Ordinal, FieldValues)>> {
+        let filename = match &self.schema.key_column {
+            Some(_) => {
+                if self.listed_state.lock().unwrap().is_none() {
+                    self.list_keys()?;
+                }
+                let listed_state = self.listed_state.lock().unwrap();
+                match listed_state
+                    .as_ref()
+                    .and_then(|state| state.key_to_file.get(key))
+                {
+                    Some(filename) => filename.clone(),
+                    None => return Ok(None),
+                }
+            }
+            None => match key {
+                KeyValue::Struct(fields) => match fields.first() {
+                    Some(KeyValue::Str(filename)) => filename.to_string(),
+                    _ => api_bail!("Invalid key: {key}"),
+                },
+                _ => api_bail!("Invalid key: {key}"),
+            },
+        };
+        if !self.pattern_matcher.is_file_included(&filename)
+            || self
+                .single_file
+                .as_ref()
+                .is_some_and(|single_file| single_file != &filename)
+        {
+            return Ok(None);
+        }
+        let Some(parsed_file) = self.parse_file(&filename)? else {
+            return Ok(None);
+        };
+        Ok(parsed_file
+            .rows
+            .get(key)
+            .map(|values| (parsed_file.ordinal, values.clone())))
+    }
+}
+
+struct Executor {
+    reader: Arc<RecordsReader>,
+}
+
+#[async_trait]
+impl SourceExecutor for Executor {
+    fn list<'a>(
+        &'a self,
+        options: &'a SourceExecutorListOptions,
+    ) -> BoxStream<'a, Result<Vec<PartialSourceRowMetadata>>> {
+        try_stream! {
+            let reader = self.reader.clone();
+            let files = tokio::task::spawn_blocking(move || reader.list_keys()).await??;
+            for (ordinal, keys) in files {
+                yield keys
+                    .iter()
+                    .map(|key| PartialSourceRowMetadata {
+                        key: key.clone(),
+                        ordinal: options.include_ordinal.then_some(ordinal),
+                    })
+                    .collect();
+            }
+        }
+        .boxed()
+    }
+
+    async fn get_value(
+        &self,
+        key: &KeyValue,
+        options: &SourceExecutorGetOptions,
+    ) -> Result<PartialSourceRowData> {
+        let reader = self.reader.clone();
+        let key = key.clone();
+        let row = tokio::task::spawn_blocking(move || {
+            reader.get_row(&key).map(|row| {
+                row.map(|(ordinal, values)| {
+                    let mut fields = Vec::with_capacity(values.fields.len() + 1);
+                    fields.push(value::Value::from(key));
+                    fields.extend(values.fields);
+                    (ordinal, FieldValues { fields })
+                })
+            })
+        })
+        .await??;
+        let Some((ordinal, values)) = row else {
+            return Ok(PartialSourceRowData {
+                value: Some(SourceValue::NonExistence),
+                ordinal: Some(Ordinal::unavailable()),
+            });
+        };
+        Ok(PartialSourceRowData {
+            value: options
+                .include_value
+                .then_some(SourceValue::Existence(values)),
+            ordinal: options.include_ordinal.then_some(ordinal),
+        })
+    }
+}
+
+pub struct Factory;
+
+impl Factory {
+    fn build_reader(&self, spec: &Spec) -> Result<RecordsReader> {
+        let path = PathBuf::from(&spec.path);
+        let (base_dir, single_file) = if path.is_file() {
+            let file_name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or_else(|| api_error!("Ill-formed file path: {}", path.display()))?
+                .to_string();
+            let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            (base_dir, Some(file_name))
+        } else {
+            (path, None)
+        };
+        let included_patterns = spec.included_patterns.clone().unwrap_or_else(|| {
+            spec.format
+                .default_included_patterns()
+                .iter()
+                .map(|p| p.to_string())
+                .collect()
+        });
+        let mut reader = RecordsReader {
+            base_dir,
+            single_file,
+            format: spec.format,
+            pattern_matcher: PatternMatcher::new(
+                Some(included_patterns),
+                spec.excluded_patterns.clone(),
+            )?,
+            schema: RecordSchema {
+                key_column: None,
+                value_fields: vec![],
+                column_types: HashMap::new(),
+            },
+            parsed_files: Mutex::new(IndexMap::new()),
+            listed_state: Mutex::new(None),
+        };
+
+        let fields = match &spec.record_type {
+            Some(record_type) => match &record_type.typ {
+                ValueType::Struct(struct_schema) => struct_schema.fields.as_ref().clone(),
+                _ => api_bail!("`record_type` must be a struct type"),
+            },
+            None => {
+                let sample_file = reader.list_files()?.into_iter().next().ok_or_else(|| {
+                    api_error!(
+                        "No file found under {} to infer the schema from. Please specify `record_type`",
+                        spec.path
+                    )
+                })?;
+                infer_fields(&reader.base_dir.join(&sample_file), spec.format)
+                    .with_context(|| format!("Failed to infer schema from {sample_file}"))?
+            }
+        };
+        let mut key_column = None;
+        let mut value_fields = Vec::with_capacity(fields.len());
+        for field in fields {
+            if Some(&field.name) == spec.key_column.as_ref() {
+                let is_key_type = match &field.value_type.typ {
+                    ValueType::Basic(typ) => matches!(
+                        typ,
+                        BasicValueType::Str
+                            | BasicValueType::Bytes
+                            | BasicValueType::Bool
+                            | BasicValueType::Int64
+                            | BasicValueType::Uuid
+                            | BasicValueType::Date
+                    ),
+                    _ => false,
+                };
+                if !is_key_type {
+                    api_bail!(
+                        "Key column `{}` has type {}, which cannot be used as a key",
+                        field.name,
+                        field.value_type.typ
+                    );
+                }
+                key_column = Some(FieldSchema {
+                    value_type: EnrichedValueType {
+                        nullable: false,
+                        ..field.value_type
+                    },
+                    ..field
+                });
+            } else {
+                value_fields.push(field);
+            }
+        }
+        if let Some(key_column_name) = &spec.key_column {
+            if key_column.is_none() {
+                api_bail!("Key column `{key_column_name}` not found in records");
+            }
+        }
+        reader.schema = RecordSchema {
+            column_types: key_column
+                .iter()
+                .chain(value_fields.iter())
+                .map(|field| (field.name.clone(), field.value_type.typ.clone()))
+                .collect(),
+            key_column,
+            value_fields,
+        };
+        Ok(reader)
+    }
+}
+
+#[async_trait]
+impl SourceFactoryBase for Factory {
+    type Spec = Spec;
+
+    fn name(&self) -> &str {
+        "StructuredFile"
+    }
+
+    async fn get_output_schema(
+        &self,
+        spec: &Spec,
+        _context: &FlowInstanceContext,
+    ) -> Result<EnrichedValueType> {
+        let reader = self.build_reader(spec)?;
+        let mut struct_schema = StructSchema::default();
+        let mut schema_builder = StructSchemaBuilder::new(&mut struct_schema);
+        schema_builder.add_field(reader.schema.key_field());
+        for field in reader.schema.value_fields {
+            schema_builder.add_field(field);
+        }
+        Ok(make_output_type(TableSchema::new(
+            TableKind::KTable,
+            struct_schema,
+        )))
+    }
+
+    async fn build_executor(
+        self: Arc<Self>,
+        spec: Spec,
+        _context: Arc<FlowInstanceContext>,
+    ) -> Result<Box<dyn SourceExecutor>> {
+        Ok(Box::new(Executor {
+            reader: Arc::new(self.build_reader(&spec)?),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::shared::test_dir::TestDir;
+
+    fn spec(path: &Path, format: FileFormat, key_column: Option<&str>) -> Spec {
+        Spec {
+            path: path.to_string_lossy().to_string(),
+            format,
+            key_column: key_column.map(|s| s.to_string()),
+            record_type: None,
+            included_patterns: None,
+            excluded_patterns: None,
+        }
+    }
+
+    fn field_types(reader: &RecordsReader) -> Vec<(String, ValueType)> {
+        reader
+            .schema
+            .value_fields
+            .iter()
+            .map(|f| (f.name.clone(), f.value_type.typ.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_jsonl_with_key_column() {
+        let dir = TestDir::new("structured-file");
+        std::fs::write(
+            dir.0.join("a.jsonl"),
+            "{\"id\": \"x\", \"n\": 1, \"tags\": [\"t\"]}\n\n{\"id\": \"y\", \"n\": 2.5}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.0.join("b.jsonl"), "{\"id\": \"z\", \"n\": 3}\n").unwrap();
+        std::fs::write(dir.0.join("ignored.csv"), "id\nw\n").unwrap();
+
+        let reader = Factory
+            .build_reader(&spec(&dir.0, FileFormat::Jsonl, Some("id")))
+            .unwrap();
+        assert_eq!(
+            reader.schema.key_field().value_type,
+            make_output_type(BasicValueType::Str)
+        );
+        assert_eq!(
+            field_types(&reader),
+            vec![
+                ("n".to_string(), ValueType::Basic(BasicValueType::Float64)),
+                ("tags".to_string(), ValueType::Basic(BasicValueType::Json)),
+            ]
+        );
+
+        let keys = reader
+            .list_keys()
+            .unwrap()
+            .into_iter()
+            .flat_map(|(_, keys)| keys.as_ref().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![
+                KeyValue::Str("x".into()),
+                KeyValue::Str("y".into()),
+                KeyValue::Str("z".into()),
+            ]
+        );
+
+        let (_, values) = reader.get_row(&KeyValue::Str("y".into())).unwrap().unwrap();
+        assert_eq!(
+            values.fields,
+            vec![
+                value::Value::Basic(BasicValue::Float64(2.5)),
+                value::Value::Null,
+            ]
+        );
+        assert!(
+            reader
+                .get_row(&KeyValue::Str("w".into()))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_csv_with_row_number_key() {
+        let dir = TestDir::new("structured-file");
+        let file_path = dir.0.join("data.csv");
+        std::fs::write(&file_path, "name,count\nfoo,3\nbar,\n").unwrap();
+
+        let mut spec = spec(&file_path, FileFormat::Csv, None);
+        spec.record_type = Some(make_output_type(StructSchema {
+            fields: Arc::new(vec![
+                FieldSchema::new("name", make_output_type(BasicValueType::Str)),
+                FieldSchema::new(
+                    "count",
+                    EnrichedValueType {
+                        nullable: true,
+                        ..make_output_type(BasicValueType::Int64)
+                    },
+                ),
+            ]),
+            description: None,
+        }));
+        let reader = Factory.build_reader(&spec).unwrap();
+        assert_eq!(reader.schema.key_field().name, ROW_KEY_FIELD_NAME);
+
+        let row_key = |row_number| {
+            KeyValue::Struct(vec![
+                KeyValue::Str("data.csv".into()),
+                KeyValue::Int64(row_number),
+            ])
+        };
+        let (_, keys) = reader.list_keys().unwrap().pop().unwrap();
+        assert_eq!(keys.as_ref(), &vec![row_key(1), row_key(2)]);
+
+        let (_, values) = reader.get_row(&row_key(1)).unwrap().unwrap();
+        assert_eq!(
+            values.fields,
+            vec![
+                value::Value::Basic(BasicValue::Str("foo".into())),
+                value::Value::Basic(BasicValue::Int64(3)),
+            ]
+        );
+        let (_, values) = reader.get_row(&row_key(2)).unwrap().unwrap();
+        assert_eq!(values.fields[1], value::Value::Null);
+        assert!(reader.get_row(&row_key(3)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_jsonl_row_numbers_and_duplicated_keys() {
+        let dir = TestDir::new("structured-file");
+        std::fs::write(
+            dir.0.join("a.jsonl"),
+            "{\"id\": \"x\", \"n\": 1}\n\n{\"id\": \"y\", \"n\": 2}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.0.join("b.jsonl"), "{\"id\": \"x\", \"n\": 3}\n").unwrap();
+
+        // Row numbers are line numbers, not shifted by the blank line.
+        let reader = Factory
+            .build_reader(&spec(&dir.0, FileFormat::Jsonl, None))
+            .unwrap();
+        let (_, keys) = reader.list_keys().unwrap().swap_remove(0);
+        assert_eq!(
+            keys.as_ref(),
+            &vec![
+                KeyValue::Struct(vec![KeyValue::Str("a.jsonl".into()), KeyValue::Int64(1)]),
+                KeyValue::Struct(vec![KeyValue::Str("a.jsonl".into()), KeyValue::Int64(3)]),
+            ]
+        );
+
+        // A key existing in multiple files is listed once, with the file it's read from.
+        let reader = Factory
+            .build_reader(&spec(&dir.0, FileFormat::Jsonl, Some("id")))
+            .unwrap();
+        let files = reader
+            .list_keys()
+            .unwrap()
+            .into_iter()
+            .map(|(_, keys)| keys.as_ref().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            vec![
+                vec![KeyValue::Str("y".into())],
+                vec![KeyValue::Str("x".into())],
+            ]
+        );
+        let (_, values) = reader.get_row(&KeyValue::Str("x".into())).unwrap().unwrap();
+        assert_eq!(
+            values.fields,
+            vec![value::Value::Basic(BasicValue::Int64(3))]
+        );
+    }
+}
@@ -0,0 +1,882 @@
+use async_stream::try_stream;
+use log::{debug, warn};
+use regex::Regex;
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{StatusCode, Url};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use crate::base::field_attrs;
+use crate::ops::sdk::*;
+
+const DEFAULT_USER_AGENT: &str = "cocoindex";
+const DEFAULT_MIME_TYPE: &str = "application/octet-stream";
+const HTML_MIME_TYPE: &str = "text/html";
+
+fn default_respect_robots_txt() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Spec {
+    start_urls: Vec<String>,
+    allowed_domains: Option<Vec<String>>,
+    allowed_path_prefixes: Option<Vec<String>>,
+    max_depth: Option<usize>,
+    binary: bool,
+    #[serde(default = "default_respect_robots_txt")]
+    respect_robots_txt: bool,
+    min_request_interval: Option<Duration>,
+    user_agent: Option<String>,
+}
+
+/// Rules from a robots.txt file applicable to our user agent.
+#[derive(Debug, Default)]
+struct RobotsRules {
+    /// Each rule is a pattern with whether it allows matching paths.
+    rules: Vec<(Regex, usize, bool)>,
+}
+
+impl RobotsRules {
+    fn parse(content: &str, user_agent: &str) -> Self {
+        let product_token = user_agent
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+
+        // Rules of groups for our user agent, and of groups for all user agents.
+        let mut specific_rules = Vec::new();
+        let mut wildcard_rules = Vec::new();
+        let mut group_agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let field = field.trim().to_ascii_lowercase();
+            let value = value.trim();
+            match field.as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        group_agents.clear();
+                        in_rules = false;
+                    }
+                    group_agents.push(value.to_ascii_lowercase());
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    if value.is_empty() {
+                        continue;
+                    }
+                    let Some(rule) = Self::compile_pattern(value) else {
+                        warn!("Ignored invalid robots.txt rule: {line}");
+                        continue;
+                    };
+                    let rule = (rule, value.len(), field == "allow");
+                    if group_agents
+                        .iter()
+                        .any(|agent| agent != "*" && product_token.contains(agent.as_str()))
+                    {
+                        specific_rules.push(rule);
+                    } else if group_agents.iter().any(|agent| agent == "*") {
+                        wildcard_rules.push(rule);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self {
+            rules: if specific_rules.is_empty() {
+                wildcard_rules
+            } else {
+                specific_rules
+            },
+        }
+    }
+
+    /// Patterns are path prefixes, with `*` matching any sequence and a trailing `$` matching the end.
+    fn compile_pattern(pattern: &str) -> Option<Regex> {
+        let (pattern, anchored) = match pattern.strip_suffix('$') {
+            Some(pattern) => (pattern, true),
+            None => (pattern, false),
+        };
+        let regex = pattern.split('*').map(regex::escape).join(".*");
+        Regex::new(&format!("^{regex}{}", if anchored { "$" } else { "" })).ok()
+    }
+
+    /// The longest matching rule wins. `Allow` wins ties.
+    fn is_allowed(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(regex, _, _)| regex.is_match(path))
+            .max_by_key(|(_, len, allow)| (*len, *allow))
+            .is_none_or(|(_, _, allow)| *allow)
+    }
+}
+
+/// Delays requests, to keep a minimal interval between them.
+struct RateLimiter {
+    interval: Duration,
+    next_request_time: tokio::sync::Mutex<tokio::time::Instant>,
+}
+
+impl RateLimiter {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next_request_time: tokio::sync::Mutex::new(tokio::time::Instant::now()),
+        }
+    }
+
+    async fn acquire(&self) {
+        let request_time = {
+            let mut next_request_time = self.next_request_time.lock().await;
+            let request_time = std::cmp::max(*next_request_time, tokio::time::Instant::now());
+            *next_request_time = request_time + self.interval;
+            request_time
+        };
+        tokio::time::sleep_until(request_time).await;
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// What we know about a page from the last time it's fetched.
+#[derive(Debug, Clone)]
+struct PageState {
+    validators: Validators,
+    ordinal: Ordinal,
+    /// Links to follow. Only extracted from HTML pages.
+    links: Arc<Vec<Url>>,
+}
+
+enum FetchResult {
+    NotModified,
+    NotFound,
+    Fetched {
+        url: Url,
+        mime_type: String,
+        content: bytes::Bytes,
+        validators: Validators,
+    },
+}
+
+fn normalize_url(mut url: Url) -> Url {
+    url.set_fragment(None);
+    url
+}
+
+/// Links in `href` attributes of `<a>` elements of an HTML page, resolved against `base_url`.
+fn extract_links(html: &str, base_url: &Url) -> Vec<Url> {
+    let mut parser = tree_sitter::Parser::new();
+    if parser
+        .set_language(&tree_sitter_html::LANGUAGE.into())
+        .is_err()
+    {
+        return vec![];
+    }
+    let Some(tree) = parser.parse(html, None) else {
+        return vec![];
+    };
+    let node_text = |node: tree_sitter::Node| node.utf8_text(html.as_bytes()).unwrap_or_default();
+
+    let mut links = Vec::new();
+    let mut cursor = tree.walk();
+    let mut nodes = vec![tree.root_node()];
+    while let Some(node) = nodes.pop() {
+        if node.kind() == "start_tag" || node.kind() == "self_closing_tag" {
+            let is_anchor = node
+                .named_child(0)
+                .is_some_and(|tag_name| node_text(tag_name).eq_ignore_ascii_case("a"));
+            if is_anchor {
+                for attr in node.named_children(&mut cursor) {
+                    if attr.kind() != "attribute" {
+                        continue;
+                    }
+                    let mut attr_cursor = attr.walk();
+                    let mut attr_children = attr.named_children(&mut attr_cursor);
+                    let Some(attr_name) = attr_children.next() else {
+                        continue;
+                    };
+                    if !node_text(attr_name).eq_ignore_ascii_case("href") {
+                        continue;
+                    }
+                    let Some(attr_value) = attr_children.next() else {
+                        continue;
+                    };
+                    let href = node_text(attr_value)
+                        .trim_matches(|c| c == '"' || c == '\'')
+                        .trim();
+                    if let Ok(url) = base_url.join(href) {
+                        links.push(normalize_url(url));
+                    }
+                }
+            }
+            continue;
+        }
+        // Reversed, so nodes are visited in document order.
+        let children = node.named_children(&mut cursor).collect::<Vec<_>>();
+        nodes.extend(children.into_iter().rev());
+    }
+    links
+}
+
+struct Crawler {
+    client: reqwest::Client,
+    user_agent: String,
+    start_urls: Vec<Url>,
+    allowed_domains: Vec<String>,
+    allowed_path_prefixes: Option<Vec<String>>,
+    max_depth: Option<usize>,
+    /// Rules of robots.txt by origin. `None` if robots.txt isn't respected.
+    robots_rules: Option<tokio::sync::Mutex<HashMap<String, Arc<RobotsRules>>>>,
+    rate_limiter: Option<RateLimiter>,
+    pages: Mutex<HashMap<String, PageState>>,
+}
+
+impl Crawler {
+    fn is_in_scope(&self, url: &Url) -> bool {
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let domain_allowed = self.allowed_domains.iter().any(|domain| {
+            host == domain
+                || host
+                    .strip_suffix(domain.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        });
+        domain_allowed
+            && self.allowed_path_prefixes.as_ref().is_none_or(|prefixes| {
+                prefixes
+                    .iter()
+                    .any(|prefix| url.path().starts_with(prefix.as_str()))
+            })
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+        Ok(request.send().await?)
+    }
+
+    async fn robots_rules_for(&self, url: &Url) -> Result<Option<Arc<RobotsRules>>> {
+        let Some(robots_rules) = &self.robots_rules else {
+            return Ok(None);
+        };
+        let origin = url.origin().ascii_serialization();
+        let mut robots_rules = robots_rules.lock().await;
+        if let Some(rules) = robots_rules.get(&origin) {
+            return Ok(Some(rules.clone()));
+        }
+        let robots_url = url.join("/robots.txt")?;
+        let resp = self.send(self.client.get(robots_url.clone())).await?;
+        let rules = if resp.status().is_success() {
+            RobotsRules::parse(&resp.text().await?, &self.user_agent)
+        } else if resp.status().is_client_error() {
+            // No robots.txt (or it isn't accessible) means no restriction.
+            RobotsRules::default()
+        } else {
+            bail!("Failed to fetch {robots_url}: {}", resp.status());
+        };
+        let rules = Arc::new(rules);
+        robots_rules.insert(origin, rules.clone());
+        Ok(Some(rules))
+    }
+
+    async fn is_allowed(&self, url: &Url) -> Result<bool> {
+        if !self.is_in_scope(url) {
+            return Ok(false);
+        }
+        let Some(robots_rules) = self.robots_rules_for(url).await? else {
+            return Ok(true);
+        };
+        let path = match url.query() {
+            Some(query) => format!("{}?{query}", url.path()),
+            None => url.path().to_string(),
+        };
+        Ok(robots_rules.is_allowed(&path))
+    }
+
+    /// Fetch a page. If `conditional`, validators of the last fetch are sent, so an unchanged page isn't downloaded.
+    async fn fetch(&self, url: &Url, conditional: bool) -> Result<FetchResult> {
+        let mut request = self.client.get(url.clone());
+        if conditional {
+            let validators = self
+                .pages
+                .lock()
+                .unwrap()
+                .get(url.as_str())
+                .map(|state| state.validators.clone());
+            if let Some(validators) = validators {
+                if let Some(etag) = validators.etag {
+                    request = request.header(IF_NONE_MATCH, etag);
+                }
+                if let Some(last_modified) = validators.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, last_modified);
+                }
+            }
+        }
+        let resp = self.send(request).await?;
+        match resp.status() {
+            StatusCode::NOT_MODIFIED => return Ok(FetchResult::NotModified),
+            StatusCode::NOT_FOUND | StatusCode::GONE => return Ok(FetchResult::NotFound),
+            status if !status.is_success() => bail!("Failed to fetch {url}: {status}"),
+            _ => {}
+        }
+        let header = |name| {
+            resp.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())
+        };
+        let mime_type = header(CONTENT_TYPE)
+            .and_then(|v| v.split(';').next().map(|v| v.trim().to_ascii_lowercase()))
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| DEFAULT_MIME_TYPE.to_string());
+        let validators = Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+        Ok(FetchResult::Fetched {
+            url: normalize_url(resp.url().clone()),
+            mime_type,
+            validators,
+            content: resp.bytes().await?,
+        })
+    }
+
+    /// Ordinal of a fetched page: the `Last-Modified` time if available.
+    /// An `ETag` alone carries no ordering, so such pages have no ordinal and their contents are compared instead.
+    fn ordinal_of(validators: &Validators) -> Ordinal {
+        validators
+            .last_modified
+            .as_ref()
+            .and_then(|v| chrono::DateTime::parse_from_rfc2822(v).ok())
+            .map_or(Ordinal::unavailable(), |last_modified| {
+                Ordinal(Some(last_modified.timestamp_micros()))
+            })
+    }
+
+    /// Fetch a page for listing, with conditional requests.
+    /// Returns `None` if the page (or the one it's redirected to) doesn't exist or isn't allowed.
+    async fn list_page(&self, url: &Url) -> Result<Option<(Url, PageState)>> {
+        if !self.is_allowed(url).await? {
+            debug!("Skipped disallowed URL: {url}");
+            return Ok(None);
+        }
+        let Some((final_url, state, _)) = self.fetch_page(url, true).await? else {
+            return Ok(None);
+        };
+        if final_url != *url && !self.is_allowed(&final_url).await? {
+            debug!("Skipped disallowed URL: {final_url}");
+            return Ok(None);
+        }
+        Ok(Some((final_url, state)))
+    }
+
+    /// Fetch a page and remember its state. Returns `None` if the page doesn't exist.
+    async fn fetch_page(
+        &self,
+        url: &Url,
+        conditional: bool,
+    ) -> Result<Option<(Url, PageState, Option<(String, bytes::Bytes)>)>> {
+        match self.fetch(url, conditional).await? {
+            FetchResult::NotModified => {
+                let state = self.pages.lock().unwrap().get(url.as_str()).cloned();
+                match state {
+                    Some(state) => Ok(Some((url.clone(), state, None))),
+                    None => bail!("Unexpected 304 Not Modified response for {url}"),
+                }
+            }
+            FetchResult::NotFound => {
+                self.pages.lock().unwrap().remove(url.as_str());
+                Ok(None)
+            }
+            FetchResult::Fetched {
+                url: final_url,
+                mime_type,
+                content,
+                validators,
+            } => {
+                let links = if mime_type == HTML_MIME_TYPE {
+                    extract_links(&String::from_utf8_lossy(&content), &final_url)
+                } else {
+                    vec![]
+                };
+                let state = PageState {
+                    ordinal: Self::ordinal_of(&validators),
+                    validators,
+                    links: Arc::new(links),
+                };
+                self.pages
+                    .lock()
+                    .unwrap()
+                    .insert(final_url.to_string(), state.clone());
+                Ok(Some((final_url, state, Some((mime_type, content)))))
+            }
+        }
+    }
+}
+
+struct Executor {
+    crawler: Crawler,
+    binary: bool,
+}
+
+#[async_trait]
+impl SourceExecutor for Executor {
+    fn list<'a>(
+        &'a self,
+        options: &'a SourceExecutorListOptions,
+    ) -> BoxStream<'a, Result<Vec<PartialSourceRowMetadata>>> {
+        let crawler = &self.crawler;
+        try_stream! {
+            let mut visited = HashSet::new();
+            let mut queue = VecDeque::new();
+            for url in crawler.start_urls.iter() {
+                if visited.insert(url.to_string()) {
+                    queue.push_back((url.clone(), 0));
+                }
+            }
+            while let Some((url, depth)) = queue.pop_front() {
+                let page = match crawler.list_page(&url).await {
+                    Ok(page) => page,
+                    Err(e) => {
+                        // Pages missing from the listing are considered deleted, so a page failing to fetch
+                        // is kept as last seen. The listing fails if there's no such state.
+                        let last_state =
+                            crawler.pages.lock().unwrap().get(url.as_str()).cloned();
+                        match last_state {
+                            Some(state) => {
+                                warn!(
+                                    "Failed to fetch {url}, keeping its last known state: {e:?}"
+                                );
+                                Some((url.clone(), state))
+                            }
+                            None => Err(e)?,
+                        }
+                    }
+                };
+                let Some((final_url, state)) = page else {
+                    continue;
+                };
+                if final_url != url && !visited.insert(final_url.to_string()) {
+                    continue;
+                }
+                if crawler.max_depth.is_none_or(|max_depth| depth < max_depth) {
+                    for link in state.links.iter() {
+                        if crawler.is_in_scope(link) && visited.insert(link.to_string()) {
+                            queue.push_back((link.clone(), depth + 1));
+                        }
+                    }
+                }
+                yield vec![PartialSourceRowMetadata {
+                    key: KeyValue::Str(final_url.as_str().into()),
+                    ordinal: options.include_ordinal.then_some(state.ordinal),
+                }];
+            }
+        }
+        .boxed()
+    }
+
+    async fn get_value(
+        &self,
+        key: &KeyValue,
+        options: &SourceExecutorGetOptions,
+    ) -> Result<PartialSourceRowData> {
+        let url = Url::parse(key.str_value()?)?;
+        let page = if self.crawler.is_allowed(&url).await? {
+            // The content is needed anyway if the value is requested, so only send conditional requests otherwise.
+            self.crawler
+                .fetch_page(&url, !options.include_value)
+                .await?
+        } else {
+            None
+        };
+        let Some((_, state, content)) = page else {
+            return Ok(PartialSourceRowData {
+                value: Some(SourceValue::NonExistence),
+                ordinal: Some(Ordinal::unavailable()),
+            });
+        };
+        let value = match content {
+            Some((mime_type, content)) if options.include_value => {
+                let fields = vec![
+                    mime_type.into(),
+                    if self.binary {
+                        content.to_vec().into()
+                    } else {
+                        String::from_utf8_lossy(&content).to_string().into()
+                    },
+                ];
+                Some(SourceValue::Existence(FieldValues { fields }))
+            }
+            _ => None,
+        };
+        Ok(PartialSourceRowData {
+            value,
+            ordinal: options.include_ordinal.then_some(state.ordinal),
+        })
+    }
+}
+
+pub struct Factory;
+
+impl Factory {
+    fn build_crawler(&self, spec: Spec) -> Result<Crawler> {
+        let start_urls = spec
+            .start_urls
+            .iter()
+            .map(|url| {
+                Url::parse(url)
+                    .map(normalize_url)
+                    .map_err(|e| api_error!("Invalid start URL `{url}`: {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if start_urls.is_empty() {
+            api_bail!("`start_urls` must not be empty");
+        }
+        let allowed_domains = match spec.allowed_domains {
+            Some(domains) => domains
+                .into_iter()
+                .map(|domain| domain.to_ascii_lowercase())
+                .collect(),
+            None => start_urls
+                .iter()
+                .filter_map(|url| url.host_str().map(|host| host.to_string()))
+                .collect(),
+        };
+        let user_agent = spec
+            .user_agent
+            .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string());
+        Ok(Crawler {
+            client: reqwest::Client::builder()
+                .user_agent(user_agent.clone())
+                .build()?,
+            user_agent,
+            start_urls,
+            allowed_domains,
+            allowed_path_prefixes: spec.allowed_path_prefixes,
+            max_depth: spec.max_depth,
+            robots_rules: spec
+                .respect_robots_txt
+                .then(|| tokio::sync::Mutex::new(HashMap::new())),
+            rate_limiter: spec.min_request_interval.map(RateLimiter::new),
+            pages: Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+#[async_trait]
+impl SourceFactoryBase for Factory {
+    type Spec = Spec;
+
+    fn name(&self) -> &str {
+        "WebCrawler"
+    }
+
+    async fn get_output_schema(
+        &self,
+        spec: &Spec,
+        _context: &FlowInstanceContext,
+    ) -> Result<EnrichedValueType> {
+        let mut struct_schema = StructSchema::default();
+        let mut schema_builder = StructSchemaBuilder::new(&mut struct_schema);
+        schema_builder.add_field(FieldSchema::new(
+            "url",
+            make_output_type(BasicValueType::Str),
+        ));
+        let mime_type_field = schema_builder.add_field(FieldSchema::new(
+            "mime_type",
+            make_output_type(BasicValueType::Str),
+        ));
+        schema_builder.add_field(FieldSchema::new(
+            "content",
+            make_output_type(if spec.binary {
+                BasicValueType::Bytes
+            } else {
+                BasicValueType::Str
+            })
+            .with_attr(
+                field_attrs::CONTENT_MIME_TYPE,
+                serde_json::to_value(mime_type_field.to_field_ref())?,
+            ),
+        ));
+        Ok(make_output_type(TableSchema::new(
+            TableKind::KTable,
+            struct_schema,
+        )))
+    }
+
+    async fn build_executor(
+        self: Arc<Self>,
+        spec: Spec,
+        _context: Arc<FlowInstanceContext>,
+    ) -> Result<Box<dyn SourceExecutor>> {
+        let binary = spec.binary;
+        Ok(Box::new(Executor {
+            crawler: self.build_crawler(spec)?,
+            binary,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderMap, StatusCode as HttpStatusCode};
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_robots_rules() {
+        let rules = RobotsRules::parse(
+            "User-agent: *\n\
+             Disallow: /private\n\
+             Allow: /private/public\n\
+             Disallow: /*.pdf$\n\
+             \n\
+             User-agent: OtherBot\n\
+             Disallow: /\n",
+            "cocoindex/1.0",
+        );
+        assert!(rules.is_allowed("/docs/intro"));
+        assert!(!rules.is_allowed("/private/secret"));
+        assert!(rules.is_allowed("/private/public/page"));
+        assert!(!rules.is_allowed("/files/manual.pdf"));
+        assert!(rules.is_allowed("/files/manual.pdf.html"));
+
+        let rules = RobotsRules::parse(
+            "User-agent: *\nDisallow: /\n\nUser-agent: cocoindex\nDisallow: /tmp\n",
+            "cocoindex",
+        );
+        assert!(rules.is_allowed("/docs"));
+        assert!(!rules.is_allowed("/tmp/x"));
+    }
+
+    #[test]
+    fn test_extract_links() {
+        let base_url = Url::parse("https://example.com/docs/").unwrap();
+        let links = extract_links(
+            r#"<html><body>
+                <a href="intro.html#setup">Intro</a>
+                <A HREF='/api/'>API</A>
+                <link href="style.css">
+                <a name="anchor">No link</a>
+                <a href=https://other.com/page>Other</a>
+            </body></html>"#,
+            &base_url,
+        );
+        assert_eq!(
+            links.iter().map(|url| url.as_str()).collect::<Vec<_>>(),
+            vec![
+                "https://example.com/docs/intro.html",
+                "https://example.com/api/",
+                "https://other.com/page",
+            ]
+        );
+    }
+
+    fn crawler_spec(start_url: String) -> Spec {
+        Spec {
+            start_urls: vec![start_url],
+            allowed_domains: None,
+            allowed_path_prefixes: Some(vec!["/docs/".to_string()]),
+            max_depth: Some(1),
+            binary: false,
+            respect_robots_txt: true,
+            min_request_interval: Some(Duration::from_millis(1)),
+            user_agent: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_crawl_local_server() {
+        let not_modified_count = Arc::new(AtomicUsize::new(0));
+        let broken_fetch_count = Arc::new(AtomicUsize::new(0));
+        let app = axum::Router::new()
+            .route(
+                "/robots.txt",
+                get(|| async { "User-agent: *\nDisallow: /docs/private\n" }),
+            )
+            .route(
+                "/docs/",
+                get(|| async {
+                    (
+                        [("content-type", "text/html; charset=utf-8")],
+                        r#"<a href="a">A</a> <a href="b.txt#top">B</a> <a href="broken">Broken</a>
+                           <a href="/docs/private/x">Private</a> <a href="/outside">Outside</a>
+                           <a href="https://other.example.com/docs/">Other</a>"#,
+                    )
+                }),
+            )
+            .route(
+                "/docs/a",
+                get({
+                    let not_modified_count = not_modified_count.clone();
+                    move |headers: HeaderMap| async move {
+                        if headers.get("if-none-match").is_some_and(|v| v == "\"v1\"") {
+                            not_modified_count.fetch_add(1, Ordering::SeqCst);
+                            return HttpStatusCode::NOT_MODIFIED.into_response();
+                        }
+                        (
+                            [("content-type", "text/html"), ("etag", "\"v1\"")],
+                            r#"<a href="/docs/deeper">Too deep</a>"#,
+                        )
+                            .into_response()
+                    }
+                }),
+            )
+            .route(
+                "/docs/broken",
+                get({
+                    let broken_fetch_count = broken_fetch_count.clone();
+                    // Only the first fetch succeeds.
+                    move || async move {
+                        if broken_fetch_count.fetch_add(1, Ordering::SeqCst) > 0 {
+                            return HttpStatusCode::INTERNAL_SERVER_ERROR.into_response();
+                        }
+                        (
+                            [
+                                ("content-type", "text/plain"),
+                                ("last-modified", "Thu, 22 Oct 2015 07:28:00 GMT"),
+                            ],
+                            "was fine",
+                        )
+                            .into_response()
+                    }
+                }),
+            )
+            .route(
+                "/docs/b.txt",
+                get(|| async {
+                    (
+                        [
+                            ("content-type", "text/plain"),
+                            ("last-modified", "Wed, 21 Oct 2015 07:28:00 GMT"),
+                        ],
+                        "plain text",
+                    )
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let executor = Executor {
+            crawler: Factory
+                .build_crawler(crawler_spec(format!("{base_url}/docs/")))
+                .unwrap(),
+            binary: false,
+        };
+        let list_rows = || async {
+            let mut rows = Vec::new();
+            let mut stream = executor.list(&SourceExecutorListOptions {
+                include_ordinal: true,
+            });
+            while let Some(batch) = stream.next().await {
+                for row in batch.unwrap() {
+                    rows.push((row.key.str_value().unwrap().to_string(), row.ordinal));
+                }
+            }
+            rows
+        };
+
+        let rows = list_rows().await;
+        assert_eq!(
+            rows.iter().map(|(url, _)| url.as_str()).collect::<Vec<_>>(),
+            vec![
+                format!("{base_url}/docs/"),
+                format!("{base_url}/docs/a"),
+                format!("{base_url}/docs/b.txt"),
+                format!("{base_url}/docs/broken"),
+            ]
+        );
+        // Pages without `Last-Modified` have no ordinal, even with an `ETag`.
+        assert_eq!(rows[0].1, Some(Ordinal::unavailable()));
+        assert_eq!(rows[1].1, Some(Ordinal::unavailable()));
+        assert_eq!(rows[2].1, Some(Ordinal(Some(1_445_412_480_000_000))));
+
+        // Unchanged pages are validated by conditional requests, keeping their ordinals.
+        // Pages failing to fetch are kept as last seen, instead of being considered deleted.
+        assert_eq!(list_rows().await, rows);
+        assert_eq!(not_modified_count.load(Ordering::SeqCst), 1);
+        assert_eq!(broken_fetch_count.load(Ordering::SeqCst), 2);
+
+        let data = executor
+            .get_value(
+                &KeyValue::Str(format!("{base_url}/docs/b.txt").into()),
+                &SourceExecutorGetOptions {
+                    include_value: true,
+                    include_ordinal: true,
+                },
+            )
+            .await
+            .unwrap();
+        let Some(SourceValue::Existence(values)) = data.value else {
+            panic!("expected an existing page");
+        };
+        assert_eq!(
+            values.fields,
+            vec![
+                Value::Basic(BasicValue::Str("text/plain".into())),
+                Value::Basic(BasicValue::Str("plain text".into())),
+            ]
+        );
+
+        // Without a last known state, the listing fails.
+        let fresh_executor = Executor {
+            crawler: Factory
+                .build_crawler(crawler_spec(format!("{base_url}/docs/broken")))
+                .unwrap(),
+            binary: false,
+        };
+        let mut stream = fresh_executor.list(&SourceExecutorListOptions {
+            include_ordinal: true,
+        });
+        assert!(stream.next().await.unwrap().is_err());
+        drop(stream);
+
+        assert!(
+            executor
+                .get_value(
+                    &KeyValue::Str(format!("{base_url}/docs/broken").into()),
+                    &SourceExecutorGetOptions {
+                        include_value: true,
+                        include_ordinal: true,
+                    },
+                )
+                .await
+                .is_err()
+        );
+
+        for missing_url in [
+            format!("{base_url}/docs/missing"),
+            format!("{base_url}/docs/private/x"),
+        ] {
+            let data = executor
+                .get_value(
+                    &KeyValue::Str(missing_url.into()),
+                    &SourceExecutorGetOptions {
+                        include_value: true,
+                        include_ordinal: true,
+                    },
+                )
+                .await
+                .unwrap();
+            assert!(matches!(data.value, Some(SourceValue::NonExistence)));
+        }
+    }
+}
@@ -5,13 +5,14 @@ use super::shared::table_columns::{
 };
 use crate::base::spec::{self, *};
 use crate::ops::sdk::*;
+use crate::ops::shared::postgres::get_db_pool;
 use crate::settings::DatabaseConnectionSpec;
 use async_trait::async_trait;
 use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
 use serde::Serialize;
-use sqlx::PgPool;
 use sqlx::postgres::types::PgRange;
+use sqlx::{PgPool, Row};
 use std::ops::Bound;
 
 #[derive(Debug, Deserialize)]
@@ -177,6 +178,7 @@ fn bind_value_field<'arg>(
 pub struct ExportContext {
     db_ref: Option<spec::AuthEntryReference<DatabaseConnectionSpec>>,
     db_pool: PgPool,
+    table_name: String,
     key_fields_schema: Vec<FieldSchema>,
     value_fields_schema: Vec<FieldSchema>,
     upsert_sql_prefix: String,
@@ -220,6 +222,7 @@ impl ExportContext {
                 " ON CONFLICT ({key_fields}) DO UPDATE SET {set_value_fields};"
             ),
             delete_sql_prefix: format!("DELETE FROM {table_name} WHERE "),
+            table_name,
         })
     }
 }
@@ -300,6 +303,92 @@ impl ExportContext {
     }
 }
 
+fn is_pgvector_field(field_schema: &FieldSchema) -> bool {
+    matches!(&field_schema.value_type.typ, ValueType::Basic(BasicValueType::Vector(vs)) if convertible_to_pgvector(vs))
+}
+
+#[async_trait]
+impl QueryTarget for ExportContext {
+    fn default_vector_field_name(&self) -> Result<String> {
+        match self
+            .value_fields_schema
+            .iter()
+            .filter(|f| is_pgvector_field(f))
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            [field] => Ok(field.name.clone()),
+            [] => api_bail!("No vector field found in table `{}`", self.table_name),
+            _ => api_bail!(
+                "Multiple vector fields found in table `{}`, please specify the one to search on",
+                self.table_name
+            ),
+        }
+    }
+
+    async fn search(&self, query: VectorMatchQuery) -> Result<QueryResults> {
+        let vector_field = self
+            .value_fields_schema
+            .iter()
+            .find(|f| f.name == query.vector_field_name)
+            .ok_or_else(|| api_error!("Field `{}` not found", query.vector_field_name))?;
+        if !is_pgvector_field(vector_field) {
+            api_bail!(
+                "Field `{}` is not a vector field with fixed dimension",
+                vector_field.name
+            );
+        }
+        let distance_sql = format!(
+            "\"{}\" {} $1",
+            vector_field.name,
+            to_distance_operator_sql(query.similarity_metric)
+        );
+        let score_sql = match query.similarity_metric {
+            VectorSimilarityMetric::CosineSimilarity => format!("1 - ({distance_sql})"),
+            VectorSimilarityMetric::L2Distance => distance_sql.clone(),
+            VectorSimilarityMetric::InnerProduct => format!("-({distance_sql})"),
+        };
+        let fields: Vec<FieldSchema> = self
+            .key_fields_schema
+            .iter()
+            .chain(
+                self.value_fields_schema
+                    .iter()
+                    .filter(|f| f.name != vector_field.name),
+            )
+            .cloned()
+            .collect();
+        let query_str = format!(
+            "SELECT {}, ({score_sql})::float8 FROM {} ORDER BY {distance_sql} LIMIT $2",
+            fields
+                .iter()
+                .map(|f| format!("to_jsonb(\"{}\")", f.name))
+                .join(", "),
+            self.table_name,
+        );
+        let rows = sqlx::query(&query_str)
+            .bind(pgvector::Vector::from(query.vector))
+            .bind(query.limit as i64)
+            .fetch_all(&self.db_pool)
+            .await?;
+        let results = rows
+            .into_iter()
+            .map(|row| {
+                let data = (0..fields.len())
+                    .map(|i| {
+                        Ok(row
+                            .try_get::<Option<serde_json::Value>, _>(i)?
+                            .unwrap_or(serde_json::Value::Null))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let score: f64 = row.try_get(fields.len())?;
+                Ok(QueryResult { data, score })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(QueryResults { fields, results })
+    }
+}
+
 #[derive(Default)]
 pub struct Factory {}
 
@@ -482,6 +571,14 @@ fn to_vector_similarity_metric_sql(metric: VectorSimilarityMetric) -> &'static s
     }
 }
 
+fn to_distance_operator_sql(metric: VectorSimilarityMetric) -> &'static str {
+    match metric {
+        VectorSimilarityMetric::CosineSimilarity => "<=>",
+        VectorSimilarityMetric::L2Distance => "<->",
+        VectorSimilarityMetric::InnerProduct => "<#>",
+    }
+}
+
 fn to_index_spec_sql(index_spec: &VectorIndexDef) -> Cow<'static, str> {
     format!(
         "USING hnsw ({} {})",
@@ -596,22 +693,6 @@ impl SetupStatus {
     }
 }
 
-async fn get_db_pool(
-    db_ref: Option<&spec::AuthEntryReference<DatabaseConnectionSpec>>,
-    auth_registry: &AuthRegistry,
-) -> Result<PgPool> {
-    let lib_context = get_lib_context()?;
-    let db_conn_spec = db_ref
-        .as_ref()
-        .map(|db_ref| auth_registry.get(db_ref))
-        .transpose()?;
-    let db_pool = match db_conn_spec {
-        Some(db_conn_spec) => lib_context.db_pools.get_pool(&db_conn_spec).await?,
-        None => lib_context.require_builtin_db_pool()?.clone(),
-    };
-    Ok(db_pool)
-}
-
 #[async_trait]
 impl StorageFactoryBase for Factory {
     type Spec = Spec;
@@ -750,4 +831,11 @@ impl StorageFactoryBase for Factory {
         }
         Ok(())
     }
+
+    fn build_query_target(
+        &self,
+        export_context: Arc<ExportContext>,
+    ) -> Result<Option<Arc<dyn QueryTarget>>> {
+        Ok(Some(export_context))
+    }
 }
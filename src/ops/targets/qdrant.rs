@@ -8,8 +8,8 @@ use crate::setup;
 use qdrant_client::Qdrant;
 use qdrant_client::qdrant::{
     CreateCollectionBuilder, DeletePointsBuilder, Distance, NamedVectors, PointId, PointStruct,
-    PointsIdsList, UpsertPointsBuilder, Value as QdrantValue, VectorParamsBuilder,
-    VectorsConfigBuilder,
+    PointsIdsList, Query, QueryPointsBuilder, UpsertPointsBuilder, Value as QdrantValue,
+    VectorParamsBuilder, VectorsConfigBuilder, point_id::PointIdOptions,
 };
 
 const DEFAULT_VECTOR_SIMILARITY_METRIC: spec::VectorSimilarityMetric =
@@ -178,7 +178,9 @@ impl SetupStatus {
 struct ExportContext {
     qdrant_client: Arc<Qdrant>,
     collection_name: String,
+    key_field_schema: schema::FieldSchema,
     fields_info: Vec<FieldInfo>,
+    vectors: BTreeMap<String, VectorDef>,
 }
 
 impl ExportContext {
@@ -216,6 +218,86 @@ impl ExportContext {
         Ok(())
     }
 }
+
+#[async_trait]
+impl QueryTarget for ExportContext {
+    fn default_vector_field_name(&self) -> Result<String> {
+        match self.vectors.keys().collect::<Vec<_>>().as_slice() {
+            [name] => Ok((*name).clone()),
+            [] => api_bail!("No vector found in collection `{}`", self.collection_name),
+            _ => api_bail!(
+                "Multiple vectors found in collection `{}`, please specify the one to search on",
+                self.collection_name
+            ),
+        }
+    }
+
+    async fn search(&self, query: VectorMatchQuery) -> Result<QueryResults> {
+        let vector_def = self.vectors.get(&query.vector_field_name).ok_or_else(|| {
+            api_error!(
+                "Vector `{}` not found in collection `{}`",
+                query.vector_field_name,
+                self.collection_name
+            )
+        })?;
+        if vector_def.metric != query.similarity_metric {
+            api_bail!(
+                "Vector `{}` is indexed with metric {}, cannot search with metric {}",
+                query.vector_field_name,
+                vector_def.metric,
+                query.similarity_metric
+            );
+        }
+        let response = self
+            .qdrant_client
+            .query(
+                QueryPointsBuilder::new(&self.collection_name)
+                    .query(Query::new_nearest(query.vector))
+                    .using(&query.vector_field_name)
+                    .limit(query.limit as u64)
+                    .with_payload(true),
+            )
+            .await?;
+
+        let payload_fields: Vec<&schema::FieldSchema> = self
+            .fields_info
+            .iter()
+            .filter(|f| !f.is_qdrant_vector)
+            .map(|f| &f.field_schema)
+            .collect();
+        let results = response
+            .result
+            .into_iter()
+            .map(|mut point| {
+                let key = match point.id.and_then(|id| id.point_id_options) {
+                    Some(PointIdOptions::Num(v)) => serde_json::Value::from(v),
+                    Some(PointIdOptions::Uuid(v)) => serde_json::Value::String(v),
+                    None => serde_json::Value::Null,
+                };
+                let data = std::iter::once(key)
+                    .chain(payload_fields.iter().map(|f| {
+                        point
+                            .payload
+                            .remove(&f.name)
+                            .map(|v| v.into_json())
+                            .unwrap_or(serde_json::Value::Null)
+                    }))
+                    .collect();
+                QueryResult {
+                    data,
+                    score: point.score as f64,
+                }
+            })
+            .collect();
+        Ok(QueryResults {
+            fields: std::iter::once(self.key_field_schema.clone())
+                .chain(payload_fields.into_iter().cloned())
+                .collect(),
+            results,
+        })
+    }
+}
+
 fn key_to_point_id(key_value: &KeyValue) -> Result<PointId> {
     let point_id = match key_value {
         KeyValue::Str(v) => PointId::from(v.to_string()),
@@ -363,7 +445,9 @@ impl StorageFactoryBase for Factory {
                     qdrant_client: self
                         .get_qdrant_client(&d.spec.connection, &context.auth_registry)?,
                     collection_name: d.spec.collection_name.clone(),
+                    key_field_schema: d.key_fields_schema[0].clone(),
                     fields_info,
+                    vectors: vector_def.clone(),
                 });
                 Ok(TypedExportDataCollectionBuildOutput {
                     export_context: Box::pin(async move { Ok(export_context) }),
@@ -477,6 +561,13 @@ impl StorageFactoryBase for Factory {
         }
         Ok(())
     }
+
+    fn build_query_target(
+        &self,
+        export_context: Arc<ExportContext>,
+    ) -> Result<Option<Arc<dyn QueryTarget>>> {
+        Ok(Some(export_context))
+    }
 }
 
 impl Factory {
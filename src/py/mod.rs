@@ -109,6 +109,24 @@ impl IndexUpdateInfo {
     }
 }
 
+#[pyclass]
+pub struct DryRunReport(pub execution::dry_run::DryRunReport);
+
+#[pymethods]
+impl DryRunReport {
+    pub fn __str__(&self) -> String {
+        format!("{}", self.0)
+    }
+
+    pub fn __repr__(&self) -> String {
+        self.__str__()
+    }
+
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        pythonize::pythonize(py, &self.0).into_py_result()
+    }
+}
+
 #[pyclass]
 pub struct Flow(pub Arc<FlowContext>);
 
@@ -217,6 +235,29 @@ impl Flow {
         })
     }
 
+    pub fn dry_run_update<'py>(
+        &self,
+        py: Python<'py>,
+        options: Pythonized<execution::dry_run::DryRunOptions>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let flow_ctx = self.0.clone();
+        future_into_py(py, async move {
+            let lib_context = get_lib_context().into_py_result()?;
+            let plan = flow_ctx.flow.get_execution_plan().await.into_py_result()?;
+            let execution_ctx = flow_ctx.use_execution_ctx().await.into_py_result()?;
+            let report = execution::dry_run::dry_run_update(
+                &plan,
+                &execution_ctx.setup_execution_context,
+                &flow_ctx.flow.data_schema,
+                options.into_inner(),
+                lib_context.require_builtin_db_pool().into_py_result()?,
+            )
+            .await
+            .into_py_result()?;
+            Ok(DryRunReport(report))
+        })
+    }
+
     #[pyo3(signature = (output_mode=None))]
     pub fn get_spec(&self, output_mode: Option<Pythonized<OutputMode>>) -> PyResult<RenderedSpec> {
         let mode = output_mode.map_or(OutputMode::Concise, |m| m.into_inner());
@@ -390,6 +431,73 @@ impl TransientFlow {
     }
 }
 
+#[pyclass]
+pub struct SimpleSemanticsQueryHandler(pub Arc<execution::query::SimpleSemanticsQueryHandler>);
+
+#[pymethods]
+impl SimpleSemanticsQueryHandler {
+    /// Build a query handler and register it to the flow under `name`.
+    #[new]
+    pub fn new(
+        py: Python<'_>,
+        name: String,
+        flow: &Flow,
+        target_name: &str,
+        query_transform_flow: &TransientFlow,
+        default_similarity_metric: Pythonized<spec::VectorSimilarityMetric>,
+    ) -> PyResult<Self> {
+        let spec = spec::SimpleSemanticsQueryHandlerSpec {
+            name: name.clone(),
+            flow_instance_name: flow.name().to_string(),
+            export_target_name: target_name.to_string(),
+            query_transform_flow: query_transform_flow.0.transient_flow_instance.clone(),
+            default_similarity_metric: default_similarity_metric.into_inner(),
+        };
+        let handler = py.allow_threads(|| {
+            get_runtime()
+                .block_on(execution::query::SimpleSemanticsQueryHandler::new(
+                    &flow.0.flow,
+                    query_transform_flow.0.clone(),
+                    spec,
+                ))
+                .into_py_result()
+        })?;
+        let handler = Arc::new(handler);
+        let mut query_handlers = flow.0.query_handlers.write().unwrap();
+        if query_handlers.contains_key(&name) {
+            return Err(PyException::new_err(format!(
+                "query handler name already exists: {name}"
+            )));
+        }
+        query_handlers.insert(name, handler.clone());
+        Ok(Self(handler))
+    }
+
+    #[pyo3(signature = (query, limit, vector_field_name=None, similarity_metric=None))]
+    pub fn search_async<'py>(
+        &self,
+        py: Python<'py>,
+        query: String,
+        limit: u32,
+        vector_field_name: Option<String>,
+        similarity_metric: Option<Pythonized<spec::VectorSimilarityMetric>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let handler = self.0.clone();
+        future_into_py(py, async move {
+            let response = handler
+                .search(
+                    query,
+                    limit,
+                    vector_field_name,
+                    similarity_metric.map(|m| m.into_inner()),
+                )
+                .await
+                .into_py_result()?;
+            Python::with_gil(|py| Ok(Pythonized(response).into_pyobject(py)?.unbind()))
+        })
+    }
+}
+
 #[pyclass]
 pub struct SetupChangeBundle(Arc<setup::SetupChangeBundle>);
 
@@ -502,7 +610,9 @@ fn cocoindex_engine(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<Flow>()?;
     m.add_class::<FlowLiveUpdater>()?;
     m.add_class::<TransientFlow>()?;
+    m.add_class::<SimpleSemanticsQueryHandler>()?;
     m.add_class::<IndexUpdateInfo>()?;
+    m.add_class::<DryRunReport>()?;
     m.add_class::<SetupChangeBundle>()?;
     m.add_class::<PyOpArgSchema>()?;
     m.add_class::<RenderedSpec>()?;
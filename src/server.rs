@@ -41,6 +41,7 @@ pub async fn init_server(
             "/cocoindex",
             routing::get(|| async { "CocoIndex is running!" }),
         )
+        .route("/metrics", routing::get(service::metrics::get_metrics))
         .nest(
             "/cocoindex/api",
             Router::new()
@@ -69,6 +70,22 @@ pub async fn init_server(
                     "/flows/{flowInstName}/update",
                     routing::post(service::flows::update),
                 )
+                .route(
+                    "/flows/{flowInstName}/update/dryRun",
+                    routing::post(service::flows::dry_run_update),
+                )
+                .route(
+                    "/flows/{flowInstName}/errors",
+                    routing::get(service::flows::get_row_failures),
+                )
+                .route(
+                    "/flows/{flowInstName}/errors/retry",
+                    routing::post(service::flows::retry_row_failures),
+                )
+                .route(
+                    "/flows/{flowInstName}/search",
+                    routing::get(service::flows::search),
+                )
                 .layer(
                     ServiceBuilder::new()
                         .layer(TraceLayer::new_for_http())
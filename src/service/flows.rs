@@ -1,6 +1,8 @@
 use crate::prelude::*;
 
-use crate::execution::{evaluator, indexing_status, memoization, row_indexer, stats};
+use crate::execution::{
+    dry_run, evaluator, indexing_status, memoization, query, row_indexer, stats,
+};
 use crate::lib_context::LibContext;
 use crate::{base::schema::FlowSchema, ops::interface::SourceExecutorListOptions};
 use axum::{
@@ -231,6 +233,25 @@ pub async fn update(
     Ok(Json(live_updater.index_update_info()))
 }
 
+pub async fn dry_run_update(
+    Path(flow_name): Path<String>,
+    Query(options): Query<dry_run::DryRunOptions>,
+    State(lib_context): State<Arc<LibContext>>,
+) -> Result<Json<dry_run::DryRunReport>, ApiError> {
+    let flow_ctx = lib_context.get_flow_context(&flow_name)?;
+    let plan = flow_ctx.flow.get_execution_plan().await?;
+    let execution_ctx = flow_ctx.use_execution_ctx().await?;
+    let report = dry_run::dry_run_update(
+        &plan,
+        &execution_ctx.setup_execution_context,
+        &flow_ctx.flow.data_schema,
+        options,
+        lib_context.require_builtin_db_pool()?,
+    )
+    .await?;
+    Ok(Json(report))
+}
+
 pub async fn get_row_indexing_status(
     Path(flow_name): Path<String>,
     Query(query): Query<SourceRowKeyParams>,
@@ -248,3 +269,86 @@ pub async fn get_row_indexing_status(
     .await?;
     Ok(Json(indexing_status))
 }
+
+pub async fn get_row_failures(
+    Path(flow_name): Path<String>,
+    State(lib_context): State<Arc<LibContext>>,
+) -> Result<Json<Vec<indexing_status::SourceRowFailure>>, ApiError> {
+    let flow_ctx = lib_context.get_flow_context(&flow_name)?;
+    let execution_ctx = flow_ctx.use_execution_ctx().await?;
+    let failures = indexing_status::list_source_row_failures(
+        &flow_ctx.flow,
+        &execution_ctx.setup_execution_context,
+        lib_context.require_builtin_db_pool()?,
+    )
+    .await?;
+    Ok(Json(failures))
+}
+
+pub async fn retry_row_failures(
+    Path(flow_name): Path<String>,
+    State(lib_context): State<Arc<LibContext>>,
+) -> Result<Json<stats::IndexUpdateInfo>, ApiError> {
+    let flow_ctx = lib_context.get_flow_context(&flow_name)?;
+    let pool = lib_context.require_builtin_db_pool()?;
+    let execution_ctx = flow_ctx.use_execution_ctx().await?;
+    let mut sources = Vec::new();
+    for (source_idx, import_op) in flow_ctx.flow.flow_instance.import_ops.iter().enumerate() {
+        let update_stats = Arc::new(stats::UpdateStats::default());
+        execution_ctx
+            .get_source_indexing_context(&flow_ctx.flow, source_idx, pool)
+            .await?
+            .retry_failed_rows(pool, &update_stats)
+            .await?;
+        sources.push(stats::SourceUpdateInfo {
+            source_name: import_op.name.clone(),
+            stats: (*update_stats).clone(),
+        });
+    }
+    Ok(Json(stats::IndexUpdateInfo {
+        sources,
+        ops: Vec::new(),
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct SearchParams {
+    handler: Option<String>,
+    q: String,
+    limit: Option<u32>,
+    field: Option<String>,
+    metric: Option<spec::VectorSimilarityMetric>,
+}
+
+pub async fn search(
+    Path(flow_name): Path<String>,
+    Query(query): Query<SearchParams>,
+    State(lib_context): State<Arc<LibContext>>,
+) -> Result<Json<query::SimpleSemanticsQueryResponse>, ApiError> {
+    let flow_ctx = lib_context.get_flow_context(&flow_name)?;
+    let query_handler = match &query.handler {
+        Some(handler) => flow_ctx.get_query_handler(handler)?,
+        None => {
+            let query_handlers = flow_ctx.query_handlers.read().unwrap();
+            if query_handlers.len() != 1 {
+                return Err(ApiError::new(
+                    &format!(
+                        "Flow `{flow_name}` has {} query handlers, please specify one with `handler`",
+                        query_handlers.len()
+                    ),
+                    StatusCode::BAD_REQUEST,
+                ));
+            }
+            query_handlers.values().next().unwrap().clone()
+        }
+    };
+    let response = query_handler
+        .search(
+            query.q,
+            query.limit.unwrap_or(10),
+            query.field,
+            query.metric,
+        )
+        .await?;
+    Ok(Json(response))
+}
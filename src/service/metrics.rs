@@ -0,0 +1,13 @@
+use crate::prelude::*;
+
+use crate::metrics;
+use axum::{http::header, response::IntoResponse};
+
+use super::error::ApiError;
+
+pub async fn get_metrics() -> Result<impl IntoResponse, ApiError> {
+    Ok((
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        metrics::encode_text()?,
+    ))
+}
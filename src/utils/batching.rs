@@ -0,0 +1,208 @@
+use crate::prelude::*;
+
+use crate::llm;
+use crate::service::error::{SharedError, SharedResultExt};
+use tokio::sync::oneshot;
+
+#[async_trait]
+pub trait Runner: Send + Sync + 'static {
+    type Input: Clone + Send + 'static;
+    type Output: Send + 'static;
+
+    /// Returns one output for each input, in the same order.
+    async fn run(&self, inputs: Vec<Self::Input>) -> Result<Vec<Self::Output>>;
+}
+
+type OutputSender<R> = oneshot::Sender<Result<<R as Runner>::Output, SharedError>>;
+
+struct BatcherState<R: Runner> {
+    pending: Vec<(R::Input, OutputSender<R>)>,
+    running: bool,
+}
+
+/// Coalesces concurrent calls into batches.
+/// A call is run right away if no batch is running; otherwise it waits, together with other
+/// calls arriving in the meantime, and all of them are run as the next batch.
+pub struct Batcher<R: Runner> {
+    runner: Arc<R>,
+    max_batch_size: Option<usize>,
+    state: Arc<Mutex<BatcherState<R>>>,
+}
+
+impl<R: Runner> Batcher<R> {
+    pub fn new(runner: R, max_batch_size: Option<usize>) -> Self {
+        Self {
+            runner: Arc::new(runner),
+            max_batch_size: max_batch_size.map(|v| v.max(1)),
+            state: Arc::new(Mutex::new(BatcherState {
+                pending: Vec::new(),
+                running: false,
+            })),
+        }
+    }
+
+    pub async fn run(&self, input: R::Input) -> Result<R::Output> {
+        let (output_tx, output_rx) = oneshot::channel();
+        let start_running = {
+            let mut state = self.state.lock().unwrap();
+            state.pending.push((input, output_tx));
+            !std::mem::replace(&mut state.running, true)
+        };
+        if start_running {
+            // Calls of a batch may come from different updates. LLM usage of batches is recorded
+            // into the update of the call starting the run.
+            tokio::spawn(llm::in_current_update_scope(Self::run_pending(
+                self.runner.clone(),
+                self.state.clone(),
+                self.max_batch_size,
+            )));
+        }
+        Ok(output_rx.await?.std_result()?)
+    }
+
+    async fn run_pending(
+        runner: Arc<R>,
+        state: Arc<Mutex<BatcherState<R>>>,
+        max_batch_size: Option<usize>,
+    ) {
+        loop {
+            let batch = {
+                let mut state = state.lock().unwrap();
+                if state.pending.is_empty() {
+                    state.running = false;
+                    return;
+                }
+                let batch_size =
+                    max_batch_size.map_or(state.pending.len(), |v| v.min(state.pending.len()));
+                state.pending.drain(..batch_size).collect::<Vec<_>>()
+            };
+            let (inputs, output_txs): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+            if inputs.len() == 1 {
+                let result = Self::run_batch(&runner, inputs).await;
+                Self::send_outputs(output_txs, result);
+                continue;
+            }
+            match Self::run_batch(&runner, inputs.clone()).await {
+                Ok(outputs) => Self::send_outputs(output_txs, Ok(outputs)),
+                Err(_) => {
+                    // Retry the inputs one by one, so only the ones causing the error fail.
+                    for (input, output_tx) in inputs.into_iter().zip(output_txs) {
+                        let result = Self::run_batch(&runner, vec![input]).await;
+                        Self::send_outputs(vec![output_tx], result);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn run_batch(runner: &R, inputs: Vec<R::Input>) -> Result<Vec<R::Output>> {
+        let num_inputs = inputs.len();
+        let outputs = runner.run(inputs).await?;
+        if outputs.len() != num_inputs {
+            bail!(
+                "Batch runner returned {} outputs for {} inputs",
+                outputs.len(),
+                num_inputs
+            );
+        }
+        Ok(outputs)
+    }
+
+    fn send_outputs(output_txs: Vec<OutputSender<R>>, result: Result<Vec<R::Output>>) {
+        match result {
+            Ok(outputs) => {
+                for (output_tx, output) in output_txs.into_iter().zip(outputs) {
+                    let _ = output_tx.send(Ok(output));
+                }
+            }
+            Err(err) => {
+                let err = SharedError::new(err);
+                for output_tx in output_txs {
+                    let _ = output_tx.send(Err(err.clone()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestRunner {
+        batches: Mutex<Vec<Vec<i64>>>,
+        gate: tokio::sync::Semaphore,
+    }
+
+    #[async_trait]
+    impl Runner for Arc<TestRunner> {
+        type Input = i64;
+        type Output = i64;
+
+        async fn run(&self, inputs: Vec<i64>) -> Result<Vec<i64>> {
+            let _permit = self.gate.acquire().await?;
+            if inputs.contains(&-1) {
+                bail!("negative input");
+            }
+            self.batches.lock().unwrap().push(inputs.clone());
+            Ok(inputs.into_iter().map(|v| v * 10).collect())
+        }
+    }
+
+    fn new_runner() -> Arc<TestRunner> {
+        Arc::new(TestRunner {
+            batches: Mutex::new(Vec::new()),
+            gate: tokio::sync::Semaphore::new(0),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_pending_calls() {
+        let runner = new_runner();
+        let batcher = Arc::new(Batcher::new(runner.clone(), Some(2)));
+        let calls = (1..=5)
+            .map(|v| {
+                let batcher = batcher.clone();
+                tokio::spawn(async move { batcher.run(v).await.unwrap() })
+            })
+            .collect::<Vec<_>>();
+        tokio::task::yield_now().await;
+        runner.gate.add_permits(10);
+
+        let outputs = futures::future::try_join_all(calls).await.unwrap();
+        assert_eq!(outputs, vec![10, 20, 30, 40, 50]);
+        let batches = runner.batches.lock().unwrap();
+        assert!(batches.len() < 5);
+        assert!(batches.iter().all(|b| b.len() <= 2));
+        let mut inputs = batches.iter().flatten().copied().collect::<Vec<_>>();
+        inputs.sort();
+        assert_eq!(inputs, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_error_propagated_to_batch() {
+        let runner = new_runner();
+        runner.gate.add_permits(10);
+        let batcher = Batcher::new(runner.clone(), None);
+        assert!(batcher.run(-1).await.is_err());
+        assert_eq!(batcher.run(2).await.unwrap(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_error_isolated_to_failing_input() {
+        let runner = new_runner();
+        let batcher = Arc::new(Batcher::new(runner.clone(), None));
+        let calls = [1, -1, 3]
+            .into_iter()
+            .map(|v| {
+                let batcher = batcher.clone();
+                tokio::spawn(async move { batcher.run(v).await.ok() })
+            })
+            .collect::<Vec<_>>();
+        tokio::task::yield_now().await;
+        runner.gate.add_permits(10);
+
+        let outputs = futures::future::try_join_all(calls).await.unwrap();
+        assert_eq!(outputs, vec![Some(10), None, Some(30)]);
+    }
+}
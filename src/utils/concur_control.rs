@@ -0,0 +1,141 @@
+use crate::prelude::*;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Limits the number of rows being processed concurrently, and the total size of their data.
+/// Optionally chained with a parent controller, whose limits are shared with other controllers.
+pub struct ConcurrencyController {
+    inflight_rows_sem: Option<Arc<Semaphore>>,
+    inflight_bytes_sem: Option<Arc<Semaphore>>,
+    max_inflight_bytes: u32,
+    parent: Option<Arc<ConcurrencyController>>,
+}
+
+pub struct ConcurrencyControllerPermit {
+    controller: Arc<ConcurrencyController>,
+    _inflight_rows_permit: Option<OwnedSemaphorePermit>,
+    inflight_bytes_permit: Option<OwnedSemaphorePermit>,
+    parent_permit: Option<Box<ConcurrencyControllerPermit>>,
+}
+
+impl ConcurrencyController {
+    pub fn new(
+        options: &spec::ExecutionOptions,
+        parent: Option<Arc<ConcurrencyController>>,
+    ) -> Self {
+        // Permits for bytes are acquired as `u32`, so the limit cannot go beyond.
+        let max_inflight_bytes = options
+            .max_inflight_bytes
+            .map(|v| v.clamp(1, u32::MAX as usize) as u32);
+        Self {
+            inflight_rows_sem: options
+                .max_inflight_rows
+                .map(|v| Arc::new(Semaphore::new(v.max(1)))),
+            inflight_bytes_sem: max_inflight_bytes.map(|v| Arc::new(Semaphore::new(v as usize))),
+            max_inflight_bytes: max_inflight_bytes.unwrap_or(0),
+            parent,
+        }
+    }
+
+    /// Acquire a permit for a new row. Waits until the number of in-flight rows is within the limit.
+    /// The row is done when the permit is dropped.
+    pub fn acquire(self: &Arc<Self>) -> BoxFuture<'static, Result<ConcurrencyControllerPermit>> {
+        let controller = self.clone();
+        async move {
+            let inflight_rows_permit = match &controller.inflight_rows_sem {
+                Some(sem) => Some(sem.clone().acquire_owned().await?),
+                None => None,
+            };
+            let parent_permit = match &controller.parent {
+                Some(parent) => Some(Box::new(parent.acquire().await?)),
+                None => None,
+            };
+            Ok(ConcurrencyControllerPermit {
+                controller,
+                _inflight_rows_permit: inflight_rows_permit,
+                inflight_bytes_permit: None,
+                parent_permit,
+            })
+        }
+        .boxed()
+    }
+}
+
+impl ConcurrencyControllerPermit {
+    /// Account the size of data for the row. Waits until the total in-flight bytes are within the limit.
+    /// A single row larger than the limit is allowed, when no other row is in flight.
+    pub fn acquire_bytes(&mut self, bytes: usize) -> BoxFuture<'_, Result<()>> {
+        async move {
+            if let Some(sem) = &self.controller.inflight_bytes_sem {
+                let bytes = bytes.clamp(1, self.controller.max_inflight_bytes as usize) as u32;
+                self.inflight_bytes_permit = Some(sem.clone().acquire_many_owned(bytes).await?);
+            }
+            if let Some(parent_permit) = &mut self.parent_permit {
+                parent_permit.acquire_bytes(bytes).await?;
+            }
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn options(
+        max_inflight_rows: Option<usize>,
+        max_inflight_bytes: Option<usize>,
+    ) -> spec::ExecutionOptions {
+        spec::ExecutionOptions {
+            max_inflight_rows,
+            max_inflight_bytes,
+        }
+    }
+
+    async fn is_blocked<T>(fut: impl Future<Output = T>) -> bool {
+        tokio::time::timeout(Duration::from_millis(50), fut)
+            .await
+            .is_err()
+    }
+
+    #[tokio::test]
+    async fn test_inflight_rows_limit() {
+        let controller = Arc::new(ConcurrencyController::new(&options(Some(2), None), None));
+        let permit1 = controller.acquire().await.unwrap();
+        let _permit2 = controller.acquire().await.unwrap();
+        assert!(is_blocked(controller.acquire()).await);
+        drop(permit1);
+        assert!(!is_blocked(controller.acquire()).await);
+    }
+
+    #[tokio::test]
+    async fn test_inflight_bytes_limit() {
+        let controller = Arc::new(ConcurrencyController::new(&options(None, Some(100)), None));
+        let mut permit1 = controller.acquire().await.unwrap();
+        permit1.acquire_bytes(60).await.unwrap();
+        let mut permit2 = controller.acquire().await.unwrap();
+        assert!(is_blocked(permit2.acquire_bytes(60)).await);
+        drop(permit1);
+        // Rows larger than the limit are allowed to proceed alone.
+        assert!(!is_blocked(permit2.acquire_bytes(1000)).await);
+    }
+
+    #[tokio::test]
+    async fn test_parent_limit() {
+        let parent = Arc::new(ConcurrencyController::new(&options(Some(1), None), None));
+        let child1 = Arc::new(ConcurrencyController::new(
+            &options(None, None),
+            Some(parent.clone()),
+        ));
+        let child2 = Arc::new(ConcurrencyController::new(
+            &options(None, None),
+            Some(parent.clone()),
+        ));
+        let permit = child1.acquire().await.unwrap();
+        assert!(is_blocked(child2.acquire()).await);
+        drop(permit);
+        assert!(!is_blocked(child2.acquire()).await);
+    }
+}
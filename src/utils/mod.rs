@@ -1,3 +1,5 @@
+pub mod batching;
+pub mod concur_control;
 pub mod db;
 pub mod fingerprint;
 pub mod immutable;